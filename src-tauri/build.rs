@@ -1,3 +1,23 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 fn main() {
+  // Embed build metadata for get_app_info / About
+  let commit = Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|out| out.status.success())
+    .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    .unwrap_or_else(|| "unknown".to_string());
+  let built_at = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0);
+  println!("cargo:rustc-env=CC_USAGE_GIT_COMMIT={}", commit);
+  println!("cargo:rustc-env=CC_USAGE_BUILD_TIMESTAMP={}", built_at);
+  println!("cargo:rerun-if-changed=../.git/HEAD");
+  println!("cargo:rerun-if-changed=../.git/refs/heads");
+
   tauri_build::build()
 }
@@ -0,0 +1,559 @@
+// Usage notifications driven by consecutive readings: "Session at 85%" once a
+// reading passes one of the configured percentages, "Session limit reached"
+// at 100% and "Claude is usable again" once it resets, "Session budget reset"
+// when a window starts over, "Session is ahead of pace" when the pace
+// indicator turns orange or red, and "Weekly budget running out early" when the
+// burn rate projects 100% well before the weekly reset. With escalation on, a
+// limit at 95%+ that keeps climbing repeats its critical alert until it's
+// acknowledged from the menu. A window's usage only climbs until it resets,
+// so comparing against the previous reading fires the first two once per
+// window without keeping any extra state; pace can flap around a boundary, so
+// it remembers which window it last alerted for, as does the projection.
+use std::collections::HashMap;
+
+use crate::locale::{text, tr, Msg};
+use crate::notify::{Alert, Severity};
+use crate::{
+    became_usable, trend, format_hours_minutes, format_time_remaining, locale, pace, pace_indicator_at, parse_reading_timestamp,
+    period_id, resolve_reset, MenuMetric, Settings, UsageData, INDICATORS_BY_SEVERITY,
+};
+
+pub(crate) const DEFAULT_THRESHOLDS: [i32; 3] = [70, 85, 95];
+
+// A drop this large means the window reset even when the reset time can't be parsed
+const SHARP_DROP_POINTS: i32 = 20;
+
+// Threshold alerts from here up are critical
+pub(crate) const CRITICAL_PERCENT: i32 = 95;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Crossing {
+    pub(crate) metric: MenuMetric,
+    pub(crate) threshold: i32,
+    pub(crate) percent: i32,
+}
+
+// The highest threshold passed between two readings. Without a previous
+// reading (first fetch after launch) nothing counts as crossed.
+pub(crate) fn crossed_threshold(previous: Option<i32>, current: Option<i32>, thresholds: &[i32]) -> Option<i32> {
+    let (prev, cur) = (previous?, current?);
+    thresholds.iter().copied().filter(|&t| prev < t && t <= cur).max()
+}
+
+// Thresholds for a metric, or None when its alerts are off. Each metric's
+// `<metric>_alerts` flag defaults to on, its thresholds to 70/85/95.
+pub(crate) fn thresholds_for(settings: &Settings, metric: MenuMetric) -> Option<Vec<i32>> {
+    let (enabled, thresholds) = match metric {
+        MenuMetric::Session => (settings.session_alerts, &settings.session_alert_thresholds),
+        MenuMetric::Weekly => (settings.weekly_alerts, &settings.weekly_alert_thresholds),
+        MenuMetric::Sonnet => (settings.sonnet_alerts, &settings.sonnet_alert_thresholds),
+        MenuMetric::Opus => (settings.opus_alerts, &settings.opus_alert_thresholds),
+    };
+    enabled
+        .unwrap_or(true)
+        .then(|| thresholds.clone().unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec()))
+}
+
+pub(crate) fn crossings(previous: &UsageData, current: &UsageData, settings: &Settings) -> Vec<Crossing> {
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let thresholds = thresholds_for(settings, metric)?;
+            let percent = metric.item(current).percent?;
+            let threshold = crossed_threshold(metric.item(previous).percent, Some(percent), &thresholds)?;
+            Some(Crossing { metric, threshold, percent })
+        })
+        .collect()
+}
+
+// ("Session at 85%", "87% used · 2h left")
+pub(crate) fn notification_text(crossing: &Crossing, usage: &UsageData) -> (String, String) {
+    let title = tr(Msg::ThresholdTitle, &[&crossing.metric.label(), &crossing.threshold]);
+    let used = tr(Msg::PercentUsed, &[&crossing.percent]);
+    let body = match crossing.metric.item(usage).resets.as_deref() {
+        Some(resets) => format!("{} · {}", used, format_time_remaining(resets)),
+        None => used,
+    };
+    (title, body)
+}
+
+// Usage went down and either the previous reading's reset time has passed by
+// the current reading or the drop is too big to be noise
+pub(crate) fn window_reset(metric: MenuMetric, previous: &UsageData, current: &UsageData) -> bool {
+    let (Some(prev), Some(cur)) = (metric.item(previous).percent, metric.item(current).percent) else {
+        return false;
+    };
+    if cur >= prev {
+        return false;
+    }
+    let reset_passed = || {
+        let prev_read = parse_reading_timestamp(previous.timestamp.as_deref()?)?;
+        let cur_read = parse_reading_timestamp(current.timestamp.as_deref()?)?;
+        let reset_at = resolve_reset(metric.item(previous).resets.as_deref(), prev_read)?;
+        Some(reset_at <= cur_read)
+    };
+    prev - cur >= SHARP_DROP_POINTS || reset_passed().unwrap_or(false)
+}
+
+// The limits that block Claude when they hit 100%
+const LIMIT_METRICS: [MenuMetric; 2] = [MenuMetric::Session, MenuMetric::Weekly];
+
+fn at_limit(metric: MenuMetric, usage: &UsageData) -> bool {
+    metric.item(usage).percent.is_some_and(|p| p >= 100)
+}
+
+fn below_limit(metric: MenuMetric, usage: &UsageData) -> bool {
+    metric.item(usage).percent.is_some_and(|p| p < 100)
+}
+
+// Limits that reached 100% between the readings (`notify_on_limit`, default on)
+pub(crate) fn limit_hits(previous: &UsageData, current: &UsageData, settings: &Settings) -> Vec<MenuMetric> {
+    if !settings.notify_on_limit.unwrap_or(true) {
+        return Vec::new();
+    }
+    LIMIT_METRICS
+        .into_iter()
+        .filter(|&m| below_limit(m, previous) && at_limit(m, current))
+        .collect()
+}
+
+// "Session limit reached" / "Usable again in 1h 47m (at 15:00)", counted from
+// when the reading was taken
+pub(crate) fn limit_hit_alert(metric: MenuMetric, usage: &UsageData) -> Alert {
+    let resets = metric.item(usage).resets.as_deref();
+    let read_at = usage
+        .timestamp
+        .as_deref()
+        .and_then(parse_reading_timestamp)
+        .unwrap_or_else(chrono::Local::now);
+    let body = match resolve_reset(resets, read_at) {
+        Some(reset_at) => tr(
+            Msg::LimitHitBody,
+            &[
+                &format_hours_minutes(reset_at.signed_duration_since(read_at)),
+                &reset_at.format(locale::clock(false)),
+            ],
+        ),
+        None => tr(Msg::Resets, &[&resets.unwrap_or("--")]),
+    };
+    Alert::new(tr(Msg::LimitHitTitle, &[&metric.label()]), body, Severity::Critical)
+}
+
+// Limits that came back from 100% between the readings, when nothing is left
+// blocking and `notify_when_usable` (default on) allows it
+pub(crate) fn became_usable_metrics(previous: &UsageData, current: &UsageData, settings: &Settings) -> Vec<MenuMetric> {
+    if !settings.notify_when_usable.unwrap_or(true) || LIMIT_METRICS.iter().any(|&m| at_limit(m, current)) {
+        return Vec::new();
+    }
+    LIMIT_METRICS
+        .into_iter()
+        .filter(|&m| became_usable(m.item(previous).percent, m.item(current).percent))
+        .collect()
+}
+
+// The follow-up to a limit alert: the refresh loop fetches right after the
+// reset, and that reading confirms it
+pub(crate) fn usable_again_alert(metric: MenuMetric, usage: &UsageData) -> Alert {
+    let percent = metric.item(usage).percent.unwrap_or(0);
+    Alert::new(
+        text(Msg::UsableAgainTitle),
+        tr(Msg::UsableAgainBody, &[&metric.label(), &percent]),
+        Severity::Info,
+    )
+}
+
+// Session and weekly windows that reset between the readings. A limit coming
+// back from 100% is left to the "usable again" notification.
+pub(crate) fn resets(previous: &UsageData, current: &UsageData, settings: &Settings) -> Vec<MenuMetric> {
+    if !settings.notify_on_reset.unwrap_or(false) {
+        return Vec::new();
+    }
+    let usable = became_usable_metrics(previous, current, settings);
+    LIMIT_METRICS
+        .into_iter()
+        .filter(|m| !usable.contains(m))
+        .filter(|&m| window_reset(m, previous, current))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PaceChange {
+    pub(crate) metric: MenuMetric,
+    pub(crate) indicator: &'static str,
+    pub(crate) percent: i32,
+    pub(crate) elapsed: i32,
+    // period_id of the window, so it's only announced once
+    pub(crate) window: String,
+}
+
+fn severity(indicator: &str) -> usize {
+    INDICATORS_BY_SEVERITY.iter().position(|i| *i == indicator).unwrap_or(0)
+}
+
+// A metric whose pace went from green/yellow to orange/red between the two
+// readings, each judged at the time it was read. Needs both timestamps and a
+// parseable reset.
+pub(crate) fn pace_degraded(metric: MenuMetric, previous: &UsageData, current: &UsageData) -> Option<PaceChange> {
+    let reading = |usage: &UsageData| {
+        let read_at = parse_reading_timestamp(usage.timestamp.as_deref()?)?;
+        let item = metric.item(usage);
+        let reset_at = resolve_reset(item.resets.as_deref(), read_at)?;
+        Some((pace_indicator_at(item.percent?, Some(reset_at), metric, read_at), reset_at, read_at))
+    };
+    let (before, _, _) = reading(previous)?;
+    let (after, reset_at, read_at) = reading(current)?;
+    (severity(before) <= 1 && severity(after) >= 2).then(|| PaceChange {
+        metric,
+        indicator: after,
+        percent: metric.item(current).percent.unwrap_or(0),
+        elapsed: pace::elapsed(metric, Some(reset_at), read_at).clamp(0, 100),
+        window: period_id(reset_at, metric.period_hours()),
+    })
+}
+
+// Pace changes not yet announced for their window (`notify_on_pace`, default on)
+pub(crate) fn pace_changes(
+    previous: &UsageData,
+    current: &UsageData,
+    settings: &Settings,
+    alerted: &HashMap<MenuMetric, String>,
+) -> Vec<PaceChange> {
+    if !settings.notify_on_pace.unwrap_or(true) {
+        return Vec::new();
+    }
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|m| pace_degraded(m, previous, current))
+        .filter(|change| alerted.get(&change.metric) != Some(&change.window))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EarlyExhaustion {
+    pub(crate) hours_early: i64,
+    // period_id of the weekly window
+    pub(crate) window: String,
+}
+
+// The weekly budget projected (from `rate`, %/hour) to hit 100% more than
+// `weekly_early_warning_hours` before it resets. Off while the setting is unset.
+pub(crate) fn weekly_early_exhaustion(usage: &UsageData, rate: Option<f64>, settings: &Settings) -> Option<EarlyExhaustion> {
+    let min_hours = settings.weekly_early_warning_hours?;
+    let read_at = parse_reading_timestamp(usage.timestamp.as_deref()?)?;
+    let item = &usage.weekly_all;
+    let reset_at = resolve_reset(item.resets.as_deref(), read_at)?;
+    let runs_out = trend::projected_exhaustion(item.percent?, rate?, read_at)?;
+    let hours_early = reset_at.signed_duration_since(runs_out).num_hours();
+    (hours_early > min_hours).then(|| EarlyExhaustion {
+        hours_early,
+        window: period_id(reset_at, MenuMetric::Weekly.period_hours()),
+    })
+}
+
+// "Weekly budget running out early" / "You'll run out ~36h early at this
+// pace", once per weekly window (`warned` is AppState's `early_warned`)
+pub(crate) fn early_warning(
+    usage: &UsageData,
+    rate: Option<f64>,
+    settings: &Settings,
+    warned: &mut Option<String>,
+) -> Option<Alert> {
+    let early = weekly_early_exhaustion(usage, rate, settings)?;
+    if warned.as_ref() == Some(&early.window) {
+        return None;
+    }
+    *warned = Some(early.window);
+    Some(Alert::new(
+        text(Msg::EarlyWarningTitle),
+        tr(Msg::EarlyWarningBody, &[&early.hours_early]),
+        Severity::Warning,
+    ))
+}
+
+// Repeating critical alerts (`escalate_every_mins`, unset: off). Starts once a
+// limit is at 95%+, with the threshold alert as the first notification, and
+// ends when every limit is back under 95%.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Escalation {
+    pub(crate) last_sent: Option<chrono::DateTime<chrono::Local>>,
+    pub(crate) acknowledged: bool,
+}
+
+impl Escalation {
+    // Shows "Acknowledge Alert" in the menu
+    pub(crate) fn pending(&self) -> bool {
+        self.last_sent.is_some() && !self.acknowledged
+    }
+}
+
+fn critical(metric: MenuMetric, usage: &UsageData) -> Option<i32> {
+    metric.item(usage).percent.filter(|&p| p >= CRITICAL_PERCENT)
+}
+
+// The highest critical limit that went up between the readings: still working
+fn climbing_critical(previous: &UsageData, current: &UsageData) -> Option<(MenuMetric, i32)> {
+    LIMIT_METRICS
+        .into_iter()
+        .filter_map(|m| Some((m, critical(m, current)?)))
+        .filter(|&(m, percent)| metric_rose(m.item(previous).percent, percent))
+        .max_by_key(|&(_, percent)| percent)
+}
+
+fn metric_rose(previous: Option<i32>, current: i32) -> bool {
+    previous.is_some_and(|prev| current > prev)
+}
+
+// "Session still at 97%" every `escalate_every_mins` while readings climb
+pub(crate) fn escalate(
+    previous: &UsageData,
+    current: &UsageData,
+    settings: &Settings,
+    escalation: &mut Escalation,
+    now: chrono::DateTime<chrono::Local>,
+) -> Option<Alert> {
+    let every = settings.escalate_every_mins?;
+    if !LIMIT_METRICS.iter().any(|&m| critical(m, current).is_some()) {
+        *escalation = Escalation::default();
+        return None;
+    }
+    let Some(last_sent) = escalation.last_sent else {
+        escalation.last_sent = Some(now);
+        return None;
+    };
+    if escalation.acknowledged || now.signed_duration_since(last_sent) < chrono::Duration::minutes(every) {
+        return None;
+    }
+    let (metric, percent) = climbing_critical(previous, current)?;
+    escalation.last_sent = Some(now);
+    Some(Alert::new(
+        tr(Msg::EscalationTitle, &[&metric.label(), &percent]),
+        text(Msg::EscalationBody),
+        Severity::Critical,
+    ))
+}
+
+// Every alert between the outgoing and incoming readings;
+// the caller checks `notify::alerts_allowed` and sends them. `pace_alerted` is
+// AppState's record of the window each metric last had a pace alert in.
+pub(crate) fn changes(
+    previous: &UsageData,
+    current: &UsageData,
+    settings: &Settings,
+    pace_alerted: &mut HashMap<MenuMetric, String>,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    // One "usable again" is enough when both limits reset together
+    if let Some(&metric) = became_usable_metrics(previous, current, settings).first() {
+        alerts.push(usable_again_alert(metric, current));
+    }
+    for metric in limit_hits(previous, current, settings) {
+        alerts.push(limit_hit_alert(metric, current));
+    }
+    for change in pace_changes(previous, current, settings, pace_alerted) {
+        let severity = if change.indicator == "🔴" {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        };
+        alerts.push(Alert::new(
+            tr(Msg::PaceTitle, &[&change.indicator, &change.metric.label()]),
+            tr(Msg::PaceBody, &[&change.percent, &change.elapsed]),
+            severity,
+        ));
+        pace_alerted.insert(change.metric, change.window);
+    }
+    for metric in resets(previous, current, settings) {
+        let percent = metric.item(current).percent.unwrap_or(0);
+        alerts.push(Alert::new(
+            tr(Msg::ResetTitle, &[&metric.label()]),
+            tr(Msg::ResetBody, &[&percent]),
+            Severity::Info,
+        ));
+    }
+    for crossing in crossings(previous, current, settings) {
+        let (title, body) = notification_text(&crossing, current);
+        let severity = if crossing.threshold >= CRITICAL_PERCENT {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        };
+        alerts.push(Alert::new(title, body, severity));
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UsageItem;
+
+    fn usage(session: i32, weekly: i32) -> UsageData {
+        UsageData {
+            session: UsageItem {
+                percent: Some(session),
+                resets: None,
+            },
+            weekly_all: UsageItem {
+                percent: Some(weekly),
+                resets: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_crossed_threshold_picks_highest_passed() {
+        assert_eq!(crossed_threshold(Some(60), Some(90), &DEFAULT_THRESHOLDS), Some(85));
+        assert_eq!(crossed_threshold(Some(70), Some(80), &DEFAULT_THRESHOLDS), None);
+        assert_eq!(crossed_threshold(Some(69), Some(70), &DEFAULT_THRESHOLDS), Some(70));
+        // A reset drops usage; that's not a crossing
+        assert_eq!(crossed_threshold(Some(96), Some(4), &DEFAULT_THRESHOLDS), None);
+        assert_eq!(crossed_threshold(None, Some(99), &DEFAULT_THRESHOLDS), None);
+    }
+
+    #[test]
+    fn test_crossings_respect_per_metric_settings() {
+        let settings = Settings {
+            weekly_alerts: Some(false),
+            session_alert_thresholds: Some(vec![50]),
+            sonnet_alerts: Some(false),
+            ..Default::default()
+        };
+        let mut previous = usage(40, 60);
+        previous.weekly_sonnet.percent = Some(60);
+        let mut current = usage(55, 90);
+        current.weekly_sonnet.percent = Some(90);
+        let found = crossings(&previous, &current, &settings);
+        assert_eq!(
+            found,
+            vec![Crossing {
+                metric: MenuMetric::Session,
+                threshold: 50,
+                percent: 55
+            }]
+        );
+        let (title, body) = notification_text(&found[0], &usage(55, 90));
+        assert_eq!((title.as_str(), body.as_str()), ("Session at 50%", "55% used"));
+
+        let opus = Settings {
+            opus_alert_thresholds: Some(vec![60]),
+            ..Default::default()
+        };
+        assert_eq!(thresholds_for(&opus, MenuMetric::Opus), Some(vec![60]));
+        assert_eq!(thresholds_for(&opus, MenuMetric::Sonnet), Some(DEFAULT_THRESHOLDS.to_vec()));
+    }
+
+    #[test]
+    fn test_window_reset_by_time_or_sharp_drop() {
+        let mut previous = usage(40, 60);
+        previous.timestamp = Some("2026-01-28T14:50:00".to_string());
+        previous.session.resets = Some("3pm".to_string());
+        let mut current = usage(35, 60);
+        current.timestamp = Some("2026-01-28T14:55:00".to_string());
+        // Small drop before the reset time: noise
+        assert!(!window_reset(MenuMetric::Session, &previous, &current));
+        current.timestamp = Some("2026-01-28T15:05:00".to_string());
+        assert!(window_reset(MenuMetric::Session, &previous, &current));
+        // Weekly didn't drop
+        assert!(!window_reset(MenuMetric::Weekly, &previous, &current));
+        assert!(window_reset(MenuMetric::Weekly, &usage(0, 60), &usage(0, 2)));
+
+        let settings = Settings {
+            notify_on_reset: Some(true),
+            ..Default::default()
+        };
+        // Back from 100% is already announced as "usable again"
+        assert_eq!(resets(&usage(100, 60), &usage(0, 2), &settings), vec![MenuMetric::Weekly]);
+        assert!(resets(&usage(100, 60), &usage(0, 2), &Settings::default()).is_empty());
+    }
+
+    #[test]
+    fn test_limit_hit_then_usable_again() {
+        let settings = Settings::default();
+        let mut capped = usage(100, 60);
+        capped.timestamp = Some("2026-01-28T13:13:00".to_string());
+        capped.session.resets = Some("3pm".to_string());
+        assert_eq!(limit_hits(&usage(97, 60), &capped, &settings), vec![MenuMetric::Session]);
+        assert!(limit_hits(&capped, &capped, &settings).is_empty());
+        let alert = limit_hit_alert(MenuMetric::Session, &capped);
+        assert_eq!(alert.title, "Session limit reached");
+        assert_eq!(alert.body, "Usable again in 1h 47m (at 15:00)");
+
+        assert_eq!(became_usable_metrics(&capped, &usage(0, 60), &settings), vec![MenuMetric::Session]);
+        // Still blocked by the weekly limit: not usable yet
+        assert!(became_usable_metrics(&usage(100, 100), &usage(0, 100), &settings).is_empty());
+        let alert = usable_again_alert(MenuMetric::Weekly, &usage(100, 3));
+        assert_eq!(alert.body, "Weekly (all) usage is back to 3%");
+    }
+
+    #[test]
+    fn test_pace_degrade_fires_once_per_window() {
+        // Session resets at 3pm, so at 1pm half of the 4h window has gone
+        let reading = |ts: &str, percent: i32| UsageData {
+            timestamp: Some(ts.to_string()),
+            session: UsageItem {
+                percent: Some(percent),
+                resets: Some("3pm".to_string()),
+            },
+            ..Default::default()
+        };
+        let previous = reading("2026-01-28T12:50:00", 55);
+        let current = reading("2026-01-28T13:00:00", 62);
+        let change = pace_degraded(MenuMetric::Session, &previous, &current).unwrap();
+        assert_eq!((change.indicator, change.percent, change.elapsed), ("🟠", 62, 50));
+        // Already orange before: no change
+        assert_eq!(pace_degraded(MenuMetric::Session, &current, &reading("2026-01-28T13:05:00", 64)), None);
+
+        let mut alerted = HashMap::new();
+        assert_eq!(pace_changes(&previous, &current, &Settings::default(), &alerted).len(), 1);
+        alerted.insert(MenuMetric::Session, change.window);
+        assert!(pace_changes(&previous, &current, &Settings::default(), &alerted).is_empty());
+    }
+
+    #[test]
+    fn test_escalation_repeats_until_acknowledged() {
+        let settings = Settings {
+            escalate_every_mins: Some(15),
+            ..Default::default()
+        };
+        let start = chrono::Local::now();
+        let at = |mins: i64| start + chrono::Duration::minutes(mins);
+        let mut escalation = Escalation::default();
+        // The 95% threshold alert is the first one
+        assert_eq!(escalate(&usage(90, 50), &usage(96, 50), &settings, &mut escalation, at(0)), None);
+        assert!(escalation.pending());
+        assert_eq!(escalate(&usage(96, 50), &usage(97, 50), &settings, &mut escalation, at(10)), None);
+        let alert = escalate(&usage(97, 50), &usage(98, 50), &settings, &mut escalation, at(15)).unwrap();
+        assert_eq!(alert.title, "Session still at 98%");
+        // Not climbing: no repeat
+        assert_eq!(escalate(&usage(98, 50), &usage(98, 50), &settings, &mut escalation, at(40)), None);
+
+        escalation.acknowledged = true;
+        assert_eq!(escalate(&usage(98, 50), &usage(99, 50), &settings, &mut escalation, at(60)), None);
+        // Back under 95% ends it, acknowledgement included
+        assert_eq!(escalate(&usage(99, 50), &usage(3, 50), &settings, &mut escalation, at(70)), None);
+        assert_eq!(escalation, Escalation::default());
+    }
+
+    #[test]
+    fn test_weekly_early_warning_once_per_window() {
+        // 40% used at noon Wednesday at 2%/hour runs out Thursday at 6pm,
+        // 36h before the Saturday 6am reset
+        let mut current = usage(10, 40);
+        current.timestamp = Some("2026-01-28T12:00:00".to_string());
+        current.weekly_all.resets = Some("Jan 31 at 6am".to_string());
+        let settings = Settings {
+            weekly_early_warning_hours: Some(24),
+            ..Default::default()
+        };
+        let early = weekly_early_exhaustion(&current, Some(2.0), &settings).unwrap();
+        assert_eq!(early.hours_early, 36);
+        assert_eq!(weekly_early_exhaustion(&current, Some(2.0), &Settings::default()), None);
+        assert_eq!(weekly_early_exhaustion(&current, Some(0.5), &settings), None);
+
+        let mut warned = None;
+        let alert = early_warning(&current, Some(2.0), &settings, &mut warned).unwrap();
+        assert_eq!(alert.body, "You'll run out ~36h early at this pace");
+        assert_eq!(early_warning(&current, Some(2.0), &settings, &mut warned), None);
+    }
+}
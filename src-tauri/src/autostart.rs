@@ -0,0 +1,38 @@
+// Start at Login, from the tray menu or Settings, through tauri-plugin-autostart:
+// a LaunchAgent on macOS, an XDG autostart entry on Linux, and a registry Run
+// key on Windows, each pointing at the running executable. The login item is
+// the setting; there's nothing in the settings file.
+use std::sync::{Arc, Mutex};
+
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::{ensure_not_guest, update_tray, AppState};
+
+pub(crate) fn is_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.autolaunch().is_enabled().unwrap_or(false)
+}
+
+pub(crate) fn set_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let result = if enabled { autolaunch.enable() } else { autolaunch.disable() };
+    result.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub(crate) fn get_launch_at_login(app: tauri::AppHandle) -> bool {
+    is_enabled(&app)
+}
+
+#[tauri::command]
+pub(crate) fn set_launch_at_login(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    let state = state.lock().unwrap();
+    ensure_not_guest(&state)?;
+    set_enabled(&app, enabled)?;
+    update_tray(&app, &state);
+    Ok(())
+}
@@ -0,0 +1,190 @@
+// Away mode: polling and notifications are suspended until a return date, and
+// the gap is recorded as an annotation so charts don't show a flat line or a
+// pile of stale errors afterwards
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{init_db, load_saved_settings, save_settings, transcripts, AppState};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Annotation {
+    kind: String,
+    start: String,
+    end: String,
+    note: Option<String>,
+}
+
+pub(crate) fn insert_annotation(
+    conn: &Connection,
+    kind: &str,
+    start: &str,
+    end: &str,
+    note: Option<&str>,
+) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO annotations (kind, start, end, note) VALUES (?1, ?2, ?3, ?4)",
+        params![kind, start, end, note],
+    )?;
+    Ok(())
+}
+
+// Annotations overlapping the range starting at `cutoff`
+pub(crate) fn query_annotations(conn: &Connection, cutoff: &str) -> Vec<Annotation> {
+    conn.prepare(
+        "SELECT kind, start, end, note FROM annotations
+         WHERE end >= ?1
+         ORDER BY start ASC",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![cutoff], |row| {
+            Ok(Annotation {
+                kind: row.get(0)?,
+                start: row.get(1)?,
+                end: row.get(2)?,
+                note: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .unwrap_or_default()
+}
+
+pub(crate) fn is_away(state: &AppState) -> bool {
+    state
+        .away_until
+        .is_some_and(|until| chrono::Local::now() < until)
+}
+
+// Seconds until the return date, while away
+pub(crate) fn secs_until_return(state: &AppState) -> Option<u64> {
+    let until = state.away_until?;
+    let remaining = until.signed_duration_since(chrono::Local::now()).num_seconds();
+    (remaining > 0).then_some(remaining as u64)
+}
+
+fn persist(state: &AppState) {
+    let mut settings = load_saved_settings();
+    settings.away_since = state.away_since.map(|t| t.format(TIMESTAMP_FORMAT).to_string());
+    settings.away_until = state.away_until.map(|t| t.format(TIMESTAMP_FORMAT).to_string());
+    save_settings(&settings);
+}
+
+pub(crate) fn parse_stored_time(value: Option<&str>) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    let naive = chrono::NaiveDateTime::parse_from_str(value?, TIMESTAMP_FORMAT).ok()?;
+    chrono::Local.from_local_datetime(&naive).earliest()
+}
+
+pub(crate) fn start_away(state: &mut AppState, until: chrono::DateTime<chrono::Local>) {
+    state.away_since = Some(chrono::Local::now());
+    state.away_until = Some(until);
+    persist(state);
+}
+
+// Start of the given day ("away until Oct 28" means back on the 28th)
+pub(crate) fn parse_return_date(input: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    let date = chrono::NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d").ok()?;
+    let until = chrono::Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()?;
+    (until > chrono::Local::now()).then_some(until)
+}
+
+// What the transcripts say happened while away. Percentages can't be
+// recovered after the fact, but activity can.
+fn summarize_transcripts(entries: &[transcripts::TranscriptEntry]) -> String {
+    if entries.is_empty() {
+        return "No Claude Code activity in local transcripts".to_string();
+    }
+    let sessions: HashSet<_> = entries.iter().filter_map(|e| e.session_id.as_deref()).collect();
+    let output_tokens: u64 = entries.iter().map(|e| e.output_tokens).sum();
+    format!(
+        "{} responses across {} sessions ({} output tokens) in local transcripts",
+        entries.len(),
+        sessions.len(),
+        output_tokens
+    )
+}
+
+// Close out the away period now: annotate it, clear errors that piled up
+// before leaving, and resume normal polling
+pub(crate) fn end_away(state: &Arc<Mutex<AppState>>) {
+    let since = {
+        let mut state = state.lock().unwrap();
+        let since = state.away_since.take();
+        state.away_until = None;
+        state.last_error = None;
+        state.consecutive_errors = 0;
+        persist(&state);
+        since
+    };
+
+    let Some(since) = since else {
+        return;
+    };
+    let now = chrono::Local::now();
+    let note = summarize_transcripts(&transcripts::read_entries(since, now));
+    if let Ok(conn) = init_db() {
+        let _ = insert_annotation(
+            &conn,
+            "away",
+            &since.format(TIMESTAMP_FORMAT).to_string(),
+            &now.format(TIMESTAMP_FORMAT).to_string(),
+            Some(&note),
+        );
+    }
+}
+
+#[tauri::command]
+pub(crate) fn get_annotations(days: i32) -> Result<Vec<Annotation>, String> {
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
+    Ok(query_annotations(&conn, &cutoff.format(TIMESTAMP_FORMAT).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_annotations_overlapping_range() {
+        let db_path = std::env::temp_dir().join(format!("test_db_annotations_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+
+        insert_annotation(&conn, "away", "2026-01-01T00:00:00", "2026-01-10T00:00:00", None).unwrap();
+        insert_annotation(&conn, "away", "2026-01-20T00:00:00", "2026-02-02T00:00:00", Some("x")).unwrap();
+
+        let found = query_annotations(&conn, "2026-02-01T00:00:00");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].start, "2026-01-20T00:00:00");
+        assert_eq!(found[0].note.as_deref(), Some("x"));
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_is_away_until_return_date() {
+        let mut state = AppState::default();
+        assert!(!is_away(&state));
+        state.away_until = Some(chrono::Local::now() + chrono::Duration::days(3));
+        assert!(is_away(&state));
+        assert!(secs_until_return(&state).is_some());
+        state.away_until = Some(chrono::Local::now() - chrono::Duration::minutes(1));
+        assert!(!is_away(&state));
+        assert!(secs_until_return(&state).is_none());
+    }
+
+    #[test]
+    fn test_parse_return_date() {
+        let future = (chrono::Local::now() + chrono::Duration::days(10)).format("%Y-%m-%d").to_string();
+        assert!(parse_return_date(&future).is_some());
+        assert!(parse_return_date("2001-01-01").is_none());
+        assert!(parse_return_date("next week").is_none());
+    }
+}
@@ -0,0 +1,154 @@
+// Backup and restore of the history database through SQLite's online backup
+// API, which copies a consistent snapshot even while the refresh loop writes
+use rusqlite::{backup::Backup, Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    choose_save_path, encryption, ensure_not_guest, get_db_path, init_db, init_schema, notify, AppState, MIGRATIONS,
+};
+use crate::locale::{text, Msg};
+
+pub(crate) const PRE_RESTORE_FILE: &str = "cc-usage-pre-restore.db";
+
+pub(crate) fn backup_to(conn: &Connection, path: &Path) -> Result<(), String> {
+    let result = Connection::open(path).and_then(|mut dst| {
+        encryption::apply_key(&dst)?;
+        Backup::new(conn, &mut dst)?.run_to_completion(100, std::time::Duration::from_millis(10), None)
+    });
+    result.map_err(|e| format!("Backup to {} failed: {}", path.display(), e))
+}
+
+// Refuse files that aren't a cc-usage database, or that come from a newer
+// version of the app with migrations this one doesn't know
+fn validate_backup(path: &Path) -> Result<(), String> {
+    let src = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    encryption::apply_key(&src).map_err(|e| e.to_string())?;
+    let has_history: bool = src
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'usage_history'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("{} is not a SQLite database", path.display()))?;
+    if !has_history {
+        return Err(format!("{} is not a Claude Usage backup", path.display()));
+    }
+    let version: usize = src
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if version > MIGRATIONS.len() {
+        return Err("Backup was made by a newer version of Claude Usage".to_string());
+    }
+    Ok(())
+}
+
+// Replace the contents of `conn` with the backup, then bring an older backup's
+// schema up to date
+pub(crate) fn restore_into(conn: &mut Connection, path: &Path) -> Result<(), String> {
+    validate_backup(path)?;
+    let src = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    encryption::apply_key(&src).map_err(|e| e.to_string())?;
+    Backup::new(&src, conn)
+        .and_then(|restore| restore.run_to_completion(100, std::time::Duration::from_millis(10), None))
+        .map_err(|e| format!("Restore from {} failed: {}", path.display(), e))?;
+    init_schema(conn).map_err(|e| format!("Failed to migrate restored database: {}", e))
+}
+
+fn pre_restore_path() -> PathBuf {
+    get_db_path().with_file_name(PRE_RESTORE_FILE)
+}
+
+// Tray menu "Back Up Data...": dialog, then a notification with the result
+pub(crate) fn backup_with_dialog() -> Result<Option<PathBuf>, String> {
+    let default_name = format!("cc-usage-backup-{}.db", chrono::Local::now().format("%Y-%m-%d"));
+    let Some(path) = choose_save_path(text(Msg::BackupPrompt), &default_name) else {
+        return Ok(None);
+    };
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    backup_to(&conn, &path)?;
+    notify::send_notification(text(Msg::BackupSavedTitle), &path.display().to_string());
+    Ok(Some(path))
+}
+
+#[tauri::command]
+pub(crate) async fn backup_db(state: tauri::State<'_, Arc<Mutex<AppState>>>, path: String) -> Result<(), String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+        backup_to(&conn, Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+// The current database is backed up next to it first, so a wrong file can be undone
+#[tauri::command]
+pub(crate) async fn restore_db(state: tauri::State<'_, Arc<Mutex<AppState>>>, path: String) -> Result<(), String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        validate_backup(&path)?;
+        let mut conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+        backup_to(&conn, &pre_restore_path())?;
+        restore_into(&mut conn, &path)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM usage_history", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let dir = std::env::temp_dir();
+        let db_path = dir.join(format!("test_db_backup_src_{}.db", std::process::id()));
+        let backup_path = dir.join(format!("test_db_backup_copy_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_file(&backup_path);
+
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        conn.execute("INSERT INTO usage_history (timestamp, session_percent) VALUES ('2026-01-28T10:00:00', 40)", [])
+            .unwrap();
+        backup_to(&conn, &backup_path).unwrap();
+
+        conn.execute("INSERT INTO usage_history (timestamp, session_percent) VALUES ('2026-01-28T10:10:00', 45)", [])
+            .unwrap();
+        assert_eq!(count(&conn), 2);
+
+        restore_into(&mut conn, &backup_path).unwrap();
+        assert_eq!(count(&conn), 1);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_file(&backup_path);
+    }
+
+    #[test]
+    fn test_restore_rejects_foreign_database() {
+        let dir = std::env::temp_dir();
+        let db_path = dir.join(format!("test_db_restore_dst_{}.db", std::process::id()));
+        let other_path = dir.join(format!("test_db_restore_other_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_file(&other_path);
+
+        Connection::open(&other_path)
+            .unwrap()
+            .execute("CREATE TABLE notes (body TEXT)", [])
+            .unwrap();
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        assert!(restore_into(&mut conn, &other_path).is_err());
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_file(&other_path);
+    }
+}
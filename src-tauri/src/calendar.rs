@@ -0,0 +1,111 @@
+// Upcoming resets as an iCalendar file: the next session reset and the next
+// few weekly ones (the weekly window repeats; a session window only starts
+// with use, so just the next is known). cc-usage-resets.ics in the data
+// folder is rewritten after every fetch, so a calendar subscribed to it stays
+// current; "Add Resets to Calendar..." writes it and opens it for importing.
+// Events are free time, with UIDs from the metric and time so importing
+// again updates them rather than adding copies.
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::{datadir, parse_reading_timestamp, period, privacy_mode, resolve_reset, Settings, UsageData};
+
+pub(crate) const RESETS_FILE: &str = "cc-usage-resets.ics";
+// Weekly resets listed, the next one included
+const WEEKLY_RESETS: i64 = 4;
+const EVENT_MINUTES: i64 = 15;
+
+fn ics_time(at: DateTime<Local>) -> String {
+    at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn event(out: &mut String, kind: &str, summary: &str, at: DateTime<Local>, now: DateTime<Local>) {
+    let lines = [
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}-{}@cc-usage", kind, ics_time(at)),
+        format!("DTSTAMP:{}", ics_time(now)),
+        format!("DTSTART:{}", ics_time(at)),
+        format!("DTEND:{}", ics_time(at + chrono::Duration::minutes(EVENT_MINUTES))),
+        format!("SUMMARY:{}", summary),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string(),
+    ];
+    for line in lines {
+        out.push_str(&line);
+        out.push_str("\r\n");
+    }
+}
+
+// Resets already past are left out
+pub(crate) fn calendar(usage: &UsageData, weekly_hours: i64, now: DateTime<Local>) -> String {
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    let mut out = String::from(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//cc-usage//Claude usage resets//EN\r\n\
+         CALSCALE:GREGORIAN\r\nX-WR-CALNAME:Claude usage resets\r\n",
+    );
+    if let Some(at) = resolve_reset(usage.session.resets.as_deref(), read_at).filter(|at| *at > now) {
+        event(&mut out, "session", "Claude session limit resets", at, now);
+    }
+    if let Some(first) = resolve_reset(usage.weekly_all.resets.as_deref(), read_at) {
+        for week in 0..WEEKLY_RESETS {
+            let at = first + chrono::Duration::hours(weekly_hours * week);
+            if at > now {
+                event(&mut out, "weekly", "Claude weekly limit resets", at, now);
+            }
+        }
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+fn resets_path() -> PathBuf {
+    datadir::data_dir().join(RESETS_FILE)
+}
+
+// After each successful fetch; not in privacy mode
+pub(crate) fn write(settings: &Settings, usage: &UsageData) {
+    if privacy_mode(settings) {
+        return;
+    }
+    let _ = datadir::write_atomic(&resets_path(), &calendar(usage, period::weekly_hours(), Local::now()));
+}
+
+// The tray's "Add Resets to Calendar...": write the file now and open it,
+// which imports it into the default calendar app
+pub(crate) fn add_to_calendar(usage: &UsageData) -> Result<(), String> {
+    let path = resets_path();
+    datadir::write_atomic(&path, &calendar(usage, period::weekly_hours(), Local::now()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    crate::open_file(&path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_calendar_lists_upcoming_resets() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 12, 50, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T12:45:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.resets = Some("Jan 30 at 9am".to_string());
+        let ics = calendar(&usage, 168, now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("SUMMARY:Claude session limit resets").count(), 1);
+        assert_eq!(ics.matches("SUMMARY:Claude weekly limit resets").count(), 4);
+        let session = Local.with_ymd_and_hms(2026, 1, 28, 16, 0, 0).unwrap();
+        assert!(ics.contains(&format!("DTSTART:{}\r\n", ics_time(session))));
+        let last_weekly = Local.with_ymd_and_hms(2026, 2, 20, 9, 0, 0).unwrap();
+        assert!(ics.contains(&format!("UID:weekly-{}@cc-usage", ics_time(last_weekly))));
+        // Once the session reset has passed, only the weekly ones are left
+        let later = calendar(&usage, 168, session + chrono::Duration::minutes(1));
+        assert!(!later.contains("session"));
+    }
+}
@@ -0,0 +1,82 @@
+// Subcommands for scripts and other menu bars: `cc-usage <name>` prints and
+// exits without starting the tray app. The menu bar ones apply the display
+// settings (language, time format, pace cutoffs) first, so output matches the
+// menu; `prompt` and `get` skip that to stay fast. `fetch` scrapes Claude
+// itself, for cron jobs on machines without the app running, and `history`
+// reads the history database. `--daemon` runs the app without its tray
+// (daemon.rs) and only exits when killed. Anything else on the command line
+// (macOS's -psn_ argument, or `refresh`, which is meant for the running
+// instance) starts the app as usual.
+use crate::{
+    apply_display_settings, daemon, fetch_usage, format_time_remaining, get_status_indicator_paced, history, prompt,
+    scripting, startup_settings, swiftbar, waybar, MenuMetric, Settings, UsageData,
+};
+
+// "🟢 Session: 42% · 2h 15m left" for each metric with a reading, as in the
+// menu but with `·` where the menu has `|`
+pub(crate) fn metric_lines(usage: &UsageData) -> Vec<String> {
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let item = metric.item(usage);
+            let percent = item.percent?;
+            let resets = item.resets.as_deref();
+            let line = format!("{} {}: {}%", get_status_indicator_paced(percent, resets, metric), metric.label(), percent);
+            Some(match (metric, resets) {
+                (MenuMetric::Session | MenuMetric::Weekly, Some(resets)) => {
+                    format!("{} · {}", line, format_time_remaining(resets))
+                }
+                _ => line,
+            })
+        })
+        .collect()
+}
+
+// `cc-usage fetch [--json]`: run the scraper once and print the reading, as
+// UsageData JSON or as the menu's lines. Nothing is saved, so it doesn't
+// disturb a running app's history. Exit code 1 when the fetch fails (the
+// JSON still prints, with `error` set), 2 for an unknown option.
+fn fetch(options: &[String], settings: &Settings) -> i32 {
+    let mut json = false;
+    for option in options {
+        match option.as_str() {
+            "--json" => json = true,
+            other => {
+                eprintln!("Unknown option: {} (usage: cc-usage fetch [--json])", other);
+                return 2;
+            }
+        }
+    }
+    let (data, _) = fetch_usage(&mut |_| {});
+    if json {
+        println!("{}", serde_json::to_string_pretty(&data).unwrap_or_default());
+    } else if let Some(error) = &data.error {
+        eprintln!("{}", error);
+    } else {
+        apply_display_settings(settings);
+        for line in metric_lines(&data) {
+            println!("{}", line);
+        }
+    }
+    i32::from(data.error.is_some())
+}
+
+// The exit code for a subcommand, or None to start the app
+pub(crate) fn run(args: &[String], settings: &Settings) -> Option<i32> {
+    match args.first()?.as_str() {
+        "--daemon" => Some(daemon::run(&startup_settings())),
+        "fetch" => Some(fetch(&args[1..], settings)),
+        "get" => Some(scripting::get(args.get(1).map(String::as_str))),
+        "history" => Some(history::print(&args[1..], settings)),
+        "prompt" => Some(prompt::print()),
+        "swiftbar" => {
+            apply_display_settings(settings);
+            Some(swiftbar::print())
+        }
+        "waybar" => {
+            apply_display_settings(settings);
+            Some(waybar::print())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,125 @@
+// "Copy Usage" menu items: the current reading as JSON or a Markdown table,
+// for pasting into standups, issues, or scripts
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::locale::{text, Msg};
+use crate::{guest_view, notify, AppState, UsageData, UsageItem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyFormat {
+    Json,
+    Markdown,
+}
+
+fn clipboard_command() -> Option<Command> {
+    #[cfg(target_os = "macos")]
+    {
+        Some(Command::new("pbcopy"))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard"]);
+        Some(cmd)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+pub(crate) fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut cmd = clipboard_command().ok_or("Clipboard not supported on this platform")?;
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run clipboard command: {}", e))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Clipboard command has no stdin")?
+        .write_all(text.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Clipboard command exited with {}", status))
+    }
+}
+
+pub(crate) fn usage_markdown(usage: &UsageData) -> String {
+    let mut out = match usage.timestamp.as_deref() {
+        Some(ts) => format!("**Claude usage** ({})\n\n", ts.split('.').next().unwrap_or(ts).replace('T', " ")),
+        None => "**Claude usage**\n\n".to_string(),
+    };
+    out.push_str("| Metric | Used | Resets |\n|---|---|---|\n");
+    let rows: [(&str, &UsageItem); 4] = [
+        ("Session", &usage.session),
+        ("Weekly (all)", &usage.weekly_all),
+        ("Weekly (Sonnet)", &usage.weekly_sonnet),
+        ("Weekly (Opus)", &usage.weekly_opus),
+    ];
+    for (name, item) in rows {
+        let Some(percent) = item.percent else {
+            continue;
+        };
+        out.push_str(&format!(
+            "| {} | {}% | {} |\n",
+            name,
+            percent,
+            item.resets.as_deref().unwrap_or("--").replace('|', "\\|")
+        ));
+    }
+    out
+}
+
+pub(crate) fn render(usage: &UsageData, format: CopyFormat) -> Result<String, String> {
+    match format {
+        CopyFormat::Json => serde_json::to_string_pretty(usage).map_err(|e| e.to_string()),
+        CopyFormat::Markdown => Ok(usage_markdown(usage)),
+    }
+}
+
+// Guest mode copies the same redacted view the frontend gets
+pub(crate) fn copy_usage(state: &AppState, format: CopyFormat) -> Result<(), String> {
+    let usage = if state.guest_mode {
+        guest_view(&state.usage)
+    } else {
+        state.usage.clone()
+    };
+    copy_to_clipboard(&render(&usage, format)?)?;
+    notify::send_notification(text(Msg::UsageCopiedTitle), text(Msg::UsageCopiedBody));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_skips_missing_metrics() {
+        let usage = UsageData {
+            timestamp: Some("2026-01-28T10:40:12.345".to_string()),
+            session: UsageItem {
+                percent: Some(42),
+                resets: Some("3pm".to_string()),
+            },
+            weekly_all: UsageItem {
+                percent: Some(61),
+                resets: None,
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            usage_markdown(&usage),
+            "**Claude usage** (2026-01-28 10:40:12)\n\n\
+             | Metric | Used | Resets |\n|---|---|---|\n\
+             | Session | 42% | 3pm |\n\
+             | Weekly (all) | 61% | -- |\n"
+        );
+        let json = render(&usage, CopyFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap()["session"]["percent"], 42);
+    }
+}
@@ -0,0 +1,185 @@
+// This week vs the previous ones, for the charts window's overlay: weekly
+// usage in each of the last few limit weeks, lined up by hours since the
+// week started, and how far ahead of or behind last week's pace the current
+// week is at the same point.
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{init_db, parse_reading_timestamp, profiles};
+
+const MAX_PREVIOUS_WEEKS: u32 = 4;
+
+// A weekly window (its weekly_period) and its (timestamp, weekly %) readings
+type Week = (String, Vec<(String, i32)>);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct WeekPoint {
+    hours_into_week: f64,
+    percent: i32,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct WeekSeries {
+    // The week's start, as in weekly_period
+    period: String,
+    // The last reading in each hour
+    points: Vec<WeekPoint>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub(crate) struct WeeklyComparison {
+    current: Option<WeekSeries>,
+    // Newest first
+    previous: Vec<WeekSeries>,
+    // Current usage minus last week's at the same hour, in points
+    ahead_of_last_week: Option<i32>,
+    summary: Option<String>,
+}
+
+fn period_start(period: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    parse_reading_timestamp(&format!("{}:00", period))
+}
+
+fn week_series(period: &str, readings: &[(String, i32)]) -> WeekSeries {
+    let start = period_start(period);
+    let mut points: Vec<WeekPoint> = Vec::new();
+    for (timestamp, percent) in readings {
+        let (Some(start), Some(at)) = (start, parse_reading_timestamp(timestamp)) else {
+            continue;
+        };
+        let minutes = at.signed_duration_since(start).num_minutes();
+        if minutes < 0 {
+            continue;
+        }
+        let point = WeekPoint {
+            hours_into_week: (minutes as f64 / 60.0 * 10.0).round() / 10.0,
+            percent: *percent,
+        };
+        match points.last_mut() {
+            Some(last) if last.hours_into_week.floor() == point.hours_into_week.floor() => *last = point,
+            _ => points.push(point),
+        }
+    }
+    WeekSeries {
+        period: period.to_string(),
+        points,
+    }
+}
+
+// Usage at `hours` into the week: the last point at or before it
+fn percent_at(series: &WeekSeries, hours: f64) -> Option<i32> {
+    series
+        .points
+        .iter()
+        .take_while(|p| p.hours_into_week <= hours)
+        .last()
+        .map(|p| p.percent)
+}
+
+fn summary(ahead: i32) -> String {
+    match ahead {
+        0 => "On last week's pace".to_string(),
+        a if a > 0 => format!("{}% ahead of last week's pace", a),
+        a => format!("{}% behind last week's pace", -a),
+    }
+}
+
+// `weeks` (newest first) are the weekly windows with their readings in order
+pub(crate) fn compare(weeks: &[Week]) -> WeeklyComparison {
+    let mut series = weeks.iter().map(|(period, readings)| week_series(period, readings));
+    let Some(current) = series.next() else {
+        return WeeklyComparison::default();
+    };
+    let previous: Vec<WeekSeries> = series.collect();
+    let ahead = current.points.last().and_then(|now| {
+        let last_week = previous.first()?;
+        Some(now.percent - percent_at(last_week, now.hours_into_week)?)
+    });
+    WeeklyComparison {
+        current: Some(current),
+        previous,
+        ahead_of_last_week: ahead,
+        summary: ahead.map(summary),
+    }
+}
+
+// The newest `count` weekly windows for the active profile, with their readings
+fn query_weeks(conn: &Connection, count: u32) -> rusqlite::Result<Vec<Week>> {
+    let profile = profiles::active_name();
+    let periods: Vec<String> = conn
+        .prepare(
+            "SELECT weekly_period FROM usage_history
+             WHERE weekly_period IS NOT NULL AND weekly_percent IS NOT NULL AND profile IS ?1
+             GROUP BY weekly_period ORDER BY weekly_period DESC LIMIT ?2",
+        )?
+        .query_map(params![profile, count], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, weekly_percent FROM usage_history
+         WHERE weekly_period = ?1 AND weekly_percent IS NOT NULL AND profile IS ?2
+         ORDER BY timestamp ASC",
+    )?;
+    periods
+        .into_iter()
+        .map(|period| {
+            let readings = stmt
+                .query_map(params![period, profile], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((period, readings))
+        })
+        .collect()
+}
+
+// The current week and the `weeks` before it (1-4, default 1)
+#[tauri::command]
+pub(crate) fn get_weekly_comparison(weeks: Option<u32>) -> Result<WeeklyComparison, String> {
+    let weeks = weeks.unwrap_or(1).clamp(1, MAX_PREVIOUS_WEEKS);
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let windows = query_weeks(&conn, weeks + 1).map_err(|e| format!("Failed to query history: {}", e))?;
+    Ok(compare(&windows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_this_week_vs_last_at_the_same_hour() {
+        let db_path = std::env::temp_dir().join(format!("test_db_comparison_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        let insert = |timestamp: &str, percent: i32, period: &str| {
+            conn.execute(
+                "INSERT INTO usage_history (timestamp, weekly_percent, weekly_period) VALUES (?1, ?2, ?3)",
+                params![timestamp, percent, period],
+            )
+            .unwrap();
+        };
+        let (two_ago, last, this) = ("2026-01-12T09:00", "2026-01-19T09:00", "2026-01-26T09:00");
+        insert("2026-01-13T09:00:00", 50, two_ago);
+        insert("2026-01-19T10:00:00", 5, last);
+        insert("2026-01-20T09:00:00", 20, last);
+        insert("2026-01-20T09:30:00", 22, last);
+        insert("2026-01-21T09:00:00", 30, last);
+        insert("2026-01-26T10:10:00", 10, this);
+        insert("2026-01-27T09:40:00", 40, this);
+
+        let weeks = query_weeks(&conn, 2).unwrap();
+        assert_eq!(weeks.len(), 2);
+        let comparison = compare(&weeks);
+        let current = comparison.current.as_ref().unwrap();
+        assert_eq!(current.period, this);
+        assert_eq!(current.points[0].hours_into_week, 1.2);
+        // Two readings in the same hour keep the later one
+        assert_eq!(comparison.previous[0].points.len(), 3);
+        // 24.7h in: 40% now vs 22% last week
+        assert_eq!(comparison.ahead_of_last_week, Some(18));
+        assert_eq!(comparison.summary.as_deref(), Some("18% ahead of last week's pace"));
+        assert_eq!(summary(-3), "3% behind last week's pace");
+        assert_eq!(compare(&[]), WeeklyComparison::default());
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
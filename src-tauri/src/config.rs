@@ -0,0 +1,203 @@
+// Optional TOML config for dotfiles and provisioning new machines, plus
+// CC_USAGE_* environment overrides. Layers, lowest first: the config file
+// (~/.config/cc-usage/config.toml, $XDG_CONFIG_HOME, or CC_USAGE_CONFIG), the
+// settings file the app writes, then the environment. Keys in the file are the
+// setting names, and any setting can be given as CC_USAGE_<NAME>
+// (CC_USAGE_REFRESH_INTERVAL_MINS=5, CC_USAGE_NOTIFY_ON_PACE=false,
+// CC_USAGE_SESSION_ALERT_THRESHOLDS=80,95). `data_dir` isn't layered; it stays
+// with CC_USAGE_DATA_DIR and the datadir module.
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde_json::{Map, Value};
+
+use crate::{append_log, Settings};
+
+pub(crate) const CONFIG_ENV: &str = "CC_USAGE_CONFIG";
+const ENV_PREFIX: &str = "CC_USAGE_";
+
+// Problems already logged. Settings are loaded several times a minute, so a
+// broken file or variable would otherwise add a line each time.
+static REPORTED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+fn first_report(message: &str) -> bool {
+    let mut reported = REPORTED.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    reported.get_or_insert_with(HashSet::new).insert(message.to_string())
+}
+
+fn log_once(message: String) {
+    if first_report(&message) {
+        append_log(&message);
+    }
+}
+
+pub(crate) fn config_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".config"));
+    base.join("cc-usage").join("config.toml")
+}
+
+fn to_map(settings: &Settings) -> Map<String, Value> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn from_map(map: Map<String, Value>) -> Option<Settings> {
+    serde_json::from_value(Value::Object(map)).ok()
+}
+
+// Fields set in `over` replace those in `base`
+pub(crate) fn layer(base: &Settings, over: &Settings) -> Settings {
+    let mut map = to_map(base);
+    map.extend(to_map(over).into_iter().filter(|(_, v)| !v.is_null()));
+    from_map(map).unwrap_or_else(|| base.clone())
+}
+
+// Fields that only repeat the config file, cleared so saving from the app
+// doesn't pin them and later edits to the file still apply
+pub(crate) fn without_config_values(settings: &Settings, config: &Settings) -> Settings {
+    let config = to_map(config);
+    let map = to_map(settings)
+        .into_iter()
+        .map(|(k, v)| if !v.is_null() && config.get(&k) == Some(&v) { (k, Value::Null) } else { (k, v) })
+        .collect();
+    from_map(map).unwrap_or_else(|| settings.clone())
+}
+
+pub(crate) fn parse_config(content: &str) -> Result<Settings, String> {
+    let mut settings: Settings = toml::from_str(content).map_err(|e| e.to_string())?;
+    settings.data_dir = None;
+    Ok(settings)
+}
+
+// The config file's settings; a missing file is empty, a broken one is logged
+// (once per distinct error) and skipped
+pub(crate) fn load_config() -> Settings {
+    let path = config_path();
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    parse_config(&content).unwrap_or_else(|e| {
+        log_once(format!("Ignoring {}: {}", path.display(), e));
+        Settings::default()
+    })
+}
+
+// What an environment value could mean, most specific first: JSON ("false",
+// "5", "[70, 85]"), a comma-separated list ("70,85,95"), then plain text
+fn env_candidates(raw: &str) -> Vec<Value> {
+    let raw = raw.trim();
+    let mut candidates: Vec<Value> = serde_json::from_str(raw).ok().into_iter().collect();
+    if raw.contains(',') {
+        let items = raw.split(',').map(|item| {
+            let item = item.trim();
+            serde_json::from_str(item).unwrap_or_else(|_| Value::String(item.to_string()))
+        });
+        candidates.push(Value::Array(items.collect()));
+    }
+    candidates.push(Value::String(raw.to_string()));
+    candidates
+}
+
+// CC_USAGE_<NAME> variables on top of `settings`. Values that don't fit their
+// setting are logged once and skipped.
+pub(crate) fn apply_env(settings: &Settings, vars: impl IntoIterator<Item = (String, String)>) -> Settings {
+    let mut map = to_map(settings);
+    for (key, raw) in vars {
+        let Some(name) = key.strip_prefix(ENV_PREFIX).map(str::to_lowercase) else {
+            continue;
+        };
+        if name == "data_dir" || !map.contains_key(&name) {
+            continue;
+        }
+        let fits = env_candidates(&raw).into_iter().find(|value| {
+            let mut trial = map.clone();
+            trial.insert(name.clone(), value.clone());
+            from_map(trial).is_some()
+        });
+        match fits {
+            Some(value) => {
+                map.insert(name, value);
+            }
+            None => log_once(format!("Ignoring {}={}: not a valid {}", key, raw, name)),
+        }
+    }
+    from_map(map).unwrap_or_else(|| settings.clone())
+}
+
+// Config file, then the saved settings, then the environment
+pub(crate) fn effective(saved: &Settings) -> Settings {
+    apply_env(&layer(&load_config(), saved), std::env::vars())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_file_then_saved_settings() {
+        let config = parse_config(
+            r#"
+refresh_interval_mins = 5
+session_alert_thresholds = [80, 95]
+tray_metric = "session"
+data_dir = "/Volumes/Elsewhere"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.data_dir, None);
+        let saved = Settings {
+            tray_metric: Some("weekly".to_string()),
+            ..Default::default()
+        };
+        let settings = layer(&config, &saved);
+        assert_eq!(settings.refresh_interval_mins, Some(5));
+        assert_eq!(settings.session_alert_thresholds, Some(vec![80, 95]));
+        assert_eq!(settings.tray_metric.as_deref(), Some("weekly"));
+        assert!(parse_config("refresh_interval_mins = \"soon\"").is_err());
+
+        let stripped = without_config_values(&settings, &config);
+        assert_eq!(stripped.refresh_interval_mins, None);
+        assert_eq!(stripped.tray_metric.as_deref(), Some("weekly"));
+    }
+
+    #[test]
+    fn test_env_overrides_parse_by_setting_type() {
+        let vars = [
+            ("CC_USAGE_REFRESH_INTERVAL_MINS", "3"),
+            ("CC_USAGE_NOTIFY_ON_PACE", "false"),
+            ("CC_USAGE_WEEKLY_ALERT_THRESHOLDS", "50, 90"),
+            ("CC_USAGE_MENU_METRICS", "session,weekly"),
+            ("CC_USAGE_MACHINE_NAME", "1234"),
+            ("CC_USAGE_STALE_AFTER_MINS", "soon"),
+            ("CC_USAGE_DATA_DIR", "/tmp/elsewhere"),
+            ("HOME", "/Users/me"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let saved = Settings {
+            refresh_interval_mins: Some(10),
+            stale_after_mins: Some(30),
+            ..Default::default()
+        };
+        let settings = apply_env(&saved, vars);
+        assert_eq!(settings.refresh_interval_mins, Some(3));
+        assert_eq!(settings.notify_on_pace, Some(false));
+        assert_eq!(settings.weekly_alert_thresholds, Some(vec![50, 90]));
+        assert_eq!(settings.menu_metrics, Some(vec!["session".to_string(), "weekly".to_string()]));
+        assert_eq!(settings.machine_name.as_deref(), Some("1234"));
+        // Not a number: skipped, the saved value stays
+        assert_eq!(settings.stale_after_mins, Some(30));
+        assert_eq!(settings.data_dir, None);
+        // Already logged, so loading settings again doesn't repeat it
+        assert!(!first_report("Ignoring CC_USAGE_STALE_AFTER_MINS=soon: not a valid stale_after_mins"));
+    }
+}
@@ -0,0 +1,168 @@
+// Token totals and estimated cost from the transcripts, per day and per
+// model, for the charts window's cost tab. The cost is what the tokens would
+// cost at API list prices, not what a subscription charges.
+use std::collections::BTreeMap;
+
+use chrono::Local;
+use serde::Serialize;
+
+use crate::transcripts::{self, TranscriptEntry};
+
+// US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price {
+    input: f64,
+    output: f64,
+    cache_write: f64,
+    cache_read: f64,
+}
+
+// (model name part, input, output). First match wins, so more specific
+// names come first.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("opus-4-5", 5.0, 25.0),
+    ("opus", 15.0, 75.0),
+    ("sonnet", 3.0, 15.0),
+    ("haiku-4-5", 1.0, 5.0),
+    ("haiku", 0.8, 4.0),
+];
+
+// Cache writes cost 1.25× input and cache reads 0.1×
+fn price_for(model: &str) -> Option<Price> {
+    let (_, input, output) = PRICES.iter().find(|(name, _, _)| model.contains(name))?;
+    Some(Price {
+        input: *input,
+        output: *output,
+        cache_write: input * 1.25,
+        cache_read: input * 0.1,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub(crate) struct TokenTotals {
+    input: u64,
+    output: u64,
+    cache_creation: u64,
+    cache_read: u64,
+}
+
+impl TokenTotals {
+    fn add(&mut self, entry: &TranscriptEntry) {
+        self.input += entry.input_tokens;
+        self.output += entry.output_tokens;
+        self.cache_creation += entry.cache_creation_tokens;
+        self.cache_read += entry.cache_read_tokens;
+    }
+}
+
+// Estimated cost of one response. None for a model without a known price.
+pub(crate) fn entry_cost(entry: &TranscriptEntry) -> Option<f64> {
+    let price = price_for(entry.model.as_deref()?)?;
+    let cost = entry.input_tokens as f64 * price.input
+        + entry.output_tokens as f64 * price.output
+        + entry.cache_creation_tokens as f64 * price.cache_write
+        + entry.cache_read_tokens as f64 * price.cache_read;
+    Some(cost / 1_000_000.0)
+}
+
+fn cents(cost: f64) -> f64 {
+    (cost * 100.0).round() / 100.0
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct CostRow {
+    // The date ("2026-01-28") or the model
+    key: String,
+    tokens: TokenTotals,
+    cost: f64,
+    // Responses from models without a known price, left out of `cost`
+    unpriced: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct CostBreakdown {
+    days: Vec<CostRow>,
+    models: Vec<CostRow>,
+    pub(crate) total_cost: f64,
+}
+
+fn rows(groups: BTreeMap<String, (TokenTotals, f64, u64)>) -> Vec<CostRow> {
+    groups
+        .into_iter()
+        .map(|(key, (tokens, cost, unpriced))| CostRow {
+            key,
+            tokens,
+            cost: cents(cost),
+            unpriced,
+        })
+        .collect()
+}
+
+// Days oldest first; models by name
+pub(crate) fn breakdown(entries: &[TranscriptEntry]) -> CostBreakdown {
+    let mut days: BTreeMap<String, (TokenTotals, f64, u64)> = BTreeMap::new();
+    let mut models: BTreeMap<String, (TokenTotals, f64, u64)> = BTreeMap::new();
+    let mut total = 0.0;
+    for entry in entries {
+        let cost = entry_cost(entry);
+        total += cost.unwrap_or(0.0);
+        let day = entry.timestamp.format("%Y-%m-%d").to_string();
+        let model = entry.model.clone().unwrap_or_else(|| "unknown".to_string());
+        for group in [days.entry(day).or_default(), models.entry(model).or_default()] {
+            group.0.add(entry);
+            group.1 += cost.unwrap_or(0.0);
+            group.2 += u64::from(cost.is_none());
+        }
+    }
+    CostBreakdown {
+        days: rows(days),
+        models: rows(models),
+        total_cost: cents(total),
+    }
+}
+
+// The last `days` days (default 30)
+#[tauri::command]
+pub(crate) async fn get_cost_breakdown(days: Option<i32>) -> Result<CostBreakdown, String> {
+    let now = Local::now();
+    let from = now - chrono::Duration::days(days.unwrap_or(30).max(1) as i64);
+    tauri::async_runtime::spawn_blocking(move || breakdown(&transcripts::read_entries(from, now)))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_breakdown_prices_by_model_and_day() {
+        let entry = |day: u32, model: Option<&str>, input: u64, output: u64, cache_read: u64| TranscriptEntry {
+            timestamp: Local.with_ymd_and_hms(2026, 1, day, 14, 0, 0).unwrap(),
+            session_id: None,
+            model: model.map(String::from),
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_tokens: 0,
+            cache_read_tokens: cache_read,
+        };
+        let entries = [
+            entry(27, Some("claude-opus-4-1-20250805"), 1_000_000, 100_000, 0),
+            entry(28, Some("claude-sonnet-4-5-20250929"), 0, 1_000_000, 1_000_000),
+            entry(28, Some("gpt-4"), 500, 500, 0),
+        ];
+        let costs = breakdown(&entries);
+        // 15 + 7.50
+        assert_eq!(costs.days[0].key, "2026-01-27");
+        assert_eq!(costs.days[0].cost, 22.5);
+        // 15 + 0.30
+        assert_eq!(costs.days[1].cost, 15.3);
+        assert_eq!(costs.days[1].unpriced, 1);
+        assert_eq!(costs.total_cost, 37.8);
+        assert_eq!(costs.models.len(), 3);
+        assert_eq!(costs.models[0].key, "claude-opus-4-1-20250805");
+        assert_eq!(costs.models[1].tokens.cache_read, 1_000_000);
+        assert_eq!(price_for("claude-opus-4-5-20251101").map(|p| p.output), Some(25.0));
+    }
+}
@@ -0,0 +1,71 @@
+// HTTP requests through the system curl, for the webhook, Slack, OTLP, and the
+// snapshot upload. Every option, the URL included, goes in a config read from
+// stdin: webhook URLs (Slack's especially) and upload keys are credentials, and
+// a process's arguments show up in `ps`.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// `name = "value"` for a curl config file, with the quoting curl expects
+pub(crate) fn config_line(name: &str, value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("{} = \"{}\"\n", name, escaped)
+}
+
+// Fail on HTTP errors, and only print why
+pub(crate) fn base_config(method: &str, max_time_secs: u32) -> String {
+    format!("fail\nsilent\nshow-error\nmax-time = {}\nrequest = {}\n", max_time_secs, method)
+}
+
+fn post_json_config(url: &str, body: &str) -> String {
+    let mut config = base_config("POST", 10);
+    config.push_str(&config_line("header", "Content-Type: application/json"));
+    config.push_str(&config_line("data-binary", body));
+    config.push_str(&config_line("url", url));
+    config
+}
+
+// Blocking; Err has curl's message
+pub(crate) fn run(config: &str) -> Result<(), String> {
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    child
+        .stdin
+        .take()
+        .ok_or("curl has no stdin")?
+        .write_all(config.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+    }
+}
+
+// Blocking
+pub(crate) fn post_json(url: &str, body: &str) -> Result<(), String> {
+    run(&post_json_config(url, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_json_config() {
+        let config = post_json_config("https://hooks.slack.com/services/T0/B0/secret", r#"{"text":"a\"b"}"#);
+        assert!(config.starts_with("fail\nsilent\nshow-error\nmax-time = 10\nrequest = POST\n"));
+        assert!(config.contains("header = \"Content-Type: application/json\"\n"));
+        assert!(config.contains("data-binary = \"{\\\"text\\\":\\\"a\\\\\\\"b\\\"}\"\n"));
+        assert!(config.ends_with("url = \"https://hooks.slack.com/services/T0/B0/secret\"\n"));
+    }
+}
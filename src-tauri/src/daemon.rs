@@ -0,0 +1,113 @@
+// `cc-usage --daemon`: the refresh loop, history recording, and the local
+// server without the tray, for headless Macs and Linux boxes where Claude
+// Code runs over SSH. Tauri isn't started, so no display is needed. Readings
+// go through the same steps as the app's (cache, history, sync, alerts,
+// webhooks, MQTT, the status files), and each is written to the database
+// right away since a daemon is stopped with a signal rather than Quit. The
+// settings file is reread before each fetch, so a new interval or server port
+// applies without a restart. Runs in the foreground until killed, printing a
+// line per fetch, for launchd or systemd to supervise. It doesn't take the
+// app's single-instance lock; run one or the other against a data folder.
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::{
+    apply_display_settings, apply_fetch_result, cli, fetch_and_log, initial_state, load_settings, minute_tick,
+    publish_fetch_result, scheduled_fetch_due, secs_until_next_fetch, secs_until_next_tick, server, settings, store,
+    write_status_files, AppState, Settings,
+};
+
+// What gets printed for a fetch: the reading's metric lines, or the error
+pub(crate) fn fetch_line(state: &AppState) -> String {
+    let at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    match &state.last_error {
+        Some(error) => format!("[{}] Fetch failed: {}", at, error),
+        None => format!("[{}] {}", at, cli::metric_lines(&state.usage).join(", ")),
+    }
+}
+
+fn fetch(state: &Mutex<AppState>, trigger: &str) {
+    let data = fetch_and_log(trigger, &|_| {});
+    let mut guard = state.lock().unwrap_or_else(PoisonError::into_inner);
+    let effects = apply_fetch_result(&mut guard, data);
+    let state = guard.clone();
+    drop(guard);
+    publish_fetch_result(effects, &state);
+    store::flush();
+    write_status_files(&state, &load_settings());
+    match state.last_error {
+        Some(_) => eprintln!("{}", fetch_line(&state)),
+        None => println!("{}", fetch_line(&state)),
+    }
+}
+
+// POST /refresh: a fetch outside the schedule, ignored while one is running
+fn spawn_triggered_fetch(state: Arc<Mutex<AppState>>, trigger: &'static str) {
+    {
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.manual_refresh_started.is_some() {
+            return;
+        }
+        state.manual_refresh_started = Some(chrono::Local::now());
+    }
+    std::thread::spawn(move || {
+        fetch(&state, trigger);
+        state.lock().unwrap_or_else(PoisonError::into_inner).manual_refresh_started = None;
+    });
+}
+
+// Held alerts, the daily summaries, and the status files' countdowns
+fn spawn_ticker(state: Arc<Mutex<AppState>>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(secs_until_next_tick(chrono::Local::now())));
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        let settings = load_settings();
+        minute_tick(&mut state, &settings, chrono::Local::now());
+        write_status_files(&state, &settings);
+    });
+}
+
+pub(crate) fn run(settings: &Settings) -> i32 {
+    apply_display_settings(settings);
+    let state = Arc::new(Mutex::new(initial_state(settings)));
+    let refresh_state = state.clone();
+    let source = server::Source {
+        state: state.clone(),
+        refresh: Arc::new(move |trigger| spawn_triggered_fetch(refresh_state.clone(), trigger)),
+    };
+    let serving = match settings.server_port {
+        Some(port) => format!("local server on 127.0.0.1:{}", port),
+        None => "no local server (server_port isn't set)".to_string(),
+    };
+    println!(
+        "cc-usage daemon: fetching every {} min, {}",
+        settings::refresh_interval_secs(settings) / 60,
+        serving
+    );
+    spawn_ticker(state.clone());
+    loop {
+        server::serve(&load_settings(), source.clone());
+        if scheduled_fetch_due(&state) {
+            fetch(&state, "scheduled");
+        }
+        let sleep_secs = secs_until_next_fetch(&state.lock().unwrap_or_else(PoisonError::into_inner));
+        std::thread::sleep(Duration::from_secs(sleep_secs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_line() {
+        let mut state = AppState::default();
+        state.usage.session.percent = Some(42);
+        let line = fetch_line(&state);
+        assert!(line.starts_with('['), "{}", line);
+        assert!(line.ends_with("Session: 42%"), "{}", line);
+
+        state.last_error = Some("No network".to_string());
+        assert!(fetch_line(&state).ends_with("] Fetch failed: No network"));
+    }
+}
@@ -0,0 +1,232 @@
+// Daily note journaling (Obsidian and other Markdown notes), off unless
+// `daily_note_path` is set. Once a day has ended, the countdown ticker
+// appends one line about it to that day's note: the peak session percentage,
+// the weekly percentage at the last reading, and the transcripts' estimated
+// cost. The path is an absolute template where `{date}` is the day as
+// 2026-01-28, or `{date:%Y/%m/%d}` in another strftime format, matching the
+// notes app's own file naming. Each line ends with an HTML comment naming the
+// day, which Markdown previews hide, so a restart doesn't add the line twice.
+// Files and folders are created if missing. Not in privacy mode.
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::locale::{tr, Msg};
+use crate::{append_log, cost, init_db, privacy_mode, profiles, transcripts, AppState, Settings};
+
+const DATE_PLACEHOLDER: &str = "{date";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct DaySummary {
+    peak_session: Option<i32>,
+    weekly_at_end: Option<i32>,
+    cost: Option<f64>,
+}
+
+fn valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+}
+
+// Each `{date}` / `{date:FORMAT}` in the template, as (start, end, format)
+fn placeholders(template: &str) -> Result<Vec<(usize, usize, &str)>, String> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = template[from..].find(DATE_PLACEHOLDER).map(|i| from + i) {
+        let end = template[start..]
+            .find('}')
+            .map(|i| start + i + 1)
+            .ok_or_else(|| format!("Unclosed {{date}} in {}", template))?;
+        let format = match &template[start + DATE_PLACEHOLDER.len()..end - 1] {
+            "" => DEFAULT_DATE_FORMAT,
+            spec => spec
+                .strip_prefix(':')
+                .ok_or_else(|| format!("Expected {{date}} or {{date:FORMAT}} in {}", template))?,
+        };
+        if !valid_format(format) {
+            return Err(format!("Invalid date format: {}", format));
+        }
+        found.push((start, end, format));
+        from = end;
+    }
+    Ok(found)
+}
+
+pub(crate) fn check(template: &str) -> Result<(), String> {
+    if !std::path::Path::new(template).is_absolute() {
+        return Err(format!("Daily note path must be an absolute path: {}", template));
+    }
+    placeholders(template).map(|_| ())
+}
+
+pub(crate) fn note_path(template: &str, day: NaiveDate) -> Result<PathBuf, String> {
+    let mut path = String::new();
+    let mut last = 0;
+    for (start, end, format) in placeholders(template)? {
+        path.push_str(&template[last..start]);
+        let _ = write!(path, "{}", day.format(format));
+        last = end;
+    }
+    path.push_str(&template[last..]);
+    Ok(PathBuf::from(path))
+}
+
+fn marker(day: NaiveDate) -> String {
+    format!("<!-- cc-usage {} -->", day.format("%Y-%m-%d"))
+}
+
+// None for a day without readings
+pub(crate) fn line(summary: DaySummary, day: NaiveDate) -> Option<String> {
+    if summary.peak_session.is_none() && summary.weekly_at_end.is_none() {
+        return None;
+    }
+    let mut parts = Vec::new();
+    parts.extend(summary.peak_session.map(|p| tr(Msg::DailyNotePeakSession, &[&p])));
+    parts.extend(summary.weekly_at_end.map(|p| tr(Msg::DailyNoteWeeklyAtEnd, &[&p])));
+    parts.extend(summary.cost.map(|c| tr(Msg::DailyNoteCost, &[&format!("{:.2}", c)])));
+    Some(format!("- {} {}", tr(Msg::DailyNoteSummary, &[&parts.join(", ")]), marker(day)))
+}
+
+fn query_day(conn: &Connection, day: NaiveDate) -> rusqlite::Result<DaySummary> {
+    let from = format!("{}T00:00:00", day.format("%Y-%m-%d"));
+    let to = format!("{}T00:00:00", day.succ_opt().unwrap_or(day).format("%Y-%m-%d"));
+    let peak_session = conn.query_row(
+        "SELECT MAX(session_percent) FROM usage_history WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3",
+        params![from, to, profiles::active_name()],
+        |row| row.get(0),
+    )?;
+    let weekly_at_end = conn
+        .query_row(
+            "SELECT weekly_percent FROM usage_history
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND weekly_percent IS NOT NULL AND profile IS ?3
+             ORDER BY timestamp DESC LIMIT 1",
+            params![from, to, profiles::active_name()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(DaySummary {
+        peak_session,
+        weekly_at_end,
+        cost: None,
+    })
+}
+
+// The day's estimated cost from the transcripts; None when there are none
+fn day_cost(day: NaiveDate) -> Option<f64> {
+    let start = Local.from_local_datetime(&day.and_hms_opt(0, 0, 0)?).earliest()?;
+    let end = Local.from_local_datetime(&day.succ_opt()?.and_hms_opt(0, 0, 0)?).earliest()?;
+    let entries = transcripts::read_entries(start, end);
+    (!entries.is_empty()).then(|| cost::breakdown(&entries).total_cost)
+}
+
+fn append(template: &str, day: NaiveDate) -> Result<(), String> {
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let summary = query_day(&conn, day).map_err(|e| e.to_string())?;
+    drop(conn);
+    let Some(line) = line(DaySummary { cost: day_cost(day), ..summary }, day) else {
+        return Ok(());
+    };
+    let path = note_path(template, day)?;
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    if existing.contains(&marker(day)) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(format!("{}{}\n", separator, line).as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Called every minute by the countdown ticker: yesterday's line, once
+pub(crate) fn append_if_due(state: &mut AppState, settings: &Settings, now: DateTime<Local>) {
+    let Some(template) = settings.daily_note_path.clone() else {
+        return;
+    };
+    let Some(yesterday) = now.date_naive().pred_opt() else {
+        return;
+    };
+    if privacy_mode(settings) || state.daily_note_written_for == Some(yesterday) {
+        return;
+    }
+    state.daily_note_written_for = Some(yesterday);
+    // Reading the transcripts can take a while
+    std::thread::spawn(move || {
+        if let Err(e) = append(&template, yesterday) {
+            append_log(&format!("Daily note failed: {}", e));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_path_and_line() {
+        let day = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        assert_eq!(
+            note_path("/vault/Daily/{date}.md", day).unwrap(),
+            PathBuf::from("/vault/Daily/2026-01-28.md")
+        );
+        assert_eq!(
+            note_path("/vault/{date:%Y}/{date:%m-%d}.md", day).unwrap(),
+            PathBuf::from("/vault/2026/01-28.md")
+        );
+        assert_eq!(note_path("/vault/log.md", day).unwrap(), PathBuf::from("/vault/log.md"));
+        assert!(check("/vault/{date:%Q}.md").is_err());
+        assert!(check("/vault/{date.md").is_err());
+        assert!(check("vault/{date}.md").is_err());
+
+        let summary = DaySummary {
+            peak_session: Some(87),
+            weekly_at_end: Some(64),
+            cost: Some(12.3),
+        };
+        assert_eq!(
+            line(summary, day).unwrap(),
+            "- Claude usage: peak session 87%, weekly 64% at end of day, est. $12.30 at API prices \
+             <!-- cc-usage 2026-01-28 -->"
+        );
+        assert_eq!(line(DaySummary::default(), day), None);
+    }
+
+    #[test]
+    fn test_query_day() {
+        let db_path = std::env::temp_dir().join(format!("test_db_dailynote_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        for (timestamp, session, weekly) in [
+            ("2026-01-27T23:50:00", 95, 50),
+            ("2026-01-28T10:00:00", 40, 55),
+            ("2026-01-28T15:00:00", 87, 61),
+            ("2026-01-28T22:00:00", 12, 64),
+            ("2026-01-29T00:10:00", 99, 70),
+        ] {
+            let mut usage = crate::UsageData {
+                timestamp: Some(timestamp.to_string()),
+                ..Default::default()
+            };
+            usage.session.percent = Some(session);
+            usage.weekly_all.percent = Some(weekly);
+            crate::insert_usage(&conn, &usage).unwrap();
+        }
+        let day = NaiveDate::from_ymd_opt(2026, 1, 28).unwrap();
+        let summary = query_day(&conn, day).unwrap();
+        assert_eq!((summary.peak_session, summary.weekly_at_end), (Some(87), Some(64)));
+        let empty = query_day(&conn, day.succ_opt().unwrap().succ_opt().unwrap()).unwrap();
+        assert_eq!(empty, DaySummary::default());
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
@@ -0,0 +1,227 @@
+// Where the cache, settings, database, and logs live. Defaults to ~/.claude;
+// CC_USAGE_DATA_DIR or the `data_dir` setting moves them (e.g. to a Dropbox
+// folder or $XDG_DATA_HOME/cc-usage).
+//
+// `data_dir` is always read from the settings file in the default location,
+// which becomes a pointer once the real settings have moved.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    append_log, backup, ensure_not_guest, load_settings, store, windowstate, AppState, Settings, CACHE_FILE, DB_FILE,
+    ERROR_LOG_FILE, LOG_FILE, SETTINGS_FILE,
+};
+
+pub(crate) const DATA_DIR_ENV: &str = "CC_USAGE_DATA_DIR";
+
+// Everything that moves with the data directory
+const DATA_FILES: &[&str] = &[
+    DB_FILE,
+    CACHE_FILE,
+    SETTINGS_FILE,
+    ERROR_LOG_FILE,
+    LOG_FILE,
+    backup::PRE_RESTORE_FILE,
+    windowstate::WINDOW_STATE_FILE,
+];
+
+pub(crate) fn default_data_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".claude")
+}
+
+// Write `contents` to a sibling temp file and rename it into place, so a reader
+// never sees the file half-written
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+fn configured_data_dir() -> Option<PathBuf> {
+    let content = fs::read_to_string(default_data_dir().join(SETTINGS_FILE)).ok()?;
+    let settings: Settings = serde_json::from_str(&content).ok()?;
+    settings.data_dir.filter(|d| !d.trim().is_empty()).map(|d| expand_home(d.trim()))
+}
+
+// Lets a test point this thread at a temporary folder
+#[cfg(test)]
+thread_local! {
+    pub(crate) static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+pub(crate) fn data_dir() -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = TEST_DATA_DIR.with(|d| d.borrow().clone()) {
+        return dir;
+    }
+    std::env::var(DATA_DIR_ENV)
+        .ok()
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| expand_home(d.trim()))
+        .or_else(configured_data_dir)
+        .unwrap_or_else(default_data_dir)
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    // rename fails across filesystems (e.g. onto an external drive)
+    fs::rename(from, to).or_else(|_| fs::copy(from, to).and_then(|_| fs::remove_file(from)))
+}
+
+// Move the data files from one directory to another. Nothing is moved if any
+// of them already exists at the destination.
+pub(crate) fn migrate_files(from: &Path, to: &Path, files: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let present: Vec<&str> = files.iter().copied().filter(|f| from.join(f).exists()).collect();
+    if let Some(conflict) = present.iter().find(|f| to.join(f).exists()) {
+        return Err(format!("{} already exists", to.join(conflict).display()));
+    }
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let mut moved = Vec::new();
+    for file in present {
+        let dest = to.join(file);
+        move_file(&from.join(file), &dest)
+            .map_err(|e| format!("Failed to move {} to {}: {}", file, to.display(), e))?;
+        moved.push(dest);
+    }
+    Ok(moved)
+}
+
+// At startup: if the directory was changed by hand (env var or editing the
+// setting) and the data is still in ~/.claude, bring it along. The settings
+// file is copied rather than moved since it holds the pointer.
+pub(crate) fn migrate_on_startup() {
+    let (from, to) = (default_data_dir(), data_dir());
+    if from == to || !from.join(DB_FILE).exists() || to.join(DB_FILE).exists() {
+        return;
+    }
+    let files: Vec<&str> = DATA_FILES.iter().copied().filter(|f| *f != SETTINGS_FILE).collect();
+    match migrate_files(&from, &to, &files) {
+        Ok(_) => {
+            if !to.join(SETTINGS_FILE).exists() {
+                let _ = fs::copy(from.join(SETTINGS_FILE), to.join(SETTINGS_FILE));
+            }
+            append_log(&format!("Moved data from {} to {}", from.display(), to.display()));
+        }
+        Err(e) => append_log(&format!("Data directory migration failed: {}", e)),
+    }
+}
+
+// Move all data to `path` (None = back to ~/.claude) and remember the choice
+#[tauri::command]
+pub(crate) fn set_data_dir(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: Option<String>,
+) -> Result<String, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    if std::env::var_os(DATA_DIR_ENV).is_some() {
+        return Err(format!("{} is set and takes precedence", DATA_DIR_ENV));
+    }
+    let default = default_data_dir();
+    let target = path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(expand_home)
+        .unwrap_or_else(|| default.clone());
+    if !target.is_absolute() {
+        return Err("Data directory must be an absolute path".to_string());
+    }
+    let current = data_dir();
+    if target == current {
+        return Ok(current.display().to_string());
+    }
+
+    let mut settings = load_settings();
+    store::close();
+    // Moving back home: the settings file there is only the pointer, and gets
+    // replaced by the full settings below
+    let files: Vec<&str> = DATA_FILES
+        .iter()
+        .copied()
+        .filter(|f| target != default || *f != SETTINGS_FILE)
+        .collect();
+    migrate_files(&current, &target, &files)?;
+    settings.data_dir = (target != default).then(|| target.display().to_string());
+    if let Ok(json) = serde_json::to_string_pretty(&settings) {
+        let _ = fs::write(target.join(SETTINGS_FILE), json);
+    }
+    if target == default {
+        let _ = fs::remove_file(current.join(SETTINGS_FILE));
+    }
+    if target != default {
+        let pointer = Settings {
+            data_dir: settings.data_dir.clone(),
+            ..Default::default()
+        };
+        let _ = fs::create_dir_all(&default);
+        if let Ok(json) = serde_json::to_string_pretty(&pointer) {
+            let _ = fs::write(default.join(SETTINGS_FILE), json);
+        }
+    }
+    append_log(&format!("Moved data from {} to {}", current.display(), target.display()));
+    Ok(target.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("test_datadir_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_migrate_moves_present_files() {
+        let (from, to) = (temp_dir("from"), temp_dir("to"));
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join(DB_FILE), "db").unwrap();
+        fs::write(from.join(CACHE_FILE), "{}").unwrap();
+
+        let moved = migrate_files(&from, &to, DATA_FILES).unwrap();
+        assert_eq!(moved.len(), 2);
+        assert!(!from.join(DB_FILE).exists());
+        assert_eq!(fs::read_to_string(to.join(DB_FILE)).unwrap(), "db");
+
+        let _ = fs::remove_dir_all(&from);
+        let _ = fs::remove_dir_all(&to);
+    }
+
+    #[test]
+    fn test_migrate_refuses_to_overwrite() {
+        let (from, to) = (temp_dir("from_conflict"), temp_dir("to_conflict"));
+        fs::create_dir_all(&from).unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(from.join(DB_FILE), "new").unwrap();
+        fs::write(from.join(CACHE_FILE), "{}").unwrap();
+        fs::write(to.join(DB_FILE), "existing").unwrap();
+
+        assert!(migrate_files(&from, &to, DATA_FILES).is_err());
+        // Nothing moved, including the non-conflicting file
+        assert!(from.join(CACHE_FILE).exists());
+        assert_eq!(fs::read_to_string(to.join(DB_FILE)).unwrap(), "existing");
+
+        let _ = fs::remove_dir_all(&from);
+        let _ = fs::remove_dir_all(&to);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/Dropbox/cc-usage"), home.join("Dropbox/cc-usage"));
+        assert_eq!(expand_home("/var/data"), PathBuf::from("/var/data"));
+    }
+}
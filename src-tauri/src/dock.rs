@@ -0,0 +1,82 @@
+// Whether the app shows in the Dock and Cmd-Tab (the macOS activation
+// policy). `dock_icon`: "windows" (default) shows it only while the charts or
+// Settings window is open, "never" keeps the app tray-only even then, and
+// "always" keeps the Dock icon. The popover doesn't count as a window.
+// Nothing to do on other platforms.
+use tauri::Runtime;
+
+#[cfg(target_os = "macos")]
+use tauri::Manager;
+
+#[cfg(target_os = "macos")]
+use crate::{load_settings, popover};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum DockIcon {
+    #[default]
+    WithWindows,
+    Never,
+    Always,
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+impl DockIcon {
+    pub(crate) const ALL: [DockIcon; 3] = [DockIcon::WithWindows, DockIcon::Never, DockIcon::Always];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            DockIcon::WithWindows => "windows",
+            DockIcon::Never => "never",
+            DockIcon::Always => "always",
+        }
+    }
+
+    fn from_setting(value: Option<&str>) -> Self {
+        Self::ALL.into_iter().find(|m| Some(m.as_str()) == value).unwrap_or_default()
+    }
+
+    fn visible(self, open_windows: usize) -> bool {
+        match self {
+            DockIcon::WithWindows => open_windows > 0,
+            DockIcon::Never => false,
+            DockIcon::Always => true,
+        }
+    }
+}
+
+// Set the policy for the windows open now. `closing` is a window being
+// destroyed, which is still listed while its event runs.
+pub(crate) fn update<R: Runtime>(app: &tauri::AppHandle<R>, closing: Option<&str>) {
+    #[cfg(target_os = "macos")]
+    {
+        let mode = DockIcon::from_setting(load_settings().dock_icon.as_deref());
+        let open = app
+            .webview_windows()
+            .keys()
+            .filter(|label| label.as_str() != popover::POPOVER_LABEL && Some(label.as_str()) != closing)
+            .count();
+        let policy = if mode.visible(open) {
+            tauri::ActivationPolicy::Regular
+        } else {
+            tauri::ActivationPolicy::Accessory
+        };
+        let _ = app.set_activation_policy(policy);
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, closing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dock_icon_follows_open_windows() {
+        assert!(!DockIcon::WithWindows.visible(0));
+        assert!(DockIcon::WithWindows.visible(1));
+        assert!(!DockIcon::Never.visible(2));
+        assert!(DockIcon::Always.visible(0));
+        assert_eq!(DockIcon::from_setting(Some("never")), DockIcon::Never);
+        assert_eq!(DockIcon::from_setting(None), DockIcon::WithWindows);
+    }
+}
@@ -0,0 +1,253 @@
+// Opt-in encryption of the history database with SQLCipher (`sqlcipher` cargo
+// feature). The key is random and lives in the macOS keychain, never on disk.
+//
+// The file is reconciled with the `encrypt_database` setting on open: a
+// plaintext database is encrypted in place when the setting is turned on, and
+// decrypted again when it's turned off.
+use rusqlite::Connection;
+use std::path::Path;
+#[cfg(feature = "sqlcipher")]
+use std::process::{Command, Stdio};
+#[cfg(feature = "sqlcipher")]
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex};
+
+use crate::{ensure_not_guest, init_db, load_saved_settings, load_settings, save_settings, store, AppState};
+
+#[cfg(feature = "sqlcipher")]
+const KEYCHAIN_SERVICE: &str = "cc-usage";
+#[cfg(feature = "sqlcipher")]
+const KEYCHAIN_ACCOUNT: &str = "history-db";
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+pub(crate) fn is_enabled() -> bool {
+    load_settings().encrypt_database.unwrap_or(false)
+}
+
+fn failure(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_AUTH), Some(message))
+}
+
+// A missing or empty file is a new database, neither plaintext nor encrypted
+fn is_plaintext(path: &Path) -> Option<bool> {
+    use std::io::Read;
+    let mut header = [0u8; 16];
+    let mut file = std::fs::File::open(path).ok()?;
+    file.read_exact(&mut header).ok()?;
+    Some(header == SQLITE_HEADER)
+}
+
+// Raw 256-bit key in SQLCipher's blob-literal form, so no key derivation runs
+#[cfg(feature = "sqlcipher")]
+fn key_literal(hex_key: &str) -> String {
+    format!("\"x'{}'\"", hex_key)
+}
+
+#[cfg(feature = "sqlcipher")]
+fn generate_key() -> Result<String, String> {
+    use std::io::Read;
+    let mut bytes = [0u8; 32];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .map_err(|e| format!("Failed to generate key: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Read once per run; every init_db() needs it
+#[cfg(feature = "sqlcipher")]
+static KEY: OnceLock<String> = OnceLock::new();
+
+#[cfg(feature = "sqlcipher")]
+fn keychain_key() -> Option<String> {
+    if let Some(key) = KEY.get() {
+        return Some(key.clone());
+    }
+    let out = Command::new("security")
+        .args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-a", KEYCHAIN_ACCOUNT, "-w"])
+        .output()
+        .ok()?;
+    let key = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !key.is_empty()).then(|| KEY.get_or_init(|| key).clone())
+}
+
+// The command goes to `security -i` on stdin, so the key never shows up in
+// another process's view of our arguments (`ps`). Interactive mode exits 0
+// even when a command fails, so the key is read back to check.
+#[cfg(feature = "sqlcipher")]
+fn store_keychain_key(key: &str) -> Result<(), String> {
+    use std::io::Write;
+    let mut child = Command::new("security")
+        .arg("-i")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run security: {}", e))?;
+    let command = format!("add-generic-password -U -s {} -a {} -w {}\n", KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT, key);
+    let written = child.stdin.take().map(|mut stdin| stdin.write_all(command.as_bytes()));
+    let status = child.wait().map_err(|e| format!("Failed to run security: {}", e))?;
+    if status.success() && matches!(written, Some(Ok(()))) && keychain_key().as_deref() == Some(key) {
+        Ok(())
+    } else {
+        Err("Failed to store database key in the keychain".to_string())
+    }
+}
+
+// Existing key, or a new one saved to the keychain first so it can't be lost
+#[cfg(feature = "sqlcipher")]
+fn database_key() -> Result<String, String> {
+    if let Some(key) = keychain_key() {
+        return Ok(key);
+    }
+    if !cfg!(target_os = "macos") {
+        return Err("Database encryption needs the macOS keychain".to_string());
+    }
+    let key = generate_key()?;
+    store_keychain_key(&key)?;
+    Ok(key)
+}
+
+// Re-key a database file by exporting it into a fresh one; `None` is plaintext
+#[cfg(feature = "sqlcipher")]
+fn convert(path: &Path, from_key: Option<&str>, to_key: Option<&str>) -> Result<(), rusqlite::Error> {
+    let tmp = path.with_extension("db.converting");
+    let _ = std::fs::remove_file(&tmp);
+    {
+        let conn = Connection::open(path)?;
+        if let Some(key) = from_key {
+            conn.execute_batch(&format!("PRAGMA key = {};", key_literal(key)))?;
+        }
+        // sqlcipher_export copies tables and indexes but not user_version
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let target_key = to_key.map(key_literal).unwrap_or_else(|| "''".to_string());
+        conn.execute_batch(&format!(
+            "ATTACH DATABASE '{}' AS converted KEY {};
+             SELECT sqlcipher_export('converted');
+             PRAGMA converted.user_version = {};
+             DETACH DATABASE converted;",
+            tmp.display().to_string().replace('\'', "''"),
+            target_key,
+            version
+        ))?;
+    }
+    std::fs::rename(&tmp, path).map_err(|_| failure(format!("Failed to replace {}", path.display())))
+}
+
+#[cfg(feature = "sqlcipher")]
+pub(crate) fn open(path: &Path) -> Result<Connection, rusqlite::Error> {
+    let enabled = is_enabled();
+    match (enabled, is_plaintext(path)) {
+        (true, Some(true)) => convert(path, None, Some(&database_key().map_err(failure)?))?,
+        (false, Some(false)) => {
+            let key = keychain_key().ok_or_else(|| failure("Database is encrypted but no key was found".to_string()))?;
+            convert(path, Some(&key), None)?;
+        }
+        _ => {}
+    }
+    let conn = Connection::open(path)?;
+    apply_key(&conn)?;
+    Ok(conn)
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+pub(crate) fn open(path: &Path) -> Result<Connection, rusqlite::Error> {
+    if is_enabled() {
+        return Err(failure("Database encryption needs a build with the sqlcipher feature".to_string()));
+    }
+    if is_plaintext(path) == Some(false) {
+        return Err(failure("Database is encrypted; this build has no SQLCipher support".to_string()));
+    }
+    Connection::open(path)
+}
+
+// For connections opened outside `open` (backups keep the database's
+// encryption, so copies are as protected as the original)
+#[cfg(feature = "sqlcipher")]
+pub(crate) fn apply_key(conn: &Connection) -> Result<(), rusqlite::Error> {
+    if is_enabled() {
+        conn.execute_batch(&format!("PRAGMA key = {};", key_literal(&database_key().map_err(failure)?)))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+pub(crate) fn apply_key(_conn: &Connection) -> Result<(), rusqlite::Error> {
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_database_encryption(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    enabled: bool,
+) -> Result<(), String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    if enabled && !cfg!(feature = "sqlcipher") {
+        return Err("This build has no SQLCipher support".to_string());
+    }
+    // The shared writer connection can't stay open across the conversion
+    store::close();
+    let mut settings = load_saved_settings();
+    let previous = settings.encrypt_database;
+    settings.encrypt_database = Some(enabled);
+    save_settings(&settings);
+    // Opening converts the file; roll the setting back if that fails
+    if let Err(e) = init_db() {
+        settings.encrypt_database = previous;
+        save_settings(&settings);
+        return Err(format!("Failed to convert database: {}", e));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_plaintext_detection() {
+        let path = std::env::temp_dir().join(format!("test_db_plaintext_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(is_plaintext(&path), None);
+        crate::init_test_db(&path).unwrap();
+        assert_eq!(is_plaintext(&path), Some(true));
+        fs::write(&path, [0x5au8; 64]).unwrap();
+        assert_eq!(is_plaintext(&path), Some(false));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn test_generated_key_is_raw_256_bit_hex() {
+        let key = generate_key().unwrap();
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(key_literal("ab"), "\"x'ab'\"");
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn test_convert_round_trip_keeps_rows_and_version() {
+        let path = std::env::temp_dir().join(format!("test_db_sqlcipher_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let conn = crate::init_test_db(&path).unwrap();
+        conn.execute("INSERT INTO usage_history (timestamp, session_percent) VALUES ('2026-01-28T10:00:00', 40)", [])
+            .unwrap();
+        drop(conn);
+
+        let key = generate_key().unwrap();
+        convert(&path, None, Some(&key)).unwrap();
+        assert_eq!(is_plaintext(&path), Some(false));
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(&format!("PRAGMA key = {};", key_literal(&key))).unwrap();
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM usage_history", [], |row| row.get(0)).unwrap();
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 1);
+        assert_eq!(version, crate::MIGRATIONS.len());
+        drop(conn);
+
+        convert(&path, Some(&key), None).unwrap();
+        assert_eq!(is_plaintext(&path), Some(true));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,126 @@
+// The Recent Errors window: the last failed fetches from fetch_log, each with
+// the raw output that failed to parse when there was one. That output is kept
+// per failure under cc-usage-errors/ (the newest `KEEP_RAW_OUTPUTS`), unlike
+// cc-usage-last-error.txt, which only ever holds the latest.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{Manager, Runtime, WebviewWindowBuilder};
+
+use crate::{datadir, dock, ensure_not_guest, init_db, open_file, query_fetch_log, AppState};
+
+pub(crate) const WINDOW_LABEL: &str = "errors";
+const RAW_OUTPUT_DIR: &str = "cc-usage-errors";
+const KEEP_RAW_OUTPUTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct ErrorLogEntry {
+    timestamp: String,
+    trigger: String,
+    duration_ms: i64,
+    error: Option<String>,
+    // Only while the file is still kept
+    raw_output: Option<String>,
+}
+
+fn raw_output_dir() -> PathBuf {
+    datadir::data_dir().join(RAW_OUTPUT_DIR)
+}
+
+// Drop all but the newest `keep` files; names are timestamps, so they sort by age
+fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect();
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for file in &files[..excess] {
+        let _ = fs::remove_file(file);
+    }
+}
+
+// Save a failed fetch's output, named for the fetch's start. Returns its path.
+pub(crate) fn keep_raw_output(timestamp: &str, raw: &str) -> Option<String> {
+    let dir = raw_output_dir();
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!("{}.txt", timestamp.replace(':', "-")));
+    fs::write(&path, raw).ok()?;
+    prune(&dir, KEEP_RAW_OUTPUTS);
+    Some(path.display().to_string())
+}
+
+fn entries(conn: &rusqlite::Connection, limit: u32) -> Vec<ErrorLogEntry> {
+    query_fetch_log(conn, limit, true)
+        .into_iter()
+        .map(|entry| ErrorLogEntry {
+            timestamp: entry.timestamp,
+            trigger: entry.trigger,
+            duration_ms: entry.duration_ms,
+            error: entry.error,
+            raw_output: entry.raw_output.filter(|path| Path::new(path).exists()),
+        })
+        .collect()
+}
+
+// The last `limit` (default 50) failed fetches, newest first
+#[tauri::command]
+pub(crate) fn get_error_log(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    limit: Option<u32>,
+) -> Result<Vec<ErrorLogEntry>, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    Ok(entries(&conn, limit.unwrap_or(50)))
+}
+
+// Only files this module saved can be opened
+#[tauri::command]
+pub(crate) fn open_raw_output(state: tauri::State<'_, Arc<Mutex<AppState>>>, path: String) -> Result<(), String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let path = PathBuf::from(path);
+    if path.parent() != Some(raw_output_dir().as_path()) || !path.is_file() {
+        return Err("Not a saved fetch output".to_string());
+    }
+    open_file(&path);
+    Ok(())
+}
+
+pub(crate) fn open_window<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.set_focus();
+    } else {
+        let _ = WebviewWindowBuilder::new(app, WINDOW_LABEL, tauri::WebviewUrl::App("errors.html".into()))
+            .title("Recent Errors")
+            .inner_size(560.0, 420.0)
+            .resizable(true)
+            .build();
+        dock::update(app, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_keeps_the_newest_outputs() {
+        let dir = std::env::temp_dir().join(format!("test_errorlog_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for day in 1..=5 {
+            fs::write(dir.join(format!("2026-01-0{}T09-00-00.txt", day)), "raw").unwrap();
+        }
+        prune(&dir, 2);
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["2026-01-04T09-00-00.txt", "2026-01-05T09-00-00.txt"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
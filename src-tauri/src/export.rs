@@ -0,0 +1,546 @@
+// History export (CSV/JSON) for analysis outside the app, and import of
+// readings recorded elsewhere
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use std::sync::{Arc, Mutex};
+
+use crate::locale::{text, Msg};
+use crate::{choose_save_path, ensure_not_guest, init_db, insert_usage_for, profiles, AppState, UsageData, UsageItem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+// One usage_history row with every stored column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct HistoryRecord {
+    pub(crate) timestamp: String,
+    pub(crate) session_percent: Option<i32>,
+    pub(crate) session_resets: Option<String>,
+    pub(crate) weekly_percent: Option<i32>,
+    pub(crate) weekly_resets: Option<String>,
+    pub(crate) sonnet_percent: Option<i32>,
+    pub(crate) sonnet_resets: Option<String>,
+    // Missing from exports made before the Opus limit was tracked
+    #[serde(default)]
+    pub(crate) opus_percent: Option<i32>,
+    #[serde(default)]
+    pub(crate) opus_resets: Option<String>,
+    // Account profile (None for the default); missing from files written before
+    // profiles existed
+    #[serde(default)]
+    pub(crate) profile: Option<String>,
+}
+
+impl HistoryRecord {
+    pub(crate) fn from_usage(usage: &UsageData) -> Self {
+        HistoryRecord {
+            timestamp: usage.timestamp.clone().unwrap_or_default(),
+            session_percent: usage.session.percent,
+            session_resets: usage.session.resets.clone(),
+            weekly_percent: usage.weekly_all.percent,
+            weekly_resets: usage.weekly_all.resets.clone(),
+            sonnet_percent: usage.weekly_sonnet.percent,
+            sonnet_resets: usage.weekly_sonnet.resets.clone(),
+            opus_percent: usage.weekly_opus.percent,
+            opus_resets: usage.weekly_opus.resets.clone(),
+            profile: profiles::active_name(),
+        }
+    }
+
+    pub(crate) fn to_usage(&self) -> UsageData {
+        UsageData {
+            timestamp: Some(self.timestamp.clone()),
+            session: UsageItem {
+                percent: self.session_percent,
+                resets: self.session_resets.clone(),
+            },
+            weekly_all: UsageItem {
+                percent: self.weekly_percent,
+                resets: self.weekly_resets.clone(),
+            },
+            weekly_sonnet: UsageItem {
+                percent: self.sonnet_percent,
+                resets: self.sonnet_resets.clone(),
+            },
+            weekly_opus: UsageItem {
+                percent: self.opus_percent,
+                resets: self.opus_resets.clone(),
+            },
+            error: None,
+        }
+    }
+}
+
+pub(crate) const CSV_HEADER: &str =
+    "timestamp,session_percent,session_resets,weekly_percent,weekly_resets,sonnet_percent,sonnet_resets,opus_percent,opus_resets,profile";
+
+// The active profile's readings since `cutoff`
+pub(crate) fn query_history_records(conn: &Connection, cutoff: &str) -> Vec<HistoryRecord> {
+    conn.prepare(
+        "SELECT timestamp, session_percent, session_resets, weekly_percent, weekly_resets, sonnet_percent, sonnet_resets,
+                opus_percent, opus_resets, profile
+         FROM usage_history
+         WHERE timestamp >= ?1 AND profile IS ?2
+         ORDER BY timestamp ASC",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![cutoff, profiles::active_name()], |row| {
+            Ok(HistoryRecord {
+                timestamp: row.get(0)?,
+                session_percent: row.get(1)?,
+                session_resets: row.get(2)?,
+                weekly_percent: row.get(3)?,
+                weekly_resets: row.get(4)?,
+                sonnet_percent: row.get(5)?,
+                sonnet_resets: row.get(6)?,
+                opus_percent: row.get(7)?,
+                opus_resets: row.get(8)?,
+                profile: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .unwrap_or_default()
+}
+
+// Quote a CSV field only when it contains a delimiter, quote, or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(|v| csv_field(&v.to_string())).unwrap_or_default()
+}
+
+pub(crate) fn records_to_csv(records: &[HistoryRecord]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for r in records {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&r.timestamp),
+            csv_opt(&r.session_percent),
+            csv_opt(&r.session_resets),
+            csv_opt(&r.weekly_percent),
+            csv_opt(&r.weekly_resets),
+            csv_opt(&r.sonnet_percent),
+            csv_opt(&r.sonnet_resets),
+            csv_opt(&r.opus_percent),
+            csv_opt(&r.opus_resets),
+            csv_opt(&r.profile),
+        ));
+    }
+    out
+}
+
+pub(crate) fn render_records(records: &[HistoryRecord], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Csv => Ok(records_to_csv(records)),
+        ExportFormat::Json => serde_json::to_string_pretty(records).map_err(|e| e.to_string()),
+    }
+}
+
+// For sharing exports publicly: timestamps are shifted so the first reading is
+// at 1970-01-01T00:00:00, keeping the spacing between readings, and the reset
+// strings (which give away the real clock time) and profile names are dropped.
+// History records hold no project names; those only live in the transcripts.
+pub(crate) fn anonymize_records(records: Vec<HistoryRecord>) -> Vec<HistoryRecord> {
+    let parse = |r: &HistoryRecord| {
+        normalize_timestamp(&r.timestamp)
+            .and_then(|ts| chrono::NaiveDateTime::parse_from_str(&ts, "%Y-%m-%dT%H:%M:%S").ok())
+    };
+    let Some(first) = records.iter().filter_map(parse).min() else {
+        return Vec::new();
+    };
+    let epoch = chrono::DateTime::UNIX_EPOCH.naive_utc();
+    records
+        .iter()
+        .filter_map(|r| {
+            let shifted = epoch + (parse(r)? - first);
+            Some(HistoryRecord {
+                timestamp: shifted.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                session_resets: None,
+                weekly_resets: None,
+                sonnet_resets: None,
+                opus_resets: None,
+                profile: None,
+                ..r.clone()
+            })
+        })
+        .collect()
+}
+
+// Cutoff for an optional day range; no range, or one reaching past the
+// earliest representable date, exports everything
+pub(crate) fn cutoff_for_days(days: Option<i32>) -> String {
+    days.and_then(|days| chrono::Local::now().checked_sub_signed(chrono::Duration::days(days as i64)))
+        .map(|cutoff| cutoff.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn write_export(path: &Path, format: ExportFormat, days: Option<i32>, anonymize: bool) -> Result<usize, String> {
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let mut records = query_history_records(&conn, &cutoff_for_days(days));
+    if anonymize {
+        records = anonymize_records(records);
+    }
+    let content = render_records(&records, format)?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(records.len())
+}
+
+// Anonymized exports leave the date out of the name too
+fn default_export_name(format: ExportFormat, anonymize: bool) -> String {
+    if anonymize {
+        return format!("cc-usage-history-anonymized.{}", format.extension());
+    }
+    format!(
+        "cc-usage-history-{}.{}",
+        chrono::Local::now().format("%Y-%m-%d"),
+        format.extension()
+    )
+}
+
+// Ask for a destination and export. Returns the written path, or None if cancelled.
+pub(crate) fn export_with_dialog(
+    format: ExportFormat,
+    days: Option<i32>,
+    anonymize: bool,
+) -> Result<Option<PathBuf>, String> {
+    let Some(path) = choose_save_path(text(Msg::ExportHistoryPrompt), &default_export_name(format, anonymize)) else {
+        return Ok(None);
+    };
+    write_export(&path, format, days, anonymize)?;
+    Ok(Some(path))
+}
+
+#[tauri::command]
+pub(crate) async fn export_history(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    format: ExportFormat,
+    days: Option<i32>,
+    anonymize: Option<bool>,
+) -> Result<Option<String>, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let anonymize = anonymize.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || export_with_dialog(format, days, anonymize))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+        .map(|path| path.map(|p| p.display().to_string()))
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct ImportSummary {
+    pub(crate) imported: usize,
+    pub(crate) duplicates: usize,
+    pub(crate) invalid: Vec<String>,
+}
+
+// Split one CSV line, honoring double-quoted fields with "" escapes
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Parse CSV with a header row; columns are matched by name so order doesn't matter
+fn parse_csv_records(content: &str) -> Result<Vec<Result<HistoryRecord, String>>, String> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = parse_csv_line(lines.next().ok_or("Empty file")?);
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let ts_col = column("timestamp").ok_or("Missing 'timestamp' column")?;
+    let cols = [
+        column("session_percent"),
+        column("session_resets"),
+        column("weekly_percent"),
+        column("weekly_resets"),
+        column("sonnet_percent"),
+        column("sonnet_resets"),
+        column("opus_percent"),
+        column("opus_resets"),
+        column("profile"),
+    ];
+
+    Ok(lines
+        .enumerate()
+        .map(|(i, line)| {
+            let fields = parse_csv_line(line);
+            let text = |col: Option<usize>| {
+                col.and_then(|c| fields.get(c))
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            let percent = |col: Option<usize>| -> Result<Option<i32>, String> {
+                text(col)
+                    .map(|v| v.parse::<i32>().map_err(|_| format!("line {}: bad percent '{}'", i + 2, v)))
+                    .transpose()
+            };
+            Ok(HistoryRecord {
+                timestamp: text(Some(ts_col)).ok_or(format!("line {}: missing timestamp", i + 2))?,
+                session_percent: percent(cols[0])?,
+                session_resets: text(cols[1]),
+                weekly_percent: percent(cols[2])?,
+                weekly_resets: text(cols[3]),
+                sonnet_percent: percent(cols[4])?,
+                sonnet_resets: text(cols[5]),
+                opus_percent: percent(cols[6])?,
+                opus_resets: text(cols[7]),
+                profile: text(cols[8]),
+            })
+        })
+        .collect())
+}
+
+fn parse_import_file(path: &Path, content: &str) -> Result<Vec<Result<HistoryRecord, String>>, String> {
+    let is_json = path.extension().and_then(|e| e.to_str()) == Some("json")
+        || content.trim_start().starts_with('[');
+    if is_json {
+        let records: Vec<HistoryRecord> =
+            serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+        Ok(records.into_iter().map(Ok).collect())
+    } else {
+        parse_csv_records(content)
+    }
+}
+
+// Normalize to second precision so "…T14:00:00.123456" and "…T14:00:00" match
+fn normalize_timestamp(ts: &str) -> Option<String> {
+    let clean = ts.trim().split('.').next()?;
+    chrono::NaiveDateTime::parse_from_str(clean, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(clean, "%Y-%m-%d %H:%M:%S"))
+        .ok()
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+fn validate_record(mut record: HistoryRecord) -> Result<HistoryRecord, String> {
+    record.timestamp = normalize_timestamp(&record.timestamp)
+        .ok_or(format!("invalid timestamp '{}'", record.timestamp))?;
+    for pct in [record.session_percent, record.weekly_percent, record.sonnet_percent, record.opus_percent]
+        .into_iter()
+        .flatten()
+    {
+        if !(0..=100).contains(&pct) {
+            return Err(format!("{}: percent {} out of range", record.timestamp, pct));
+        }
+    }
+    if record.session_percent.is_none() && record.weekly_percent.is_none() {
+        return Err(format!("{}: no usage values", record.timestamp));
+    }
+    Ok(record)
+}
+
+// `machine` tags the rows as synced from another machine (see sync.rs)
+pub(crate) fn import_records(
+    conn: &mut Connection,
+    records: Vec<Result<HistoryRecord, String>>,
+    machine: Option<&str>,
+) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary::default();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut exists = tx
+            .prepare(
+                "SELECT 1 FROM usage_history
+                 WHERE substr(timestamp, 1, 19) = ?1 AND profile IS ?2 AND machine IS ?3 LIMIT 1",
+            )
+            .map_err(|e| e.to_string())?;
+        for record in records {
+            let record = match record.and_then(validate_record) {
+                Ok(record) => record,
+                Err(e) => {
+                    summary.invalid.push(e);
+                    continue;
+                }
+            };
+            // Also catches duplicates within the file, since earlier rows are already inserted.
+            // Another machine's reading in the same second as one of ours isn't a duplicate.
+            if exists
+                .exists(params![record.timestamp, record.profile, machine])
+                .map_err(|e| e.to_string())?
+            {
+                summary.duplicates += 1;
+                continue;
+            }
+            // Each reading keeps the profile it was recorded for
+            let id = insert_usage_for(&tx, &record.to_usage(), record.profile.as_deref())
+                .map_err(|e| e.to_string())?;
+            if let Some(machine) = machine {
+                tx.execute("UPDATE usage_history SET machine = ?1 WHERE id = ?2", params![machine, id])
+                    .map_err(|e| e.to_string())?;
+            }
+            summary.imported += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub(crate) async fn import_history(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let records = parse_import_file(&path, &content)?;
+        let mut conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+        import_records(&mut conn, records, None)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record() -> HistoryRecord {
+        HistoryRecord {
+            timestamp: "2026-01-28T14:00:00".to_string(),
+            session_percent: Some(25),
+            session_resets: Some("3pm".to_string()),
+            weekly_percent: Some(50),
+            weekly_resets: Some("Jan 29, 5pm".to_string()),
+            sonnet_percent: None,
+            sonnet_resets: None,
+            opus_percent: None,
+            opus_resets: None,
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn test_cutoff_for_days() {
+        assert_eq!(cutoff_for_days(None), "");
+        assert_eq!(cutoff_for_days(Some(1)).len(), 19);
+        // Past chrono's range: everything, rather than a panic
+        assert_eq!(cutoff_for_days(Some(i32::MAX)), "");
+    }
+
+    #[test]
+    fn test_records_to_csv_quotes_commas() {
+        let csv = records_to_csv(&[sample_record()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "2026-01-28T14:00:00,25,3pm,50,\"Jan 29, 5pm\",,,,,");
+    }
+
+    #[test]
+    fn test_render_records_json_roundtrip() {
+        let json = render_records(&[sample_record()], ExportFormat::Json).unwrap();
+        let parsed: Vec<HistoryRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, vec![sample_record()]);
+    }
+
+    #[test]
+    fn test_parse_csv_line_handles_quotes() {
+        assert_eq!(
+            parse_csv_line("a,\"b, c\",\"say \"\"hi\"\"\","),
+            vec!["a", "b, c", "say \"hi\"", ""]
+        );
+    }
+
+    #[test]
+    fn test_csv_export_import_roundtrip() {
+        let csv = records_to_csv(&[sample_record()]);
+        let parsed = parse_csv_records(&csv).unwrap();
+        assert_eq!(parsed, vec![Ok(sample_record())]);
+    }
+
+    #[test]
+    fn test_validate_record_rejects_out_of_range() {
+        let mut record = sample_record();
+        record.session_percent = Some(140);
+        assert!(validate_record(record).is_err());
+        let mut record = sample_record();
+        record.timestamp = "yesterday".to_string();
+        assert!(validate_record(record).is_err());
+    }
+
+    #[test]
+    fn test_anonymize_shifts_to_epoch_and_drops_resets() {
+        let mut later = sample_record();
+        later.timestamp = "2026-01-28T16:30:00.123456".to_string();
+        later.sonnet_resets = Some("Jan 30, 9am".to_string());
+        later.opus_resets = Some("Jan 30, 9am".to_string());
+        let mut first = sample_record();
+        first.timestamp = "2026-01-28T14:00:00".to_string();
+        let records = anonymize_records(vec![later, first]);
+        assert_eq!(records[0].timestamp, "1970-01-01T02:30:00");
+        assert_eq!(records[1].timestamp, "1970-01-01T00:00:00");
+        assert!(records.iter().all(|r| {
+            r.session_resets.is_none() && r.weekly_resets.is_none() && r.sonnet_resets.is_none() && r.opus_resets.is_none()
+        }));
+        assert_eq!(records[0].session_percent, sample_record().session_percent);
+    }
+
+    #[test]
+    fn test_import_deduplicates_by_timestamp() {
+        let db_path = std::env::temp_dir().join(format!("test_db_import_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent) VALUES ('2026-01-28T14:00:00.123456', 10)",
+            [],
+        )
+        .unwrap();
+
+        let mut newer = sample_record();
+        newer.timestamp = "2026-01-28T15:00:00".to_string();
+        let records = vec![
+            Ok(sample_record()),          // matches existing row at second precision
+            Ok(newer.clone()),
+            Ok(newer),                    // duplicate within the file
+            Err("line 5: bad percent 'x'".to_string()),
+        ];
+        let summary = import_records(&mut conn, records, None).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.duplicates, 2);
+        assert_eq!(summary.invalid.len(), 1);
+
+        // The same second synced from another machine is a reading of its own
+        let summary = import_records(&mut conn, vec![Ok(sample_record())], Some("laptop")).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.duplicates, 0);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
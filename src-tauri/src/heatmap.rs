@@ -0,0 +1,114 @@
+// When usage actually goes up: the average per-reading increase (the stored
+// Δ%) in each hour-of-day × day-of-week bucket, for the charts window's
+// heatmap
+use chrono::{DateTime, Datelike, Local, Timelike};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{init_db, parse_reading_timestamp, profiles};
+
+// Metrics with a stored delta column
+const METRICS: [&str; 3] = ["session", "weekly", "sonnet"];
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct Heatmap {
+    metric: String,
+    days: i32,
+    // cells[weekday][hour]: average points added in that hour on that weekday,
+    // Monday first
+    cells: Vec<Vec<f64>>,
+    // The largest cell, for scaling the colors
+    max: f64,
+}
+
+// Increases by bucket, averaged over how many times each weekday came round
+// between `from` and `to`. Drops (resets) carry the new window's usage as
+// their delta, which is still usage gained.
+pub(crate) fn build(metric: &str, days: i32, deltas: &[(String, i32)], from: DateTime<Local>, to: DateTime<Local>) -> Heatmap {
+    let mut totals = vec![vec![0i64; 24]; 7];
+    for (timestamp, delta) in deltas {
+        if let Some(at) = parse_reading_timestamp(timestamp) {
+            totals[at.weekday().num_days_from_monday() as usize][at.hour() as usize] += *delta as i64;
+        }
+    }
+    let mut occurrences = [0i64; 7];
+    let mut date = from.date_naive();
+    while date <= to.date_naive() {
+        occurrences[date.weekday().num_days_from_monday() as usize] += 1;
+        let Some(next) = date.succ_opt() else {
+            break;
+        };
+        date = next;
+    }
+    let cells: Vec<Vec<f64>> = totals
+        .iter()
+        .zip(occurrences)
+        .map(|(hours, count)| {
+            hours
+                .iter()
+                .map(|total| if count > 0 { (*total as f64 / count as f64 * 10.0).round() / 10.0 } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    let max = cells.iter().flatten().copied().fold(0.0, f64::max);
+    Heatmap {
+        metric: metric.to_string(),
+        days,
+        cells,
+        max,
+    }
+}
+
+fn query_deltas(conn: &Connection, metric: &str, since: &str) -> rusqlite::Result<Vec<(String, i32)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT timestamp, {}_delta FROM usage_history
+         WHERE timestamp >= ?1 AND {}_delta > 0 AND profile IS ?2",
+        metric, metric
+    ))?;
+    let rows = stmt.query_map(params![since, profiles::active_name()], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+// The last `days` (default 28) of the `metric` (session, weekly or sonnet;
+// default weekly)
+#[tauri::command]
+pub(crate) fn get_heatmap(days: Option<i32>, metric: Option<String>) -> Result<Heatmap, String> {
+    let days = days.unwrap_or(28).max(1);
+    let metric = metric.unwrap_or_else(|| "weekly".to_string());
+    if !METRICS.contains(&metric.as_str()) {
+        return Err(format!("Unknown metric: {}", metric));
+    }
+    let now = Local::now();
+    let from = now - chrono::Duration::days(days as i64);
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let deltas = query_deltas(&conn, &metric, &from.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .map_err(|e| format!("Failed to query history: {}", e))?;
+    Ok(build(&metric, days, &deltas, from, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_heatmap_averages_increases_per_weekday_and_hour() {
+        // Two weeks ending Wednesday 2026-01-28: Wednesdays come round three
+        // times, Mondays twice
+        let from = Local.with_ymd_and_hms(2026, 1, 14, 0, 0, 0).unwrap();
+        let to = Local.with_ymd_and_hms(2026, 1, 28, 23, 0, 0).unwrap();
+        let deltas = [
+            ("2026-01-19T09:15:00".to_string(), 4),
+            ("2026-01-19T09:45:00".to_string(), 2),
+            ("2026-01-26T09:30:00".to_string(), 4),
+            ("2026-01-28T14:00:00".to_string(), 3),
+        ];
+        let heatmap = build("weekly", 14, &deltas, from, to);
+        assert_eq!(heatmap.cells.len(), 7);
+        assert_eq!(heatmap.cells[0].len(), 24);
+        assert_eq!(heatmap.cells[0][9], 5.0);
+        assert_eq!(heatmap.cells[2][14], 1.0);
+        assert_eq!(heatmap.cells[4][9], 0.0);
+        assert_eq!(heatmap.max, 5.0);
+    }
+}
@@ -0,0 +1,174 @@
+// `cc-usage history [--days N] [--format table|csv|json]`: the recorded
+// readings from the history database, for the terminal or a pipe. CSV and
+// JSON are the same as Export History's files; the table, the default, has
+// just the percentages. The last 7 days unless `--days` says otherwise.
+use std::io::{ErrorKind, Write};
+
+use crate::export::{self, ExportFormat, HistoryRecord};
+use crate::{init_db, Settings};
+
+const DEFAULT_DAYS: i32 = 7;
+// A century; more than any history, and far from chrono's date range
+const MAX_DAYS: i32 = 36_500;
+const USAGE: &str = "usage: cc-usage history [--days N] [--format table|csv|json]";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    Table,
+    Export(ExportFormat),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Options {
+    days: i32,
+    format: Format,
+}
+
+pub(crate) fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        days: DEFAULT_DAYS,
+        format: Format::Table,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--days" => {
+                let days = value()?;
+                options.days = days
+                    .parse()
+                    .ok()
+                    .filter(|d| *d > 0 && *d <= MAX_DAYS)
+                    .ok_or_else(|| format!("--days must be a number from 1 to {} (got {})", MAX_DAYS, days))?;
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "table" => Format::Table,
+                    "csv" => Format::Export(ExportFormat::Csv),
+                    "json" => Format::Export(ExportFormat::Json),
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+// Timestamp and percentage columns, "-" where a limit had no reading
+pub(crate) fn table(records: &[HistoryRecord]) -> String {
+    let mut out = format!("{:<19}  {:>7}  {:>6}  {:>6}  {:>4}\n", "timestamp", "session", "weekly", "sonnet", "opus");
+    let cell = |p: Option<i32>| p.map_or("-".to_string(), |p| format!("{}%", p));
+    for r in records {
+        let timestamp: String = r.timestamp.chars().take(19).collect();
+        out.push_str(&format!(
+            "{:<19}  {:>7}  {:>6}  {:>6}  {:>4}\n",
+            timestamp,
+            cell(r.session_percent),
+            cell(r.weekly_percent),
+            cell(r.sonnet_percent),
+            cell(r.opus_percent)
+        ));
+    }
+    out
+}
+
+// Exit code 1 when the database can't be read or in guest mode, 2 for bad options
+pub(crate) fn print(args: &[String], settings: &Settings) -> i32 {
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{} ({})", e, USAGE);
+            return 2;
+        }
+    };
+    if settings.guest_mode.unwrap_or(false) {
+        eprintln!("History isn't available in guest mode");
+        return 1;
+    }
+    let conn = match init_db() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Failed to open database: {}", e);
+            return 1;
+        }
+    };
+    let records = export::query_history_records(&conn, &export::cutoff_for_days(Some(options.days)));
+    let output = match options.format {
+        Format::Table => Ok(table(&records)),
+        Format::Export(format) => export::render_records(&records, format),
+    };
+    match output {
+        Ok(output) => write_output(&output),
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// print! panics when the reader goes away (`cc-usage history | head`); a
+// closed pipe just means nobody wants the rest
+fn write_output(output: &str) -> i32 {
+    let mut stdout = std::io::stdout().lock();
+    let newline = if output.ends_with('\n') { "" } else { "\n" };
+    let written = stdout
+        .write_all(output.as_bytes())
+        .and_then(|_| stdout.write_all(newline.as_bytes()))
+        .and_then(|_| stdout.flush());
+    match written {
+        Ok(()) => 0,
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => 0,
+        Err(e) => {
+            eprintln!("Failed to write output: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_options_and_table() {
+        assert_eq!(
+            parse_options(&[]),
+            Ok(Options {
+                days: 7,
+                format: Format::Table
+            })
+        );
+        assert_eq!(
+            parse_options(&args(&["--format", "csv", "--days", "30"])),
+            Ok(Options {
+                days: 30,
+                format: Format::Export(ExportFormat::Csv)
+            })
+        );
+        assert!(parse_options(&args(&["--days", "0"])).is_err());
+        assert!(parse_options(&args(&["--days", "999999999"])).is_err());
+        assert!(parse_options(&args(&["--days"])).is_err());
+        assert!(parse_options(&args(&["--format", "xml"])).is_err());
+        assert!(parse_options(&args(&["--verbose"])).is_err());
+
+        let record = HistoryRecord::from_usage(&{
+            let mut usage = crate::UsageData {
+                timestamp: Some("2026-01-28T10:40:00.123".to_string()),
+                ..Default::default()
+            };
+            usage.session.percent = Some(42);
+            usage.weekly_all.percent = Some(7);
+            usage
+        });
+        assert_eq!(
+            table(&[record]),
+            "timestamp            session  weekly  sonnet  opus\n\
+             2026-01-28T10:40:00      42%      7%       -     -\n"
+        );
+    }
+}
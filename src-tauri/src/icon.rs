@@ -0,0 +1,317 @@
+// Tray icon rendered at runtime from the session percentage: a ring that
+// fills clockwise from 12 o'clock, a battery-style bar, or a sparkline of the
+// last day's session usage, colored by the worst
+// pace indicator. The bundled asterisk PNG is still used until the first
+// reading arrives.
+//
+// In template mode the icon is drawn in black and marked as a template image,
+// so macOS tints it to match light/dark menu bars (and follows appearance
+// changes) on its own.
+use tauri::image::Image;
+
+use crate::trend::{TrendPoint, TREND_HOURS};
+
+// 22pt menu bar icon at 2x
+const ICON_SIZE: u32 = 44;
+// The sparkline needs room for a day of readings
+const SPARKLINE_WIDTH: u32 = 88;
+// Samples per pixel along each axis, for anti-aliased edges
+const SUPERSAMPLE: u32 = 4;
+
+type Rgba = [u8; 4];
+
+// Matching the 🟢🟡🟠🔴 menu indicators
+const GREEN: Rgba = [52, 199, 89, 255];
+const YELLOW: Rgba = [255, 204, 0, 255];
+const ORANGE: Rgba = [255, 149, 0, 255];
+const RED: Rgba = [255, 59, 48, 255];
+const TRACK: Rgba = [128, 128, 128, 110];
+const OUTLINE: Rgba = [128, 128, 128, 255];
+// Template images only use alpha
+const TEMPLATE_FILL: Rgba = [0, 0, 0, 255];
+const TEMPLATE_TRACK: Rgba = [0, 0, 0, 80];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Palette {
+    fill: Rgba,
+    track: Rgba,
+    outline: Rgba,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IconStyle {
+    Gauge,
+    Battery,
+    // The last 24 hours of session usage
+    Sparkline,
+    // The bundled PNG, never re-rendered
+    Static,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IconAppearance {
+    // Colored by pace
+    Status,
+    // Monochrome, adapting to the menu bar
+    Template,
+}
+
+impl IconAppearance {
+    // `icon_appearance` setting: "status" (default) or "template"
+    pub(crate) fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("template") => IconAppearance::Template,
+            _ => IconAppearance::Status,
+        }
+    }
+
+    fn palette(self, status: &str) -> Palette {
+        match self {
+            IconAppearance::Status => Palette {
+                fill: status_color(status),
+                track: TRACK,
+                outline: OUTLINE,
+            },
+            IconAppearance::Template => Palette {
+                fill: TEMPLATE_FILL,
+                track: TEMPLATE_TRACK,
+                outline: TEMPLATE_FILL,
+            },
+        }
+    }
+}
+
+impl IconStyle {
+    // `icon_style` setting; unknown values fall back to the gauge
+    pub(crate) fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("battery") => IconStyle::Battery,
+            Some("sparkline") => IconStyle::Sparkline,
+            Some("static") => IconStyle::Static,
+            _ => IconStyle::Gauge,
+        }
+    }
+}
+
+pub(crate) fn static_icon() -> Image<'static> {
+    let icon_bytes = include_bytes!("../icons/tray-icon.png");
+    Image::from_bytes(icon_bytes).expect("Failed to load tray icon")
+}
+
+fn status_color(indicator: &str) -> Rgba {
+    match indicator {
+        "🔴" => RED,
+        "🟠" => ORANGE,
+        "🟡" => YELLOW,
+        _ => GREEN,
+    }
+}
+
+// Which color (if any) covers the point (x, y), in pixels from the top left
+fn gauge_sample(x: f64, y: f64, size: f64, fraction: f64, palette: Palette) -> Option<Rgba> {
+    let center = size / 2.0;
+    let (dx, dy) = (x - center, y - center);
+    let distance = (dx * dx + dy * dy).sqrt();
+    let outer = center - 1.0;
+    if distance > outer || distance < outer * 0.62 {
+        return None;
+    }
+    // Clockwise from 12 o'clock, 0.0..1.0
+    let angle = dx.atan2(-dy).rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU;
+    Some(if angle < fraction { palette.fill } else { palette.track })
+}
+
+fn battery_sample(x: f64, y: f64, size: f64, fraction: f64, palette: Palette) -> Option<Rgba> {
+    let (left, right) = (2.0, size - 7.0);
+    let (top, bottom) = (size * 0.27, size * 0.73);
+    let stroke = 3.0;
+    // Terminal nub on the right
+    if x >= right && x < right + 3.0 && y >= size * 0.4 && y < size * 0.6 {
+        return Some(palette.outline);
+    }
+    if x < left || x >= right || y < top || y >= bottom {
+        return None;
+    }
+    if x < left + stroke || x >= right - stroke || y < top + stroke || y >= bottom - stroke {
+        return Some(palette.outline);
+    }
+    let inner = left + stroke + 1.0;
+    let width = right - stroke - 1.0 - inner;
+    (x >= inner && x < inner + width * fraction && y >= top + stroke + 1.0 && y < bottom - stroke - 1.0)
+        .then_some(palette.fill)
+}
+
+// RGBA pixels for a width x height image, supersampling `sample` (which color,
+// if any, covers a point) for anti-aliased edges
+fn rasterize(width: u32, height: u32, sample: impl Fn(f64, f64) -> Option<Rgba>) -> Vec<u8> {
+    let samples = (SUPERSAMPLE * SUPERSAMPLE) as f64;
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for py in 0..height {
+        for px in 0..width {
+            // Average the covering colors, weighting by alpha
+            let (mut rgb, mut alpha) = ([0.0f64; 3], 0.0f64);
+            for sy in 0..SUPERSAMPLE {
+                for sx in 0..SUPERSAMPLE {
+                    let x = px as f64 + (sx as f64 + 0.5) / SUPERSAMPLE as f64;
+                    let y = py as f64 + (sy as f64 + 0.5) / SUPERSAMPLE as f64;
+                    if let Some(color) = sample(x, y) {
+                        let a = color[3] as f64 / 255.0;
+                        for i in 0..3 {
+                            rgb[i] += color[i] as f64 * a;
+                        }
+                        alpha += a;
+                    }
+                }
+            }
+            if alpha == 0.0 {
+                pixels.extend_from_slice(&[0, 0, 0, 0]);
+            } else {
+                pixels.extend(rgb.iter().map(|c| (c / alpha).round() as u8));
+                pixels.push((alpha / samples * 255.0).round() as u8);
+            }
+        }
+    }
+    pixels
+}
+
+// Square icon for `percent` (clamped to 0-100) as a gauge or battery
+fn render_pixels(style: IconStyle, percent: i32, size: u32, palette: Palette) -> Vec<u8> {
+    let fraction = percent.clamp(0, 100) as f64 / 100.0;
+    let sample = match style {
+        IconStyle::Battery => battery_sample,
+        _ => gauge_sample,
+    };
+    rasterize(size, size, |x, y| sample(x, y, size as f64, fraction, palette))
+}
+
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - cx).powi(2) + (p.1 - cy).powi(2)).sqrt()
+}
+
+// Session usage over the TREND_HOURS ending at the latest reading, as a
+// line over a faint baseline
+fn render_sparkline(trend: &[TrendPoint], width: u32, height: u32, palette: Palette) -> Vec<u8> {
+    let margin = 3.0;
+    let (w, h) = (width as f64, height as f64);
+    let end = trend.last().map(|(at, _)| *at).unwrap_or_else(chrono::Local::now);
+    let span = (TREND_HOURS * 3600) as f64;
+    let points: Vec<(f64, f64)> = trend
+        .iter()
+        .map(|(at, pct)| {
+            let age = end.signed_duration_since(*at).num_seconds() as f64;
+            let x = margin + (1.0 - (age / span).clamp(0.0, 1.0)) * (w - 2.0 * margin);
+            let y = h - margin - (*pct).clamp(0, 100) as f64 / 100.0 * (h - 2.0 * margin);
+            (x, y)
+        })
+        .collect();
+    rasterize(width, height, |x, y| {
+        let near_line = match points.as_slice() {
+            [] => false,
+            [only] => distance_to_segment((x, y), *only, *only) <= 2.5,
+            _ => points
+                .windows(2)
+                .any(|pair| distance_to_segment((x, y), pair[0], pair[1]) <= 1.75),
+        };
+        if near_line {
+            Some(palette.fill)
+        } else if (y - (h - margin)).abs() <= 0.75 && x >= margin && x <= w - margin {
+            Some(palette.track)
+        } else {
+            None
+        }
+    })
+}
+
+// The icon for the current session percentage (None = no reading yet), filled
+// in the color of the `status` pace indicator unless drawn as a template
+pub(crate) fn tray_icon(
+    style: IconStyle,
+    appearance: IconAppearance,
+    percent: Option<i32>,
+    status: &str,
+    trend: &[TrendPoint],
+) -> Image<'static> {
+    let palette = appearance.palette(status);
+    match (style, percent) {
+        (IconStyle::Static, _) | (_, None) => static_icon(),
+        (IconStyle::Sparkline, Some(_)) => Image::new_owned(
+            render_sparkline(trend, SPARKLINE_WIDTH, ICON_SIZE, palette),
+            SPARKLINE_WIDTH,
+            ICON_SIZE,
+        ),
+        (style, Some(percent)) => Image::new_owned(render_pixels(style, percent, ICON_SIZE, palette), ICON_SIZE, ICON_SIZE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(pixels: &[u8], size: u32, x: u32, y: u32) -> Rgba {
+        let i = ((y * size + x) * 4) as usize;
+        [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+    }
+
+    #[test]
+    fn test_gauge_fills_clockwise_from_top() {
+        let pixels = render_pixels(IconStyle::Gauge, 50, ICON_SIZE, IconAppearance::Status.palette("🔴"));
+        assert_eq!(pixels.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        // On the ring: 3 o'clock is inside the first half, 9 o'clock isn't
+        assert_eq!(pixel(&pixels, ICON_SIZE, 38, 22), RED);
+        assert_eq!(pixel(&pixels, ICON_SIZE, 5, 22), TRACK);
+        // Center and corners stay transparent
+        assert_eq!(pixel(&pixels, ICON_SIZE, 22, 22)[3], 0);
+        assert_eq!(pixel(&pixels, ICON_SIZE, 0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_battery_fill_tracks_percent() {
+        let empty = render_pixels(IconStyle::Battery, 0, ICON_SIZE, IconAppearance::Status.palette("🟢"));
+        let full = render_pixels(IconStyle::Battery, 100, ICON_SIZE, IconAppearance::Status.palette("🟢"));
+        assert_eq!(pixel(&empty, ICON_SIZE, 20, 22)[3], 0);
+        assert_eq!(pixel(&full, ICON_SIZE, 20, 22), GREEN);
+        assert_eq!(pixel(&full, ICON_SIZE, 2, 22), OUTLINE);
+    }
+
+    #[test]
+    fn test_template_is_monochrome() {
+        let palette = IconAppearance::from_setting(Some("template")).palette("🔴");
+        let pixels = render_pixels(IconStyle::Gauge, 50, ICON_SIZE, palette);
+        assert_eq!(pixel(&pixels, ICON_SIZE, 38, 22), TEMPLATE_FILL);
+        assert_eq!(pixel(&pixels, ICON_SIZE, 5, 22), TEMPLATE_TRACK);
+        assert_eq!(IconAppearance::from_setting(None), IconAppearance::Status);
+    }
+
+    #[test]
+    fn test_sparkline_follows_readings() {
+        let end = chrono::Local::now();
+        let trend = vec![(end - chrono::Duration::hours(TREND_HOURS), 0), (end, 100)];
+
+        let palette = IconAppearance::Status.palette("🟢");
+        let pixels = render_sparkline(&trend, SPARKLINE_WIDTH, ICON_SIZE, palette);
+        assert_eq!(pixels.len(), (SPARKLINE_WIDTH * ICON_SIZE * 4) as usize);
+        let at = |x: u32, y: u32| {
+            let i = ((y * SPARKLINE_WIDTH + x) * 4) as usize;
+            [pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3]]
+        };
+        // From the bottom left (0%) to the top right (100%)
+        assert_eq!(at(3, 40), GREEN);
+        assert_eq!(at(84, 3), GREEN);
+        assert_eq!(at(84, 30)[3], 0);
+    }
+
+    #[test]
+    fn test_style_from_setting() {
+        assert_eq!(IconStyle::from_setting(Some("battery")), IconStyle::Battery);
+        assert_eq!(IconStyle::from_setting(Some("bogus")), IconStyle::Gauge);
+        assert_eq!(IconStyle::from_setting(None), IconStyle::Gauge);
+    }
+}
@@ -0,0 +1,230 @@
+// Build, environment, and path info for About and bug reports, and the
+// fuller diagnostics behind the diagnostics panel
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    datadir, ensure_not_guest, get_cache_path, get_db_path, get_error_log_path, get_log_path, get_settings_path,
+    init_db, parse_reading_timestamp, query_fetch_log, transcripts, AppState, FetchLogEntry,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DataPaths {
+    data_dir: String,
+    cache: String,
+    settings: String,
+    database: String,
+    error_log: String,
+    log: String,
+    transcripts: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AppInfo {
+    pub(crate) version: String,
+    pub(crate) git_commit: String,
+    pub(crate) build_date: String,
+    pub(crate) os: String,
+    pub(crate) claude_version: Option<String>,
+    pub(crate) paths: DataPaths,
+}
+
+fn build_date() -> String {
+    env!("CC_USAGE_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn os_description() -> String {
+    let release = if cfg!(target_os = "macos") {
+        command_output("sw_vers", &["-productVersion"])
+    } else {
+        command_output("uname", &["-r"])
+    };
+    match release {
+        Some(release) => format!("{} {} ({})", std::env::consts::OS, release, std::env::consts::ARCH),
+        None => format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+    }
+}
+
+pub(crate) fn data_paths() -> DataPaths {
+    DataPaths {
+        data_dir: datadir::data_dir().display().to_string(),
+        cache: get_cache_path().display().to_string(),
+        settings: get_settings_path().display().to_string(),
+        database: get_db_path().display().to_string(),
+        error_log: get_error_log_path().display().to_string(),
+        log: get_log_path().display().to_string(),
+        transcripts: transcripts::projects_dir().display().to_string(),
+    }
+}
+
+// Runs `claude --version`, so call off the main thread
+pub(crate) fn collect_app_info() -> AppInfo {
+    AppInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("CC_USAGE_GIT_COMMIT").to_string(),
+        build_date: build_date(),
+        os: os_description(),
+        claude_version: command_output("claude", &["--version"]),
+        paths: data_paths(),
+    }
+}
+
+// Paths are left out in guest mode since they include the home directory
+// `last_fetch` is the timestamp of the current (last successful) reading
+pub(crate) fn format_app_info(info: &AppInfo, include_paths: bool, last_fetch: Option<&str>) -> String {
+    let last_fetch = match last_fetch {
+        Some(ts) => parse_reading_timestamp(ts)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| ts.to_string()),
+        None => "never".to_string(),
+    };
+    let mut text = format!(
+        "Version {} ({}, built {})\nOS: {}\nClaude CLI: {}\nLast successful fetch: {}",
+        info.version,
+        info.git_commit,
+        info.build_date,
+        info.os,
+        info.claude_version.as_deref().unwrap_or("not found"),
+        last_fetch,
+    );
+    if include_paths {
+        text.push_str(&format!(
+            "\n\nData folder: {}\nCache: {}\nDatabase: {}\nLog: {}",
+            info.paths.data_dir, info.paths.cache, info.paths.database, info.paths.log
+        ));
+    }
+    text
+}
+
+// The programs the fetch script runs
+const TOOLS: [(&str, &str); 3] = [("tmux", "-V"), ("claude", "--version"), ("python3", "--version")];
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ToolCheck {
+    name: String,
+    // None when it isn't on PATH
+    path: Option<String>,
+    version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DatabaseInfo {
+    readings: Option<i64>,
+    size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Diagnostics {
+    app: AppInfo,
+    tools: Vec<ToolCheck>,
+    // The newest fetch_log row: strategy, duration, and outcome
+    last_fetch: Option<FetchLogEntry>,
+    database: DatabaseInfo,
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn check_tool(name: &str, version_flag: &str) -> ToolCheck {
+    let path = find_in_path(name);
+    ToolCheck {
+        name: name.to_string(),
+        version: path.as_ref().and_then(|_| command_output(name, &[version_flag])),
+        path: path.map(|p| p.display().to_string()),
+    }
+}
+
+// Runs each tool, so call off the main thread
+pub(crate) fn collect_diagnostics() -> Diagnostics {
+    let conn = init_db().ok();
+    let readings = conn
+        .as_ref()
+        .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM usage_history", [], |row| row.get(0)).ok());
+    Diagnostics {
+        app: collect_app_info(),
+        tools: TOOLS.iter().map(|(name, flag)| check_tool(name, flag)).collect(),
+        last_fetch: conn.as_ref().and_then(|conn| query_fetch_log(conn, 1, false).into_iter().next()),
+        database: DatabaseInfo {
+            readings,
+            size_bytes: std::fs::metadata(get_db_path()).ok().map(|m| m.len()),
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_diagnostics(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<Diagnostics, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    tauri::async_runtime::spawn_blocking(collect_diagnostics)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_app_info(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<AppInfo, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    tauri::async_runtime::spawn_blocking(collect_app_info)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_app_info_hides_paths_on_request() {
+        let path = |name: &str| format!("/Users/me/.claude/{}", name);
+        let info = AppInfo {
+            version: "0.1.0".to_string(),
+            git_commit: "abc1234".to_string(),
+            build_date: "2026-01-28 10:00".to_string(),
+            os: "macos 15.2 (aarch64)".to_string(),
+            claude_version: Some("2.1.0 (Claude Code)".to_string()),
+            paths: DataPaths {
+                data_dir: "/Users/me/.claude".to_string(),
+                cache: path("cc-usage-cache.json"),
+                settings: path("cc-usage-settings.json"),
+                database: path("cc-usage.db"),
+                error_log: path("cc-usage-last-error.txt"),
+                log: path("cc-usage.log"),
+                transcripts: path("projects"),
+            },
+        };
+        assert!(format_app_info(&info, true, None).contains(&info.paths.database));
+        assert!(!format_app_info(&info, false, None).contains(&info.paths.database));
+        assert!(!format_app_info(&info, false, None).contains(&info.paths.data_dir));
+        assert!(format_app_info(&info, false, Some("2026-01-28T10:40:12.345")).contains("fetch: 2026-01-28 10:40"));
+    }
+
+    #[test]
+    fn test_tools_not_on_path_are_reported_missing() {
+        let missing = check_tool("cc-usage-no-such-tool", "--version");
+        assert_eq!(missing.path, None);
+        assert_eq!(missing.version, None);
+    }
+
+    #[test]
+    fn test_build_metadata_is_embedded() {
+        assert!(!env!("CC_USAGE_GIT_COMMIT").is_empty());
+        assert_ne!(build_date(), "unknown");
+    }
+}
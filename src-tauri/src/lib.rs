@@ -1,21 +1,96 @@
+mod alerts;
+mod autostart;
+mod away;
+mod backup;
+mod calendar;
+mod cli;
+mod clipboard;
+mod comparison;
+mod config;
+mod cost;
+mod curl;
+mod daemon;
+mod dailynote;
+mod datadir;
+mod dock;
+mod encryption;
+mod errorlog;
+mod export;
+mod heatmap;
+mod history;
+mod icon;
+mod info;
+mod limits;
+mod locale;
+mod machines;
+mod metrics;
+mod mqtt;
+mod notify;
+mod pace;
+mod pause;
+mod period;
+mod popover;
+mod profiles;
+mod prompt;
+mod quiet;
+mod report;
+mod retention;
+mod rollups;
+mod scripting;
+mod server;
+mod settings;
+mod slack;
+mod snapshot;
+mod stats;
+mod store;
+mod streamdeck;
+mod swiftbar;
+mod sync;
+mod telemetry;
+mod theme;
+mod tmux;
+mod transcripts;
+mod trend;
+mod update;
+mod upload;
+mod velocity;
+mod waybar;
+mod webhook;
+mod windowstate;
+mod ws;
+
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::io::{BufRead, Read, Write};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
+use locale::{text, tr, Msg};
 use tauri::{
-    image::Image,
-    menu::{Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Manager, Runtime, WebviewWindowBuilder,
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    Emitter, Manager, Runtime, WebviewWindowBuilder,
 };
 
-const CACHE_FILE: &str = ".claude/cc-usage-cache.json";
-const DB_FILE: &str = ".claude/cc-usage.db";
-const SETTINGS_FILE: &str = ".claude/cc-usage-settings.json";
-const ERROR_LOG_FILE: &str = ".claude/cc-usage-last-error.txt";
+// File names within the data directory (~/.claude unless moved, see datadir.rs)
+const CACHE_FILE: &str = "cc-usage-cache.json";
+const DB_FILE: &str = "cc-usage.db";
+const SETTINGS_FILE: &str = "cc-usage-settings.json";
+const ERROR_LOG_FILE: &str = "cc-usage-last-error.txt";
+const LOG_FILE: &str = "cc-usage.log";
+
+const REFRESH_INTERVAL_SECS: u64 = 600; // 10 minutes
+
+// Default limit window lengths (see period.rs)
+const SESSION_PERIOD_HOURS: i64 = 4;
+const WEEKLY_PERIOD_HOURS: i64 = 168;
+const WATCHDOG_CHECK_SECS: u64 = 60;
+// Restart the refresh loop if nothing was attempted for this many sleep intervals
+const WATCHDOG_STALL_FACTOR: u64 = 3;
+const COUNTDOWN_TICK_SECS: u32 = 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct UsageData {
@@ -27,6 +102,9 @@ struct UsageData {
     weekly_all: UsageItem,
     #[serde(default)]
     weekly_sonnet: UsageItem,
+    // Only on plans with a separate Opus limit
+    #[serde(default)]
+    weekly_opus: UsageItem,
     #[serde(default)]
     error: Option<String>,
 }
@@ -44,24 +122,350 @@ struct AppState {
     has_network: bool,
     consecutive_errors: u32,
     show_percentages: bool,
+    guest_mode: bool,
+    away_since: Option<chrono::DateTime<chrono::Local>>,
+    away_until: Option<chrono::DateTime<chrono::Local>>,
+    last_recorded_at: Option<chrono::DateTime<chrono::Local>>,
+    trends: trend::Trends,
+    tray_metric: TrayMetric,
+    // The reading before `usage`, for the change shown next to each metric
+    previous_usage: Option<UsageData>,
+    refresh_pause: Option<pause::RefreshPause>,
+    alerts_snoozed_until: Option<chrono::DateTime<chrono::Local>>,
+    // Set while a "Refresh Now" fetch is in flight
+    manual_refresh_started: Option<chrono::DateTime<chrono::Local>>,
+    // `stale_after_mins` / `stale_title_marker` settings
+    stale_after_mins: Option<i64>,
+    stale_title_marker: bool,
+    // None shows every metric (see visible_metrics)
+    menu_metrics: Option<Vec<MenuMetric>>,
+    // Window (period_id) each metric last had a pace alert in
+    pace_alerted: std::collections::HashMap<MenuMetric, String>,
+    // Alerts raised during quiet hours, for the summary when they end
+    held_alerts: Vec<notify::Alert>,
+    // Day the Slack daily summary last went out
+    slack_summary_sent_on: Option<chrono::NaiveDate>,
+    // Day the daily note line was last written for
+    daily_note_written_for: Option<chrono::NaiveDate>,
+    // Weekly window (period_id) the early-exhaustion warning last fired in
+    early_warned: Option<String>,
+    escalation: alerts::Escalation,
+}
+
+// Which metric(s) the menu bar title shows and the icon and its status color follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum TrayMetric {
+    #[default]
+    Both,
+    Session,
+    Weekly,
+}
+
+impl TrayMetric {
+    const ALL: [TrayMetric; 3] = [TrayMetric::Both, TrayMetric::Session, TrayMetric::Weekly];
+
+    // `tray_metric` setting; unknown values show both
+    fn from_setting(value: Option<&str>) -> Self {
+        TrayMetric::ALL
+            .into_iter()
+            .find(|m| Some(m.as_str()) == value)
+            .unwrap_or_default()
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            TrayMetric::Both => "both",
+            TrayMetric::Session => "session",
+            TrayMetric::Weekly => "weekly",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TrayMetric::Both => text(Msg::SessionAndWeekly),
+            TrayMetric::Session => text(Msg::SessionOnly),
+            TrayMetric::Weekly => text(Msg::WeeklyOnly),
+        }
+    }
+
+    fn menu_id(self) -> String {
+        format!("tray_metric_{}", self.as_str())
+    }
+
+    // Percentage drawn into the gauge and battery icons
+    fn icon_percent(self, usage: &UsageData) -> Option<i32> {
+        match self {
+            TrayMetric::Weekly => usage.weekly_all.percent,
+            _ => usage.session.percent,
+        }
+    }
+}
+
+// Metric lines in the menu. The `menu_metrics` setting lists the ones to show,
+// in order; hidden metrics are also left out of the menu bar title and color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MenuMetric {
+    Session,
+    Weekly,
+    Sonnet,
+    Opus,
+}
+
+impl MenuMetric {
+    const ALL: [MenuMetric; 4] = [MenuMetric::Session, MenuMetric::Weekly, MenuMetric::Sonnet, MenuMetric::Opus];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            MenuMetric::Session => "session",
+            MenuMetric::Weekly => "weekly",
+            MenuMetric::Sonnet => "sonnet",
+            MenuMetric::Opus => "opus",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        MenuMetric::ALL.into_iter().find(|m| m.as_str() == name)
+    }
+
+    // Unknown and repeated names are skipped; no setting shows everything
+    fn from_setting(value: Option<&[String]>) -> Option<Vec<Self>> {
+        let mut metrics = Vec::new();
+        for metric in value?.iter().filter_map(|name| MenuMetric::parse(name)) {
+            if !metrics.contains(&metric) {
+                metrics.push(metric);
+            }
+        }
+        Some(metrics)
+    }
+
+    fn item(self, usage: &UsageData) -> &UsageItem {
+        match self {
+            MenuMetric::Session => &usage.session,
+            MenuMetric::Weekly => &usage.weekly_all,
+            MenuMetric::Sonnet => &usage.weekly_sonnet,
+            MenuMetric::Opus => &usage.weekly_opus,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        text(match self {
+            MenuMetric::Session => Msg::Session,
+            MenuMetric::Weekly => Msg::WeeklyAll,
+            MenuMetric::Sonnet => Msg::WeeklySonnet,
+            MenuMetric::Opus => Msg::WeeklyOpus,
+        })
+    }
+
+    fn period_hours(self) -> i64 {
+        match self {
+            MenuMetric::Session => period::session_hours(),
+            _ => period::weekly_hours(),
+        }
+    }
+}
+
+fn visible_metrics(state: &AppState) -> &[MenuMetric] {
+    state.menu_metrics.as_deref().unwrap_or(&MenuMetric::ALL)
+}
+
+const GUEST_MODE_ERROR: &str = "Not available in guest mode";
+
+// Guest mode is for shared/streamed screens: only percentages and countdowns
+// leave the backend, and anything that reveals paths or files is refused
+fn ensure_not_guest(state: &AppState) -> Result<(), String> {
+    if state.guest_mode {
+        Err(GUEST_MODE_ERROR.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn guest_view(usage: &UsageData) -> UsageData {
+    UsageData {
+        // Error text can contain file paths
+        error: usage.error.as_ref().map(|_| "Last refresh failed".to_string()),
+        ..usage.clone()
+    }
+}
+
+// Liveness info for the background refresh loop, and the watchdog's record of
+// restarts. Kept outside AppState so the watchdog never needs the state lock,
+// which a deadlocked loop may be holding. Times are monotonic, which doesn't
+// advance while the machine sleeps, so waking a laptop after hours isn't
+// mistaken for a stalled loop.
+#[derive(Debug)]
+struct RefreshHeartbeat {
+    started: std::time::Instant,
+    last_attempt: AtomicI64, // seconds since `started`
+    sleep_secs: AtomicU64,   // current sleep between attempts, including backoff
+    generation: AtomicU64,   // bumped on restart so a superseded loop exits
+    restarts: AtomicU64,
+    last_incident: Mutex<Option<String>>, // only the watchdog takes this lock
+}
+
+impl RefreshHeartbeat {
+    fn new() -> Self {
+        RefreshHeartbeat {
+            started: std::time::Instant::now(),
+            last_attempt: AtomicI64::new(0),
+            sleep_secs: AtomicU64::new(REFRESH_INTERVAL_SECS),
+            generation: AtomicU64::new(0),
+            restarts: AtomicU64::new(0),
+            last_incident: Mutex::new(None),
+        }
+    }
+
+    fn now(&self) -> i64 {
+        self.started.elapsed().as_secs() as i64
+    }
+
+    fn record_attempt(&self) {
+        self.last_attempt.store(self.now(), Ordering::SeqCst);
+    }
+}
+
+fn is_refresh_stalled(now: i64, last_attempt: i64, sleep_secs: u64) -> bool {
+    let limit = (sleep_secs * WATCHDOG_STALL_FACTOR) as i64;
+    now - last_attempt > limit
 }
 
 fn get_cache_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(CACHE_FILE)
+    datadir::data_dir().join(profiles::cache_file())
 }
 
 fn get_settings_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(SETTINGS_FILE)
+    datadir::data_dir().join(SETTINGS_FILE)
 }
 
 fn get_error_log_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(ERROR_LOG_FILE)
+    datadir::data_dir().join(ERROR_LOG_FILE)
+}
+
+fn get_log_path() -> PathBuf {
+    datadir::data_dir().join(LOG_FILE)
+}
+
+// Append a timestamped line to the app log
+fn append_log(message: &str) {
+    let path = get_log_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(path) {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        let _ = writeln!(file, "[{}] {}", timestamp, message);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Native save dialog via AppleScript; elsewhere fall back to the Downloads folder.
+// Returns None if the user cancels.
+fn choose_save_path(prompt: &str, default_name: &str) -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "POSIX path of (choose file name with prompt \"{}\" default name \"{}\")",
+            applescript_escape(prompt),
+            applescript_escape(default_name)
+        );
+        let out = Command::new("osascript").arg("-e").arg(script).output().ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        let path = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        (!path.is_empty()).then(|| PathBuf::from(path))
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = prompt;
+        let dir = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."));
+        Some(dir.join(default_name))
+    }
+}
+
+// Single-line text prompt via AppleScript. Returns None if cancelled or unsupported.
+fn prompt_text(prompt: &str, default_answer: &str) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "text returned of (display dialog \"{}\" default answer \"{}\")",
+            applescript_escape(prompt),
+            applescript_escape(default_answer)
+        );
+        let out = Command::new("osascript").arg("-e").arg(script).output().ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (prompt, default_answer);
+        None
+    }
+}
+
+// Informational dialog via AppleScript; elsewhere a notification
+fn show_message(title: &str, text: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "display dialog \"{}\" with title \"{}\" buttons {{\"OK\"}} default button 1",
+            applescript_escape(text),
+            applescript_escape(title)
+        );
+        let _ = Command::new("osascript").arg("-e").arg(script).output();
+    }
+    #[cfg(not(target_os = "macos"))]
+    notify::send_notification(title, text);
+}
+
+// Open with the default application (folders open in the file manager)
+fn open_file(path: &Path) {
+    #[cfg(target_os = "macos")]
+    let _ = Command::new("open").arg(path).spawn();
+    #[cfg(target_os = "linux")]
+    let _ = Command::new("xdg-open").arg(path).spawn();
+    #[cfg(target_os = "windows")]
+    let _ = Command::new("notepad").arg(path).spawn();
+}
+
+// Show a file selected in Finder; elsewhere, open its folder
+fn reveal_in_file_manager(path: &Path) {
+    #[cfg(target_os = "macos")]
+    let _ = Command::new("open").arg("-R").arg(path).spawn();
+    #[cfg(not(target_os = "macos"))]
+    open_file(path.parent().unwrap_or(path));
+}
+
+// OK/Cancel dialog via AppleScript. Unsupported platforms never confirm.
+fn confirm(question: &str, ok_button: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            "display dialog \"{}\" buttons {{\"{cancel}\", \"{}\"}} default button \"{cancel}\" \
+             cancel button \"{cancel}\" with icon caution",
+            applescript_escape(question),
+            applescript_escape(ok_button),
+            cancel = applescript_escape(text(Msg::Cancel))
+        );
+        Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .is_ok_and(|out| out.status.success())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (question, ok_button);
+        false
+    }
 }
 
 fn save_error_log(raw_output: &str, parse_error: &str) {
@@ -83,9 +487,94 @@ fn save_error_log(raw_output: &str, parse_error: &str) {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct Settings {
     show_percentages: Option<bool>,
+    guest_mode: Option<bool>,
+    notify_when_usable: Option<bool>,
+    away_since: Option<String>,
+    away_until: Option<String>,
+    encrypt_database: Option<bool>,
+    data_dir: Option<String>,
+    skip_unchanged_readings: Option<bool>,
+    sync_dir: Option<String>,
+    machine_name: Option<String>,
+    icon_style: Option<String>,
+    icon_appearance: Option<String>,
+    tray_metric: Option<String>,
+    refresh_interval_mins: Option<u64>,
+    tray_click: Option<String>,
+    stale_after_mins: Option<i64>,
+    stale_title_marker: Option<bool>,
+    menu_metrics: Option<Vec<String>>,
+    language: Option<String>,
+    time_format: Option<String>,
+    session_alerts: Option<bool>,
+    session_alert_thresholds: Option<Vec<i32>>,
+    weekly_alerts: Option<bool>,
+    weekly_alert_thresholds: Option<Vec<i32>>,
+    sonnet_alerts: Option<bool>,
+    sonnet_alert_thresholds: Option<Vec<i32>>,
+    opus_alerts: Option<bool>,
+    opus_alert_thresholds: Option<Vec<i32>>,
+    notify_on_reset: Option<bool>,
+    notify_on_pace: Option<bool>,
+    notify_on_limit: Option<bool>,
+    quiet_hours_start: Option<String>,
+    quiet_hours_end: Option<String>,
+    quiet_weekends: Option<bool>,
+    sound_info: Option<String>,
+    sound_warning: Option<String>,
+    sound_critical: Option<String>,
+    webhook_url: Option<String>,
+    webhook_template: Option<String>,
+    slack_webhook_url: Option<String>,
+    slack_daily_summary_at: Option<String>,
+    weekly_early_warning_hours: Option<i64>,
+    escalate_every_mins: Option<i64>,
+    session_pace_thresholds: Option<Vec<i32>>,
+    weekly_pace_thresholds: Option<Vec<i32>>,
+    sonnet_pace_thresholds: Option<Vec<i32>>,
+    opus_pace_thresholds: Option<Vec<i32>>,
+    session_period_hours: Option<i64>,
+    weekly_period_hours: Option<i64>,
+    pacing_mode: Option<String>,
+    work_hours_start: Option<String>,
+    work_hours_end: Option<String>,
+    profiles: Option<Vec<profiles::Profile>>,
+    active_profile: Option<String>,
+    privacy_mode: Option<bool>,
+    dock_icon: Option<String>,
+    theme: Option<String>,
+    // Local HTTP server (server.rs); off when unset
+    server_port: Option<u16>,
+    // Bearer token POST /refresh requires; refreshing is refused without one
+    server_token: Option<String>,
+    // Page origins allowed to open /ws, e.g. "http://localhost:3000"
+    server_allowed_origins: Option<Vec<String>>,
+    // MQTT publishing (mqtt.rs); off without a broker
+    mqtt_broker: Option<String>,
+    mqtt_username: Option<String>,
+    mqtt_password: Option<String>,
+    mqtt_topic: Option<String>,
+    mqtt_discovery_prefix: Option<String>,
+    // Metrics pushed after each fetch (telemetry.rs); off when unset
+    statsd_host: Option<String>,
+    otlp_endpoint: Option<String>,
+    // Snapshot upload after each fetch (upload.rs); off without a URL
+    upload_url: Option<String>,
+    upload_access_key_id: Option<String>,
+    upload_secret_access_key: Option<String>,
+    upload_region: Option<String>,
+    // Daily note file template (dailynote.rs); off when unset
+    daily_note_path: Option<String>,
 }
 
+// What the app runs with: the config file, the settings file, then CC_USAGE_*
+// overrides (see config.rs)
 fn load_settings() -> Settings {
+    config::effective(&load_saved_settings())
+}
+
+// The settings file alone, for changing a field and saving it back
+fn load_saved_settings() -> Settings {
     let path = get_settings_path();
     fs::read_to_string(path)
         .ok()
@@ -109,31 +598,38 @@ fn load_cached_usage() -> Option<UsageData> {
     serde_json::from_str(&content).ok()
 }
 
+// Atomically, since `cc-usage get` and the other subcommands read it
+// while the app may be writing it
 fn save_cached_usage(usage: &UsageData) {
-    let path = get_cache_path();
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
     if let Ok(json) = serde_json::to_string_pretty(usage) {
-        let _ = fs::write(path, json);
+        let _ = datadir::write_atomic(&get_cache_path(), &json);
     }
 }
 
 // Database functions
 fn get_db_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(DB_FILE)
+    datadir::data_dir().join(DB_FILE)
 }
 
+// Connection for reading or one-off changes. Queued readings are flushed
+// first so they show up in queries.
 fn init_db() -> Result<Connection, rusqlite::Error> {
+    store::flush();
+    open_db()
+}
+
+fn open_db() -> Result<Connection, rusqlite::Error> {
     let path = get_db_path();
     if let Some(parent) = path.parent() {
         let _ = fs::create_dir_all(parent);
     }
 
-    let conn = Connection::open(&path)?;
+    let conn = encryption::open(&path)?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
 
+fn init_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS usage_history (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -154,26 +650,541 @@ fn init_db() -> Result<Connection, rusqlite::Error> {
         [],
     )?;
 
-    Ok(conn)
+    // One row per fetch attempt, successful or not
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS fetch_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            strategy TEXT NOT NULL,
+            trigger TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_fetch_log_timestamp ON fetch_log(timestamp)",
+        [],
+    )?;
+
+    run_migrations(conn)
 }
 
-fn save_to_db(usage: &UsageData) {
-    if let Ok(conn) = init_db() {
-        let timestamp = usage.timestamp.as_deref().unwrap_or("");
-        let _ = conn.execute(
-            "INSERT INTO usage_history (timestamp, session_percent, session_resets, weekly_percent, weekly_resets, sonnet_percent, sonnet_resets)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                timestamp,
-                usage.session.percent,
-                usage.session.resets,
-                usage.weekly_all.percent,
-                usage.weekly_all.resets,
-                usage.weekly_sonnet.percent,
-                usage.weekly_sonnet.resets,
-            ],
+type Migration = fn(&Connection) -> Result<(), rusqlite::Error>;
+
+// Schema changes on top of the base tables, applied in order. PRAGMA
+// user_version records how many have run. Append only - never edit or
+// reorder an existing entry.
+const MIGRATIONS: &[Migration] = &[
+    migrate_add_reset_at_and_periods,
+    migrate_add_annotations,
+    migrate_add_limit_events,
+    migrate_add_deltas,
+    migrate_add_rollups,
+    migrate_add_sync,
+    migrate_add_opus,
+    migrate_add_profile,
+    migrate_add_raw_output,
+    migrate_rollups_by_profile,
+];
+
+fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch("BEGIN")?;
+        let result = migration(conn)
+            .and_then(|_| conn.execute_batch(&format!("PRAGMA user_version = {}", i + 1)));
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT")?,
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+// 1: resolved reset datetimes and limit-window ids, backfilled from the raw
+// reset strings relative to each reading's own timestamp
+fn migrate_add_reset_at_and_periods(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE usage_history ADD COLUMN session_reset_at TEXT;
+         ALTER TABLE usage_history ADD COLUMN weekly_reset_at TEXT;
+         ALTER TABLE usage_history ADD COLUMN sonnet_reset_at TEXT;
+         ALTER TABLE usage_history ADD COLUMN session_period TEXT;
+         ALTER TABLE usage_history ADD COLUMN weekly_period TEXT;
+         CREATE INDEX IF NOT EXISTS idx_session_period ON usage_history(session_period);
+         CREATE INDEX IF NOT EXISTS idx_weekly_period ON usage_history(weekly_period);",
+    )?;
+
+    type ResetStrings = (Option<String>, Option<String>, Option<String>);
+    let rows: Vec<(i64, String, ResetStrings)> = conn
+        .prepare("SELECT id, timestamp, session_resets, weekly_resets, sonnet_resets FROM usage_history")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, (row.get(2)?, row.get(3)?, row.get(4)?))))?
+        .collect::<Result<_, _>>()?;
+
+    let mut update = conn.prepare(
+        "UPDATE usage_history
+         SET session_reset_at = ?2, weekly_reset_at = ?3, sonnet_reset_at = ?4,
+             session_period = ?5, weekly_period = ?6
+         WHERE id = ?1",
+    )?;
+    for (id, timestamp, (session_resets, weekly_resets, sonnet_resets)) in rows {
+        let Some(read_at) = parse_reading_timestamp(&timestamp) else {
+            continue;
+        };
+        let session = resolve_reset(session_resets.as_deref(), read_at);
+        let weekly = resolve_reset(weekly_resets.as_deref(), read_at);
+        let sonnet = resolve_reset(sonnet_resets.as_deref(), read_at);
+        update.execute(params![
+            id,
+            session.map(format_reset_at),
+            weekly.map(format_reset_at),
+            sonnet.map(format_reset_at),
+            session.map(|r| period_id(r, period::session_hours())),
+            weekly.map(|r| period_id(r, period::weekly_hours())),
+        ])?;
+    }
+    Ok(())
+}
+
+// 2: time-range notes shown alongside history (e.g. away periods)
+fn migrate_add_annotations(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT NOT NULL,
+            note TEXT
         );
+        CREATE INDEX IF NOT EXISTS idx_annotations_end ON annotations(end);",
+    )
+}
+
+// 3: first capped reading per metric and limit window, backfilled from the
+// readings already stored
+fn migrate_add_limit_events(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS limit_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            metric TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            period TEXT NOT NULL,
+            reset_at TEXT NOT NULL,
+            minutes_into_window INTEGER NOT NULL,
+            minutes_before_reset INTEGER NOT NULL
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_limit_events_period ON limit_events(metric, period);
+        CREATE INDEX IF NOT EXISTS idx_limit_events_timestamp ON limit_events(timestamp);",
+    )?;
+    for metric in [LimitMetric::Session, LimitMetric::Weekly] {
+        let (period_col, percent_col, reset_col) = metric.columns();
+        // SQLite takes bare columns from the MIN(timestamp) row
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO limit_events
+                    (metric, timestamp, period, reset_at, minutes_into_window, minutes_before_reset)
+                 SELECT ?1, timestamp, period, reset_at, ?2 - before_reset, before_reset
+                 FROM (
+                    SELECT MIN(timestamp) AS timestamp, {period} AS period, {reset} AS reset_at,
+                           CAST(ROUND((julianday({reset}) - julianday(timestamp)) * 1440) AS INTEGER) AS before_reset
+                    FROM usage_history
+                    WHERE {percent} >= 100 AND {period} IS NOT NULL AND {reset} IS NOT NULL
+                    GROUP BY {period}
+                 )",
+                period = period_col,
+                percent = percent_col,
+                reset = reset_col,
+            ),
+            params![metric.as_str(), metric.period_hours() * 60],
+        )?;
     }
+    Ok(())
+}
+
+// 4: change since the previous reading and %/hour per metric
+fn migrate_add_deltas(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE usage_history ADD COLUMN session_delta INTEGER;
+         ALTER TABLE usage_history ADD COLUMN weekly_delta INTEGER;
+         ALTER TABLE usage_history ADD COLUMN sonnet_delta INTEGER;
+         ALTER TABLE usage_history ADD COLUMN session_velocity REAL;
+         ALTER TABLE usage_history ADD COLUMN weekly_velocity REAL;
+         ALTER TABLE usage_history ADD COLUMN sonnet_velocity REAL;",
+    )?;
+    velocity::backfill(conn)
+}
+
+// 5: daily/weekly min/max/avg for long-range charts. Filled by the rebuild in
+// migration 10, since the rollups now include columns added up to there.
+fn migrate_add_rollups(conn: &Connection) -> Result<(), rusqlite::Error> {
+    rollups::create_tables(conn)
+}
+
+// 6: which machine a synced reading came from (NULL = this one), and how far
+// each machine's sync file has been read
+fn migrate_add_sync(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE usage_history ADD COLUMN machine TEXT;
+         CREATE TABLE IF NOT EXISTS sync_offsets (
+            machine TEXT PRIMARY KEY,
+            bytes_read INTEGER NOT NULL
+         );",
+    )
+}
+
+// 7: the Opus weekly limit, in history and the rollups. The rollups used to be
+// rebuilt here; migration 10 recreates and fills them instead.
+fn migrate_add_opus(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE usage_history ADD COLUMN opus_percent INTEGER;
+         ALTER TABLE usage_history ADD COLUMN opus_resets TEXT;
+         ALTER TABLE usage_history ADD COLUMN opus_reset_at TEXT;",
+    )?;
+    rollups::add_metric_columns(conn, "opus")
+}
+
+// 8: which account profile a reading is for (NULL = the default, see profiles.rs)
+fn migrate_add_profile(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "ALTER TABLE usage_history ADD COLUMN profile TEXT;
+         CREATE INDEX IF NOT EXISTS idx_profile ON usage_history(profile);",
+    )
+}
+
+// 9: where a failed fetch's raw output was kept, for the Recent Errors window
+fn migrate_add_raw_output(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("ALTER TABLE fetch_log ADD COLUMN raw_output TEXT;")
+}
+
+// 10: one rollup row per period and profile, so charts don't mix accounts
+fn migrate_rollups_by_profile(conn: &Connection) -> Result<(), rusqlite::Error> {
+    rollups::key_by_profile(conn)?;
+    rollups::rebuild(conn)
+}
+
+// Reading timestamps are local time from Python's isoformat(), possibly with
+// fractional seconds
+fn parse_reading_timestamp(ts: &str) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    let ts_clean = ts.split('.').next().unwrap_or(ts);
+    let naive = chrono::NaiveDateTime::parse_from_str(ts_clean, "%Y-%m-%dT%H:%M:%S").ok()?;
+    chrono::Local.from_local_datetime(&naive).earliest()
+}
+
+fn resolve_reset(
+    resets: Option<&str>,
+    read_at: chrono::DateTime<chrono::Local>,
+) -> Option<chrono::DateTime<chrono::Local>> {
+    parse_reset_time_at(resets?, read_at)
+}
+
+fn format_reset_at(reset_at: chrono::DateTime<chrono::Local>) -> String {
+    reset_at.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+// Identify a limit window by its start: the reset time (rounded to the hour,
+// since displayed resets can drift by a minute between readings) minus the
+// window length
+fn period_id(reset_at: chrono::DateTime<chrono::Local>, period_hours: i64) -> String {
+    use chrono::Timelike;
+    let rounded = reset_at
+        .with_minute(0)
+        .and_then(|t| t.with_second(0))
+        .map(|t| if reset_at.minute() >= 30 { t + chrono::Duration::hours(1) } else { t })
+        .unwrap_or(reset_at);
+    (rounded - chrono::Duration::hours(period_hours))
+        .format("%Y-%m-%dT%H:%M")
+        .to_string()
+}
+
+fn save_to_db(usage: &UsageData) {
+    store::queue_reading(usage);
+}
+
+// Returns the new row's id
+fn insert_usage(conn: &Connection, usage: &UsageData) -> Result<i64, rusqlite::Error> {
+    insert_usage_for(conn, usage, profiles::active_name().as_deref())
+}
+
+// A reading for the given profile rather than the active one, for imports
+fn insert_usage_for(conn: &Connection, usage: &UsageData, profile: Option<&str>) -> Result<i64, rusqlite::Error> {
+    let timestamp = usage.timestamp.as_deref().unwrap_or("");
+    let read_at = parse_reading_timestamp(timestamp).unwrap_or_else(chrono::Local::now);
+    let session_reset = resolve_reset(usage.session.resets.as_deref(), read_at);
+    let weekly_reset = resolve_reset(usage.weekly_all.resets.as_deref(), read_at);
+    let sonnet_reset = resolve_reset(usage.weekly_sonnet.resets.as_deref(), read_at);
+    let opus_reset = resolve_reset(usage.weekly_opus.resets.as_deref(), read_at);
+    conn.execute(
+        "INSERT INTO usage_history (timestamp, session_percent, session_resets, weekly_percent, weekly_resets, sonnet_percent, sonnet_resets,
+                                    session_reset_at, weekly_reset_at, sonnet_reset_at, session_period, weekly_period,
+                                    opus_percent, opus_resets, opus_reset_at, profile)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            timestamp,
+            usage.session.percent,
+            usage.session.resets,
+            usage.weekly_all.percent,
+            usage.weekly_all.resets,
+            usage.weekly_sonnet.percent,
+            usage.weekly_sonnet.resets,
+            session_reset.map(format_reset_at),
+            weekly_reset.map(format_reset_at),
+            sonnet_reset.map(format_reset_at),
+            session_reset.map(|r| period_id(r, period::session_hours())),
+            weekly_reset.map(|r| period_id(r, period::weekly_hours())),
+            usage.weekly_opus.percent,
+            usage.weekly_opus.resets,
+            opus_reset.map(format_reset_at),
+            profile,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    velocity::update_around(conn, id)?;
+    rollups::refresh_for(conn, timestamp, profile)?;
+    if usage.session.percent.is_some_and(|p| p >= 100) {
+        limits::record_limit_hit(conn, LimitMetric::Session, read_at, session_reset)?;
+    }
+    if usage.weekly_all.percent.is_some_and(|p| p >= 100) {
+        limits::record_limit_hit(conn, LimitMetric::Weekly, read_at, weekly_reset)?;
+    }
+    Ok(id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LimitMetric {
+    Session,
+    Weekly,
+}
+
+impl LimitMetric {
+    fn as_str(self) -> &'static str {
+        match self {
+            LimitMetric::Session => "session",
+            LimitMetric::Weekly => "weekly",
+        }
+    }
+
+    fn period_hours(self) -> i64 {
+        match self {
+            LimitMetric::Session => period::session_hours(),
+            LimitMetric::Weekly => period::weekly_hours(),
+        }
+    }
+
+    fn columns(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            LimitMetric::Session => ("session_period", "session_percent", "session_reset_at"),
+            LimitMetric::Weekly => ("weekly_period", "weekly_percent", "weekly_reset_at"),
+        }
+    }
+}
+
+// Readings grouped by limit window, newest window first
+#[derive(Debug, Clone, Serialize)]
+struct LimitWindow {
+    period: String,
+    reset_at: Option<String>,
+    max_percent: Option<i32>,
+    readings: i64,
+    first_reading: String,
+    last_reading: String,
+}
+
+fn query_limit_windows(conn: &Connection, metric: LimitMetric, limit: u32) -> Vec<LimitWindow> {
+    let (period_col, percent_col, reset_col) = metric.columns();
+    let sql = format!(
+        "SELECT {period}, MAX({reset}), MAX({percent}), COUNT(*), MIN(timestamp), MAX(timestamp)
+         FROM usage_history
+         WHERE {period} IS NOT NULL AND profile IS ?2
+         GROUP BY {period}
+         ORDER BY {period} DESC
+         LIMIT ?1",
+        period = period_col,
+        percent = percent_col,
+        reset = reset_col,
+    );
+    conn.prepare(&sql)
+        .and_then(|mut stmt| {
+            stmt.query_map(params![limit, profiles::active_name()], |row| {
+                Ok(LimitWindow {
+                    period: row.get(0)?,
+                    reset_at: row.get(1)?,
+                    max_percent: row.get(2)?,
+                    readings: row.get(3)?,
+                    first_reading: row.get(4)?,
+                    last_reading: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .unwrap_or_default()
+}
+
+// Only one way to get usage today, but recorded so the log stays meaningful
+// if another is added
+const FETCH_STRATEGY: &str = "tmux";
+const FETCH_LOG_RETENTION_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Serialize)]
+struct FetchLogEntry {
+    timestamp: String,
+    duration_ms: i64,
+    strategy: String,
+    trigger: String,
+    success: bool,
+    error: Option<String>,
+    // The failed fetch's saved output (see errorlog.rs)
+    raw_output: Option<String>,
+}
+
+fn insert_fetch_log(conn: &Connection, entry: &FetchLogEntry) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO fetch_log (timestamp, duration_ms, strategy, trigger, success, error, raw_output)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            entry.timestamp,
+            entry.duration_ms,
+            entry.strategy,
+            entry.trigger,
+            entry.success,
+            entry.error,
+            entry.raw_output,
+        ],
+    )?;
+    let cutoff = chrono::Local::now() - chrono::Duration::days(FETCH_LOG_RETENTION_DAYS);
+    conn.execute(
+        "DELETE FROM fetch_log WHERE timestamp < ?1",
+        params![cutoff.format("%Y-%m-%dT%H:%M:%S").to_string()],
+    )?;
+    Ok(())
+}
+
+// Newest first, optionally only the failures
+fn query_fetch_log(conn: &Connection, limit: u32, failures_only: bool) -> Vec<FetchLogEntry> {
+    conn.prepare(
+        "SELECT timestamp, duration_ms, strategy, trigger, success, error, raw_output
+         FROM fetch_log
+         WHERE success = 0 OR NOT ?2
+         ORDER BY timestamp DESC, id DESC
+         LIMIT ?1",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![limit, failures_only], |row| {
+            Ok(FetchLogEntry {
+                timestamp: row.get(0)?,
+                duration_ms: row.get(1)?,
+                strategy: row.get(2)?,
+                trigger: row.get(3)?,
+                success: row.get(4)?,
+                error: row.get(5)?,
+                raw_output: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .unwrap_or_default()
+}
+
+// Pushed while a fetch runs, since it takes several seconds: "starting",
+// "launched" (claude is up), "captured" (the /usage screen was read),
+// "parsed", then "done" or "error" with how long the fetch took
+const FETCH_PROGRESS_EVENT: &str = "usage://progress";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum FetchStage {
+    Starting,
+    Launched,
+    Captured,
+    Parsed,
+    Done,
+    Error,
+}
+
+impl FetchStage {
+    // The script reports the stages only it can see as "progress:<stage>" on stderr
+    fn from_marker(line: &str) -> Option<Self> {
+        match line.trim().strip_prefix("progress:")? {
+            "launched" => Some(FetchStage::Launched),
+            "captured" => Some(FetchStage::Captured),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FetchProgress {
+    stage: FetchStage,
+    trigger: String,
+    elapsed_ms: i64,
+    error: Option<String>,
+}
+
+// Run the fetcher and record the attempt in fetch_log.
+// `trigger` says what started it: "scheduled", "menu", or "command", or
+// "script" and "streamdeck" from outside the app.
+fn fetch_usage_logged<R: Runtime>(app: &tauri::AppHandle<R>, trigger: &str) -> UsageData {
+    fetch_and_log(trigger, &|event| {
+        let _ = app.emit(FETCH_PROGRESS_EVENT, event);
+    })
+}
+
+// fetch_usage_logged without the app: progress goes to `on_progress`
+fn fetch_and_log(trigger: &str, on_progress: &dyn Fn(FetchProgress)) -> UsageData {
+    let started = chrono::Local::now();
+    let timer = std::time::Instant::now();
+    let progress = |stage: FetchStage, error: Option<String>| {
+        on_progress(FetchProgress {
+            stage,
+            trigger: trigger.to_string(),
+            elapsed_ms: timer.elapsed().as_millis() as i64,
+            error,
+        });
+    };
+    progress(FetchStage::Starting, None);
+    let (data, raw_output) = fetch_usage(&mut |stage| progress(stage, None));
+    match &data.error {
+        Some(error) => progress(FetchStage::Error, Some(error.clone())),
+        None => progress(FetchStage::Done, None),
+    }
+    let entry = FetchLogEntry {
+        timestamp: started.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        duration_ms: timer.elapsed().as_millis() as i64,
+        strategy: FETCH_STRATEGY.to_string(),
+        trigger: trigger.to_string(),
+        success: data.error.is_none(),
+        error: data.error.clone(),
+        raw_output: None,
+    };
+    metrics::record_fetch(entry.duration_ms, entry.success);
+    let settings = load_settings();
+    telemetry::push(&settings, &data, entry.duration_ms, entry.success);
+    log_fetch(&settings, entry, raw_output);
+    data
+}
+
+// Into the fetch log, with a failed fetch's raw output kept alongside; nothing
+// in privacy mode
+fn log_fetch(settings: &Settings, entry: FetchLogEntry, raw_output: Option<String>) {
+    if privacy_mode(settings) {
+        return;
+    }
+    let entry = FetchLogEntry {
+        raw_output: raw_output.and_then(|raw| errorlog::keep_raw_output(&entry.timestamp, &raw)),
+        ..entry
+    };
+    let _ = store::with_connection(|conn| insert_fetch_log(conn, &entry));
+}
+
+// `privacy_mode`: the tray still updates from each fetch, but nothing is
+// written to the history, the cache, the fetch log, or the sync folder
+fn privacy_mode(settings: &Settings) -> bool {
+    settings.privacy_mode.unwrap_or(false)
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -182,53 +1193,287 @@ struct UsageHistoryRow {
     session_percent: Option<i32>,
     weekly_percent: Option<i32>,
     sonnet_percent: Option<i32>,
+    opus_percent: Option<i32>,
 }
 
-fn get_usage_history(days: i32) -> Vec<UsageHistoryRow> {
-    let mut results = Vec::new();
+// How readings inside one downsampling bucket are combined into a single point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum Aggregation {
+    Min,
+    #[default]
+    Avg,
+    Max,
+    Latest,
+}
 
-    if let Ok(conn) = init_db() {
-        let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
-        let cutoff_str = cutoff.format("%Y-%m-%dT%H:%M:%S").to_string();
+impl Aggregation {
+    // SQL expression for one metric column within a GROUP BY bucket
+    fn sql_expr(self, column: &str) -> String {
+        match self {
+            Aggregation::Min => format!("MIN({})", column),
+            Aggregation::Avg => format!("CAST(ROUND(AVG({})) AS INTEGER)", column),
+            Aggregation::Max => format!("MAX({})", column),
+            // Correlated lookup of the newest reading in the same bucket
+            Aggregation::Latest => format!(
+                "(SELECT {col} FROM bucketed latest
+                  WHERE latest.bucket = bucketed.bucket
+                  ORDER BY latest.timestamp DESC LIMIT 1)",
+                col = column
+            ),
+        }
+    }
+}
 
-        let mut stmt = conn.prepare(
-            "SELECT timestamp, session_percent, weekly_percent, sonnet_percent
-             FROM usage_history
-             WHERE timestamp >= ?1
-             ORDER BY timestamp ASC"
-        ).ok();
-
-        if let Some(ref mut stmt) = stmt {
-            let rows = stmt.query_map(params![cutoff_str], |row| {
-                Ok(UsageHistoryRow {
-                    timestamp: row.get(0)?,
-                    session_percent: row.get(1)?,
-                    weekly_percent: row.get(2)?,
-                    sonnet_percent: row.get(3)?,
-                })
-            });
+// Query history since `cutoff`, optionally downsampled into buckets of `bucket_secs`.
+// Each bucket is labeled with the timestamp of its first reading. Timestamps are
+// local time, so treating them as UTC in strftime('%s') keeps daily buckets
+// aligned to local midnight. Only the active profile's readings are included.
+fn query_usage_history(
+    conn: &Connection,
+    cutoff: &str,
+    bucket_secs: Option<i64>,
+    aggregation: Aggregation,
+) -> Vec<UsageHistoryRow> {
+    let map_row = |row: &rusqlite::Row| {
+        Ok(UsageHistoryRow {
+            timestamp: row.get(0)?,
+            session_percent: row.get(1)?,
+            weekly_percent: row.get(2)?,
+            sonnet_percent: row.get(3)?,
+            opus_percent: row.get(4)?,
+        })
+    };
+    let profile = profiles::active_name();
+
+    let rows = match bucket_secs.filter(|secs| *secs > 0) {
+        None => conn
+            .prepare(
+                "SELECT timestamp, session_percent, weekly_percent, sonnet_percent, opus_percent
+                 FROM usage_history
+                 WHERE timestamp >= ?1 AND profile IS ?2
+                 ORDER BY timestamp ASC",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![cutoff, profile], map_row)?
+                    .collect::<Result<Vec<_>, _>>()
+            }),
+        Some(secs) => {
+            let sql = format!(
+                "WITH bucketed AS (
+                    SELECT *, CAST(strftime('%s', timestamp) AS INTEGER) / ?2 AS bucket
+                    FROM usage_history
+                    WHERE timestamp >= ?1 AND profile IS ?3
+                 )
+                 SELECT MIN(timestamp), {}, {}, {}, {}
+                 FROM bucketed
+                 GROUP BY bucket
+                 ORDER BY bucket ASC",
+                aggregation.sql_expr("session_percent"),
+                aggregation.sql_expr("weekly_percent"),
+                aggregation.sql_expr("sonnet_percent"),
+                aggregation.sql_expr("opus_percent"),
+            );
+            conn.prepare(&sql).and_then(|mut stmt| {
+                stmt.query_map(params![cutoff, secs, profile], map_row)?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+        }
+    };
 
-            if let Ok(rows) = rows {
-                for row in rows.flatten() {
-                    results.push(row);
+    rows.unwrap_or_default()
+}
+
+// Point resolution for get_history. Buckets are computed in SQL, so a 90-day
+// range returns ~90 daily points instead of ~13k raw readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum Granularity {
+    Raw,
+    Hourly,
+    Daily,
+    Weekly,
+    // Finest resolution that stays around MAX_HISTORY_POINTS for the range
+    #[default]
+    Auto,
+}
+
+// Readings are every 10 minutes: 2 days raw = 288, 60 days hourly = 1440
+const MAX_HISTORY_POINTS: i64 = 1500;
+const READINGS_PER_HOUR: i64 = 6;
+
+impl Granularity {
+    fn resolve(self, days: i32) -> Granularity {
+        match self {
+            Granularity::Auto => {
+                let hours = days.max(1) as i64 * 24;
+                if hours * READINGS_PER_HOUR <= MAX_HISTORY_POINTS / 5 {
+                    Granularity::Raw
+                } else if hours <= MAX_HISTORY_POINTS {
+                    Granularity::Hourly
+                } else if days as i64 <= MAX_HISTORY_POINTS {
+                    Granularity::Daily
+                } else {
+                    Granularity::Weekly
                 }
             }
+            other => other,
         }
     }
 
-    results
+    fn bucket_secs(self, days: i32) -> Option<i64> {
+        match self.resolve(days) {
+            Granularity::Hourly => Some(3600),
+            Granularity::Daily => Some(86400),
+            Granularity::Weekly => Some(7 * 86400),
+            _ => None,
+        }
+    }
+}
+
+// Narrowing for get_history beyond the day count. `from` / `to` are reading
+// timestamps, dates ("2026-01-28", whole days), or RFC 3339 from the frontend.
+// `metrics` nulls out the other columns and drops readings with none of the
+// chosen ones; `limit` / `offset` then page through what's left.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct HistoryRange {
+    from: Option<String>,
+    to: Option<String>,
+    metrics: Option<Vec<String>>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+// A range bound as a reading timestamp; a date alone is its start or, for
+// `to`, its last second
+fn parse_history_bound(value: &str, end_of_day: bool) -> Result<String, String> {
+    let value = value.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end_of_day { "23:59:59" } else { "00:00:00" };
+        return Ok(format!("{}T{}", date.format("%Y-%m-%d"), time));
+    }
+    let at = chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Local))
+        .ok()
+        .or_else(|| parse_reading_timestamp(value))
+        .or_else(|| parse_reading_timestamp(&format!("{}:00", value)))
+        .ok_or_else(|| format!("Not a date or time: {}", value))?;
+    Ok(at.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+impl HistoryRange {
+    fn metric_filter(&self) -> Result<Option<Vec<MenuMetric>>, String> {
+        let Some(names) = &self.metrics else {
+            return Ok(None);
+        };
+        names
+            .iter()
+            .map(|name| MenuMetric::parse(name).ok_or_else(|| format!("Unknown metric: {}", name)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+
+    fn apply(&self, rows: Vec<UsageHistoryRow>, to: Option<&str>, metrics: Option<&[MenuMetric]>) -> Vec<UsageHistoryRow> {
+        let wanted = |metric: MenuMetric| metrics.map_or(true, |m| m.contains(&metric));
+        rows.into_iter()
+            .filter(|row| to.map_or(true, |to| row.timestamp.get(..19).unwrap_or(&row.timestamp) <= to))
+            .map(|row| UsageHistoryRow {
+                session_percent: row.session_percent.filter(|_| wanted(MenuMetric::Session)),
+                weekly_percent: row.weekly_percent.filter(|_| wanted(MenuMetric::Weekly)),
+                sonnet_percent: row.sonnet_percent.filter(|_| wanted(MenuMetric::Sonnet)),
+                opus_percent: row.opus_percent.filter(|_| wanted(MenuMetric::Opus)),
+                ..row
+            })
+            .filter(|row| {
+                metrics.is_none()
+                    || [row.session_percent, row.weekly_percent, row.sonnet_percent, row.opus_percent]
+                        .iter()
+                        .any(Option::is_some)
+            })
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+fn get_usage_history(days: i32, granularity: Granularity, aggregation: Aggregation) -> Vec<UsageHistoryRow> {
+    let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
+    get_usage_history_since(&cutoff.format("%Y-%m-%dT%H:%M:%S").to_string(), days, granularity, aggregation)
+}
+
+// `days` is the span, for the automatic granularity
+fn get_usage_history_since(
+    cutoff_str: &str,
+    days: i32,
+    granularity: Granularity,
+    aggregation: Aggregation,
+) -> Vec<UsageHistoryRow> {
+    let Ok(conn) = init_db() else {
+        return Vec::new();
+    };
+    // Daily and weekly points come from the rollup tables when they have the
+    // aggregation
+    let rollup = match granularity.resolve(days) {
+        Granularity::Daily => Some(rollups::Rollup::Daily),
+        Granularity::Weekly => Some(rollups::Rollup::Weekly),
+        _ => None,
+    };
+    rollup
+        .and_then(|rollup| rollups::query(&conn, rollup, cutoff_str, aggregation))
+        .unwrap_or_else(|| query_usage_history(&conn, cutoff_str, granularity.bucket_secs(days), aggregation))
 }
 
 // Tauri commands for frontend
 #[tauri::command]
 fn get_current_usage(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> UsageData {
     let state = state.lock().unwrap();
-    state.usage.clone()
+    if state.guest_mode {
+        guest_view(&state.usage)
+    } else {
+        state.usage.clone()
+    }
 }
 
+// The last `days` days, or `range.from` to `range.to` when given
 #[tauri::command]
-fn get_history(days: i32) -> Vec<UsageHistoryRow> {
-    get_usage_history(days)
+fn get_history(
+    days: Option<i32>,
+    granularity: Option<Granularity>,
+    aggregation: Option<Aggregation>,
+    range: Option<HistoryRange>,
+) -> Result<Vec<UsageHistoryRow>, String> {
+    let range = range.unwrap_or_default();
+    let granularity = granularity.unwrap_or_default();
+    let aggregation = aggregation.unwrap_or_default();
+    let metrics = range.metric_filter()?;
+    let to = range.to.as_deref().map(|to| parse_history_bound(to, true)).transpose()?;
+    let rows = match range.from.as_deref() {
+        Some(from) => {
+            let from = parse_history_bound(from, false)?;
+            let end = to.as_deref().and_then(parse_reading_timestamp).unwrap_or_else(chrono::Local::now);
+            let span = parse_reading_timestamp(&from).map(|start| end.signed_duration_since(start));
+            let span_days = span.map(|s| (s.num_hours() + 23) / 24).unwrap_or(1).max(1) as i32;
+            get_usage_history_since(&from, span_days, granularity, aggregation)
+        }
+        None => get_usage_history(days.unwrap_or(7), granularity, aggregation),
+    };
+    Ok(range.apply(rows, to.as_deref(), metrics.as_deref()))
+}
+
+#[tauri::command]
+fn get_fetch_log(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    limit: Option<u32>,
+) -> Result<Vec<FetchLogEntry>, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    Ok(query_fetch_log(&conn, limit.unwrap_or(100), false))
+}
+
+#[tauri::command]
+fn get_limit_windows(metric: LimitMetric, limit: Option<u32>) -> Result<Vec<LimitWindow>, String> {
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    Ok(query_limit_windows(&conn, metric, limit.unwrap_or(12)))
 }
 
 #[tauri::command]
@@ -237,34 +1482,295 @@ async fn refresh_usage(
     app: tauri::AppHandle,
 ) -> Result<UsageData, String> {
     // Run fetch in background thread to avoid blocking UI
-    let data = tauri::async_runtime::spawn_blocking(fetch_usage)
+    let handle = app.clone();
+    let data = tauri::async_runtime::spawn_blocking(move || fetch_usage_logged(&handle, "command"))
         .await
         .map_err(|e| format!("Task failed: {}", e))?;
 
-    let mut app_state = state.lock().unwrap();
+    let mut app_state = state.lock().unwrap();
+    let effects = apply_fetch_result(&mut app_state, data.clone());
+    let state_clone = app_state.clone();
+    drop(app_state);
+    emit_fetch_result(&app, effects, &state_clone);
+
+    // Update tray
+    update_tray(&app, &state_clone);
+
+    if state_clone.guest_mode {
+        Ok(guest_view(&data))
+    } else {
+        Ok(data)
+    }
+}
+
+// Pushed to open windows after every fetch so they don't have to poll:
+// the reading (as get_current_usage returns it) on success, FetchError otherwise
+const USAGE_UPDATED_EVENT: &str = "usage://updated";
+const USAGE_ERROR_EVENT: &str = "usage://error";
+
+#[derive(Debug, Clone, Serialize)]
+struct FetchError {
+    error: String,
+    consecutive_errors: u32,
+    // Timestamp of the reading still shown
+    last_success: Option<String>,
+}
+
+// Tell open windows, WebSocket clients, and the upload endpoint how the
+// fetch went, after publishing its effects. `state` is a snapshot taken after
+// apply_fetch_result, with the lock already released.
+fn emit_fetch_result<R: Runtime>(app: &tauri::AppHandle<R>, effects: FetchEffects, state: &AppState) {
+    publish_fetch_result(effects, state);
+    match &state.last_error {
+        Some(error) => {
+            let event = FetchError {
+                error: error.clone(),
+                consecutive_errors: state.consecutive_errors,
+                last_success: state.usage.timestamp.clone(),
+            };
+            let _ = app.emit(USAGE_ERROR_EVENT, event);
+        }
+        None if state.guest_mode => {
+            let _ = app.emit(USAGE_UPDATED_EVENT, guest_view(&state.usage));
+        }
+        None => {
+            let _ = app.emit(USAGE_UPDATED_EVENT, &state.usage);
+        }
+    }
+}
+
+// What a fetch result sets off, decided by apply_fetch_result under the state
+// lock and carried out by publish_fetch_result once it's released
+#[derive(Debug, Default)]
+struct FetchEffects {
+    settings: Settings,
+    // Goes into history and the sync folder
+    record: bool,
+    // The error streak just reached webhook::ERROR_STREAK
+    error_streak: bool,
+    crossings: Vec<alerts::Crossing>,
+    // Not held for quiet hours
+    alerts: Vec<notify::Alert>,
+}
+
+// Every file write, network call, and notification a fetch leads to. Runs
+// without the state lock: webhooks, MQTT, and the upload go out in the
+// background and log their own failures, but the cache, history, sync, and
+// calendar writes happen here. The part of emit_fetch_result that doesn't
+// need the app (daemon.rs).
+fn publish_fetch_result(effects: FetchEffects, state: &AppState) {
+    let settings = &effects.settings;
+    match &state.last_error {
+        Some(error) if effects.error_streak => {
+            let event = webhook::WebhookEvent::FetchErrors {
+                count: state.consecutive_errors,
+                error,
+            };
+            webhook::send(settings, event, &state.usage);
+        }
+        Some(_) => {}
+        None => {
+            let data = &state.usage;
+            let private = privacy_mode(settings);
+            if !private {
+                save_cached_usage(data);
+            }
+            if effects.record {
+                save_to_db(data);
+                sync::publish(data);
+            }
+            if !private {
+                sync::merge_remote();
+            }
+            mqtt::publish(settings, data);
+            calendar::write(settings, data);
+            // Automations and the team channel get every crossing, snoozed or not
+            for crossing in &effects.crossings {
+                webhook::send(settings, webhook::WebhookEvent::Threshold(crossing), data);
+                slack::send_crossing(settings, crossing, data);
+            }
+        }
+    }
+    for alert in &effects.alerts {
+        notify::send_alert(alert, settings);
+    }
+    ws::broadcast(state);
+    upload::upload(settings, state);
+}
+
+// Apply a fetch result to state, returning what should happen because of it.
+// Shared by the refresh loop, the menu's Refresh Now, the refresh_usage
+// command, and the daemon; touches nothing outside `state`.
+fn apply_fetch_result(state: &mut AppState, data: UsageData) -> FetchEffects {
+    let settings = load_settings();
+    if let Some(ref err) = data.error {
+        state.last_error = Some(err.clone());
+        state.consecutive_errors += 1;
+        state.has_network = !err.contains("No network");
+        return FetchEffects {
+            error_streak: state.consecutive_errors == webhook::ERROR_STREAK,
+            settings,
+            ..Default::default()
+        };
+    }
+
+    let now = chrono::Local::now();
+    let private = privacy_mode(&settings);
+    let skip_unchanged = settings.skip_unchanged_readings.unwrap_or(false);
+    let record = !private && (!skip_unchanged || should_record(&state.usage, &data, state.last_recorded_at, now));
+    if record {
+        state.last_recorded_at = Some(now);
+    }
+    state.trends.push(now, &data);
+
+    let crossings = alerts::crossings(&state.usage, &data, &settings);
+    let mut to_send = Vec::new();
+    let escalation = alerts::escalate(&state.usage, &data, &settings, &mut state.escalation, now);
+    if notify::alerts_allowed(state, now) {
+        let mut raised = alerts::changes(&state.usage, &data, &settings, &mut state.pace_alerted);
+        let rate = state.trends.weekly_rate();
+        raised.extend(alerts::early_warning(&data, rate, &settings, &mut state.early_warned));
+        raised.extend(escalation);
+        for alert in raised {
+            to_send.extend(quiet::hold_if_quiet(state, &settings, now, alert));
+        }
+    }
+
+    state.previous_usage = Some(std::mem::replace(&mut state.usage, data));
+    state.last_error = None;
+    state.consecutive_errors = 0;
+    state.has_network = true;
+    FetchEffects {
+        settings,
+        record,
+        error_streak: false,
+        crossings,
+        alerts: to_send,
+    }
+}
+
+// At startup: the history row before the cached reading
+fn load_previous_reading(usage: &UsageData) -> Option<UsageData> {
+    let timestamp = usage.timestamp.as_deref()?;
+    let conn = init_db().ok()?;
+    conn.query_row(
+        "SELECT timestamp, session_percent, weekly_percent, sonnet_percent, opus_percent FROM usage_history
+         WHERE substr(timestamp, 1, 19) < substr(?1, 1, 19) AND machine IS NULL AND profile IS ?2
+         ORDER BY timestamp DESC LIMIT 1",
+        params![timestamp, profiles::active_name()],
+        |row| {
+            let item = |percent: Option<i32>| UsageItem { percent, resets: None };
+            Ok(UsageData {
+                timestamp: row.get(0)?,
+                session: item(row.get(1)?),
+                weekly_all: item(row.get(2)?),
+                weekly_sonnet: item(row.get(3)?),
+                weekly_opus: item(row.get(4)?),
+                error: None,
+            })
+        },
+    )
+    .ok()
+}
+
+// " (↑5)" / " (↓3)" after a percentage; nothing when unchanged or unknown
+fn delta_suffix(previous: Option<i32>, current: Option<i32>) -> String {
+    match (previous, current) {
+        (Some(prev), Some(cur)) if cur > prev => format!(" (↑{})", cur - prev),
+        (Some(prev), Some(cur)) if cur < prev => format!(" (↓{})", prev - cur),
+        _ => String::new(),
+    }
+}
+
+// With skip_unchanged_readings, idle periods are recorded at most hourly
+const UNCHANGED_RECORD_INTERVAL_MINS: i64 = 60;
+
+// Record when any percentage changed, or the last recorded reading is an hour old
+fn should_record(
+    previous: &UsageData,
+    current: &UsageData,
+    last_recorded_at: Option<chrono::DateTime<chrono::Local>>,
+    now: chrono::DateTime<chrono::Local>,
+) -> bool {
+    let percents = |u: &UsageData| {
+        (u.session.percent, u.weekly_all.percent, u.weekly_sonnet.percent, u.weekly_opus.percent)
+    };
+    match last_recorded_at {
+        Some(at) => {
+            percents(previous) != percents(current)
+                || now.signed_duration_since(at).num_minutes() >= UNCHANGED_RECORD_INTERVAL_MINS
+        }
+        None => true,
+    }
+}
+
+// Usage only drops within a window when it resets, so any reading below 100%
+// after a capped one confirms the reset actually happened
+fn became_usable(previous: Option<i32>, current: Option<i32>) -> bool {
+    matches!((previous, current), (Some(prev), Some(cur)) if prev >= 100 && cur < 100)
+}
+
+// Reset string of the limit that's blocking usage, if any. When both session
+// and weekly are capped, the later reset is the one that matters.
+fn blocking_reset(usage: &UsageData) -> Option<&str> {
+    [&usage.session, &usage.weekly_all]
+        .into_iter()
+        .filter(|item| item.percent.is_some_and(|p| p >= 100))
+        .filter_map(|item| {
+            let resets = item.resets.as_deref()?;
+            Some((parse_reset_time(resets)?, resets))
+        })
+        .max_by_key(|(time, _)| *time)
+        .map(|(_, resets)| resets)
+}
+
+// Seconds to wait so the next fetch lands a minute after the reset. Once that
+// moment has passed, the post-reset fetch has happened, so a reading still at
+// 100% (a dated reset already behind us) waits the usual interval instead of
+// fetching every minute.
+fn post_reset_wait_secs(resets: &str, now: chrono::DateTime<chrono::Local>) -> Option<u64> {
+    let reset_time = parse_reset_time_at(resets, now)?;
+    let wait = reset_time.signed_duration_since(now).num_seconds() + 60;
+    (wait > 0).then_some(wait as u64)
+}
 
-    if data.error.is_none() {
-        save_cached_usage(&data);
-        save_to_db(&data);
-        app_state.usage = data.clone();
-        app_state.last_error = None;
+// "1h 47m" / "47m" - finer than format_duration, for short waits
+fn format_hours_minutes(duration: chrono::Duration) -> String {
+    let hours = duration.num_hours();
+    let mins = duration.num_minutes() % 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, mins)
     } else {
-        app_state.last_error = data.error.clone();
+        format!("{}m", mins.max(1))
     }
+}
 
-    // Update tray
-    let title = get_tray_title(&app_state);
-    let state_clone = app_state.clone();
-    drop(app_state);
+// A reading this old gets a "stale" row in the menu, unless `stale_after_mins` says otherwise
+const DEFAULT_STALE_AFTER_MINS: i64 = 30;
 
-    if let Some(tray) = app.tray_by_id("main") {
-        let _ = tray.set_title(Some(&title));
-        if let Ok(menu) = build_menu(&app, &state_clone) {
-            let _ = tray.set_menu(Some(menu));
+// Age of the current reading, once it's past the stale threshold
+fn stale_age(state: &AppState, now: chrono::DateTime<chrono::Local>) -> Option<chrono::Duration> {
+    let at = parse_reading_timestamp(state.usage.timestamp.as_deref()?)?;
+    let age = now.signed_duration_since(at);
+    (age.num_minutes() >= state.stale_after_mins.unwrap_or(DEFAULT_STALE_AFTER_MINS)).then_some(age)
+}
+
+fn stale_text(age: chrono::Duration) -> String {
+    tr(Msg::Stale, &[&format_hours_minutes(age)])
+}
+
+fn time_to_green_text(resets: &str) -> String {
+    match parse_reset_time(resets) {
+        Some(reset_time) => {
+            let remaining = reset_time.signed_duration_since(chrono::Local::now());
+            if remaining.num_seconds() > 0 {
+                tr(Msg::UsableAgainIn, &[&format_hours_minutes(remaining)])
+            } else {
+                text(Msg::LimitShouldHaveReset).to_string()
+            }
         }
+        None => tr(Msg::LimitReached, &[&resets]),
     }
-
-    Ok(data)
 }
 
 fn get_usage_script() -> String {
@@ -291,6 +1797,7 @@ if [ $? -ne 0 ]; then
 fi
 
 tmux send-keys -t "$SESSION" "claude --dangerously-skip-permissions" Enter
+echo "progress:launched" >&2
 sleep 5
 
 tmux send-keys -t "$SESSION" "/usage"
@@ -299,6 +1806,7 @@ tmux send-keys -t "$SESSION" Enter
 sleep 4
 
 tmux capture-pane -t "$SESSION" -p -S -50 > "$OUTPUT_FILE"
+echo "progress:captured" >&2
 tmux send-keys -t "$SESSION" "/exit" Enter
 sleep 1
 
@@ -317,7 +1825,8 @@ result = {
     "timestamp": datetime.now().isoformat(),
     "session": {"percent": None, "resets": None},
     "weekly_all": {"percent": None, "resets": None},
-    "weekly_sonnet": {"percent": None, "resets": None}
+    "weekly_sonnet": {"percent": None, "resets": None},
+    "weekly_opus": {"percent": None, "resets": None}
 }
 
 current_section = None
@@ -328,6 +1837,8 @@ for line in content.split("\n"):
         current_section = "weekly_all"
     elif "Current week (Sonnet only)" in line:
         current_section = "weekly_sonnet"
+    elif "Current week (Opus" in line:
+        current_section = "weekly_opus"
 
     if pct := re.search(r'(\d+)%\s*used', line):
         if current_section:
@@ -346,45 +1857,86 @@ PYTHON
 "#.to_string()
 }
 
-fn fetch_usage() -> UsageData {
-    let script = get_usage_script();
-    let output = Command::new("bash")
-        .arg("-c")
-        .arg(&script)
-        .output();
+// Run the script, passing its progress markers to `progress` as they arrive
+fn run_usage_script(progress: &mut dyn FnMut(FetchStage)) -> std::io::Result<(std::process::ExitStatus, String, String)> {
+    let mut command = Command::new("bash");
+    command.arg("-c").arg(get_usage_script());
+    if let Some(dir) = profiles::config_dir() {
+        command.env("CLAUDE_CONFIG_DIR", dir);
+    }
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let mut stdout_pipe = child.stdout.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut stdout = String::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_string(&mut stdout);
+        }
+        stdout
+    });
+    let mut stderr = String::new();
+    if let Some(pipe) = child.stderr.take() {
+        for line in std::io::BufReader::new(pipe).lines().map_while(Result::ok) {
+            match FetchStage::from_marker(&line) {
+                Some(stage) => progress(stage),
+                None => {
+                    stderr.push_str(&line);
+                    stderr.push('\n');
+                }
+            }
+        }
+    }
+    let status = child.wait()?;
+    Ok((status, stdout_reader.join().unwrap_or_default(), stderr))
+}
 
-    match output {
-        Ok(out) if out.status.success() => {
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            serde_json::from_str(&stdout).unwrap_or_else(|e| {
+// The reading, and the script's raw output when that's what failed
+fn fetch_usage(progress: &mut dyn FnMut(FetchStage)) -> (UsageData, Option<String>) {
+    match run_usage_script(progress) {
+        Ok((status, stdout, _)) if status.success() => match serde_json::from_str(&stdout) {
+            Ok(data) => {
+                progress(FetchStage::Parsed);
+                (data, None)
+            }
+            Err(e) => {
                 let error_msg = e.to_string();
                 save_error_log(&stdout, &error_msg);
                 let error_path = get_error_log_path();
-                UsageData {
+                let data = UsageData {
                     error: Some(format!("Parse error (see {})", error_path.display())),
                     ..Default::default()
-                }
-            })
-        }
-        Ok(out) => {
-            let stderr = String::from_utf8_lossy(&out.stderr);
+                };
+                (data, Some(stdout))
+            }
+        },
+        Ok((_, _, stderr)) => {
             save_error_log(&stderr, "Script failed");
-            UsageData {
+            let data = UsageData {
                 error: Some(format!("Script failed: {}", stderr)),
                 ..Default::default()
-            }
+            };
+            (data, Some(stderr))
+        }
+        Err(e) => {
+            let data = UsageData {
+                error: Some(format!("Failed to run script: {}", e)),
+                ..Default::default()
+            };
+            (data, None)
         }
-        Err(e) => UsageData {
-            error: Some(format!("Failed to run script: {}", e)),
-            ..Default::default()
-        },
     }
 }
 
 fn parse_reset_time(resets: &str) -> Option<chrono::DateTime<chrono::Local>> {
-    use chrono::{Local, NaiveTime, NaiveDate, TimeZone, Datelike};
+    parse_reset_time_at(resets, chrono::Local::now())
+}
 
-    let now = Local::now();
+// Resolve a reset string relative to `now` (the moment it was read) - "3pm"
+// means the next 3pm after that moment
+fn parse_reset_time_at(
+    resets: &str,
+    now: chrono::DateTime<chrono::Local>,
+) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::{Local, NaiveTime, NaiveDate, TimeZone, Datelike};
 
     // Try to parse time like "3pm" or "3:59pm"
     fn parse_time(s: &str) -> Option<NaiveTime> {
@@ -457,15 +2009,15 @@ fn format_duration(duration: chrono::Duration) -> String {
     let hours = total_hours % 24;
 
     if days > 0 {
-        format!("{}d {}h left", days, hours)
+        tr(Msg::DaysHoursLeft, &[&days, &hours])
     } else if hours > 0 {
-        format!("{}h left", hours)
+        tr(Msg::HoursLeft, &[&hours])
     } else {
         let mins = duration.num_minutes();
         if mins > 0 {
-            format!("{}m left", mins)
+            tr(Msg::MinutesLeft, &[&mins])
         } else {
-            "soon".to_string()
+            text(Msg::Soon).to_string()
         }
     }
 }
@@ -478,56 +2030,126 @@ fn format_time_remaining(resets: &str) -> String {
         if duration.num_seconds() > 0 {
             return format_duration(duration);
         }
+        let pattern = if reset_time.date_naive() == now.date_naive() {
+            locale::clock(false).to_string()
+        } else {
+            locale::date_clock(false)
+        };
+        return tr(Msg::Resets, &[&reset_time.format(&pattern)]);
     }
 
     // Fallback to showing the raw reset time
     if resets.contains("at") {
-        format!("Resets {}", resets)
+        tr(Msg::Resets, &[&resets])
     } else {
-        format!("Resets today {}", resets)
-    }
-}
-
-// Get status based on usage vs time elapsed
-// period_hours: total period length (4 for session, 168 for week)
-fn get_status_indicator_paced(usage_percent: i32, resets: Option<&str>, period_hours: i32) -> &'static str {
-    // Calculate how much time has elapsed as a percentage
-    let time_percent = if let Some(reset_str) = resets {
-        if let Some(reset_time) = parse_reset_time(reset_str) {
-            let now = chrono::Local::now();
-            let remaining = reset_time.signed_duration_since(now);
-            let remaining_hours = remaining.num_hours() as i32;
-            let elapsed_hours = period_hours - remaining_hours;
-            if period_hours > 0 {
-                ((elapsed_hours as f32 / period_hours as f32) * 100.0) as i32
-            } else {
-                50 // fallback
-            }
+        tr(Msg::ResetsToday, &[&resets])
+    }
+}
+
+// Get status based on usage vs time elapsed in the metric's window
+fn get_status_indicator_paced(usage_percent: i32, resets: Option<&str>, metric: MenuMetric) -> &'static str {
+    let reset_time = resets.and_then(parse_reset_time);
+    pace_indicator_at(usage_percent, reset_time, metric, chrono::Local::now())
+}
+
+// How much of the window has elapsed at `now`, as a percentage
+fn elapsed_percent(
+    reset_time: Option<chrono::DateTime<chrono::Local>>,
+    period_hours: i32,
+    now: chrono::DateTime<chrono::Local>,
+) -> i32 {
+    if let Some(reset_time) = reset_time {
+        let remaining = reset_time.signed_duration_since(now);
+        let remaining_hours = remaining.num_hours() as i32;
+        let elapsed_hours = period_hours - remaining_hours;
+        if period_hours > 0 {
+            ((elapsed_hours as f32 / period_hours as f32) * 100.0) as i32
         } else {
-            50 // can't parse, assume midpoint
+            50 // fallback
         }
     } else {
-        50 // no reset info, assume midpoint
+        50 // no or unparseable reset info, assume midpoint
+    }
+}
+
+// The pace indicator as of `now`, for readings other than the current one
+fn pace_indicator_at(
+    usage_percent: i32,
+    reset_time: Option<chrono::DateTime<chrono::Local>>,
+    metric: MenuMetric,
+    now: chrono::DateTime<chrono::Local>,
+) -> &'static str {
+    // Compare usage to time elapsed; by default 20+ points ahead (or 90%+
+    // used) is red, 10+ orange, ahead at all yellow, otherwise green. The
+    // pacing mode decides how elapsed time is counted.
+    pace::indicator(metric, usage_percent, reset_time, now)
+}
+
+const INDICATORS_BY_SEVERITY: [&str; 4] = ["🟢", "🟡", "🟠", "🔴"];
+
+// "green" through "red", for output that can't show the emoji
+fn indicator_color(indicator: &str) -> &'static str {
+    const COLORS: [&str; 4] = ["green", "yellow", "orange", "red"];
+    INDICATORS_BY_SEVERITY
+        .iter()
+        .position(|i| *i == indicator)
+        .map_or("green", |i| COLORS[i])
+}
+
+// The most severe pace indicator across the visible metrics the tray follows
+// that have a reading
+fn worst_pace_indicator(usage: &UsageData, metric: TrayMetric, visible: &[MenuMetric]) -> &'static str {
+    let followed: &[MenuMetric] = match metric {
+        TrayMetric::Both => &MenuMetric::ALL,
+        TrayMetric::Session => &[MenuMetric::Session],
+        TrayMetric::Weekly => &[MenuMetric::Weekly],
     };
+    followed
+        .iter()
+        .filter(|m| visible.contains(m))
+        .filter_map(|m| {
+            let item = m.item(usage);
+            Some(get_status_indicator_paced(item.percent?, item.resets.as_deref(), *m))
+        })
+        .max_by_key(|indicator| INDICATORS_BY_SEVERITY.iter().position(|i| i == indicator))
+        .unwrap_or("🟢")
+}
 
-    // Compare usage to time elapsed
-    // If usage is 20%+ ahead of time, red
-    // If usage is 10%+ ahead of time, orange
-    // If usage is ahead but <10%, yellow
-    // Otherwise green
-    let pace_diff = usage_percent - time_percent;
-
-    if usage_percent >= 90 {
-        "🔴" // Always red at 90%+
-    } else if pace_diff >= 20 {
-        "🔴"
-    } else if pace_diff >= 10 {
-        "🟠"
-    } else if pace_diff > 0 {
-        "🟡"
-    } else {
-        "🟢"
-    }
+// "🟢 Session: 42% (↑5) | 2h 15m left". Session and weekly always get a line;
+// Sonnet and Opus only when the reading has them.
+fn metric_line(state: &AppState, metric: MenuMetric) -> Option<String> {
+    let item = metric.item(&state.usage);
+    let percent = match metric {
+        MenuMetric::Sonnet | MenuMetric::Opus => item.percent?,
+        _ => item.percent.unwrap_or(0),
+    };
+    let resets = item.resets.as_deref();
+    let indicator = get_status_indicator_paced(percent, resets, metric);
+    let previous = state.previous_usage.as_ref().and_then(|p| metric.item(p).percent);
+    let delta = delta_suffix(previous, item.percent);
+    let line = format!("{} {}: {}%{}", indicator, metric.label(), percent, delta);
+    Some(match metric {
+        MenuMetric::Session | MenuMetric::Weekly => {
+            format!("{} | {}", line, format_time_remaining(resets.unwrap_or("--")))
+        }
+        MenuMetric::Sonnet | MenuMetric::Opus => line,
+    })
+}
+
+// Burn rate projection under the session and weekly lines
+fn metric_projection(state: &AppState, metric: MenuMetric, now: chrono::DateTime<chrono::Local>) -> Option<String> {
+    let rate = match metric {
+        MenuMetric::Session => state.trends.session_rate(),
+        MenuMetric::Weekly => state.trends.weekly_rate(),
+        MenuMetric::Sonnet | MenuMetric::Opus => return None,
+    };
+    let item = metric.item(&state.usage);
+    trend::projection_text(
+        item.percent.unwrap_or(0),
+        rate,
+        item.resets.as_deref().and_then(parse_reset_time),
+        now,
+    )
 }
 
 fn build_menu<R: Runtime>(app: &tauri::AppHandle<R>, state: &AppState) -> tauri::Result<Menu<R>> {
@@ -536,45 +2158,61 @@ fn build_menu<R: Runtime>(app: &tauri::AppHandle<R>, state: &AppState) -> tauri:
 
     // Show error if present
     if let Some(ref err) = state.last_error {
-        let err_text = format!("⚠️ {}", err);
+        let err_text = if state.guest_mode {
+            text(Msg::LastRefreshFailed).to_string()
+        } else {
+            format!("⚠️ {}", err)
+        };
         menu.append(&MenuItem::new(app, &err_text, false, None::<&str>)?)?;
         // Add option to view error log if it exists
         let error_log_path = get_error_log_path();
-        if error_log_path.exists() {
-            let view_error = MenuItem::with_id(app, "view_error_log", "View Error Log...", true, None::<&str>)?;
+        if !state.guest_mode && error_log_path.exists() {
+            let view_error = MenuItem::with_id(app, "view_error_log", text(Msg::ViewErrorLog), true, None::<&str>)?;
             menu.append(&view_error)?;
         }
         menu.append(&MenuItem::new(app, "─────────────", false, None::<&str>)?)?;
     }
 
-    // Session info (4 hour period for Opus)
-    let session_pct = usage.session.percent.unwrap_or(0);
-    let session_reset = usage.session.resets.as_deref();
-    let session_indicator = get_status_indicator_paced(session_pct, session_reset, 4);
-    let session_reset_display = session_reset.unwrap_or("--");
-    let session_text = format!(
-        "{} Session: {}% | {}",
-        session_indicator, session_pct, format_time_remaining(session_reset_display)
-    );
-    menu.append(&MenuItem::new(app, &session_text, false, None::<&str>)?)?;
-
-    // Weekly all models (7 day = 168 hour period)
-    let weekly_pct = usage.weekly_all.percent.unwrap_or(0);
-    let weekly_reset = usage.weekly_all.resets.as_deref();
-    let weekly_indicator = get_status_indicator_paced(weekly_pct, weekly_reset, 168);
-    let weekly_reset_display = weekly_reset.unwrap_or("--");
-    let weekly_text = format!(
-        "{} Weekly (all): {}% | {}",
-        weekly_indicator, weekly_pct, format_time_remaining(weekly_reset_display)
-    );
-    menu.append(&MenuItem::new(app, &weekly_text, false, None::<&str>)?)?;
+    if let Some(age) = stale_age(state, chrono::Local::now()) {
+        menu.append(&MenuItem::new(app, stale_text(age), false, None::<&str>)?)?;
+    }
+
+    if let Some(until) = state.away_until.filter(|_| away::is_away(state)) {
+        let away_text = tr(Msg::AwayUntil, &[&until.format("%b %d")]);
+        menu.append(&MenuItem::new(app, &away_text, false, None::<&str>)?)?;
+    }
+
+    if let Some(pause_text) = pause::status_text(state, chrono::Local::now()) {
+        menu.append(&MenuItem::new(app, &pause_text, false, None::<&str>)?)?;
+    }
+
+    if let Some(snooze_text) = notify::snooze_text(state, chrono::Local::now()) {
+        menu.append(&MenuItem::new(app, &snooze_text, false, None::<&str>)?)?;
+    }
 
-    // Weekly Sonnet (also 7 day period)
-    if let Some(sonnet_pct) = usage.weekly_sonnet.percent {
-        let sonnet_reset = usage.weekly_sonnet.resets.as_deref();
-        let sonnet_indicator = get_status_indicator_paced(sonnet_pct, sonnet_reset, 168);
-        let sonnet_text = format!("{} Weekly (Sonnet): {}%", sonnet_indicator, sonnet_pct);
-        menu.append(&MenuItem::new(app, &sonnet_text, false, None::<&str>)?)?;
+    if let Some(warning) = trend::weekly_warning(
+        usage.weekly_all.percent.unwrap_or(0),
+        state.trends.weekly_rate(),
+        usage.weekly_all.resets.as_deref().and_then(parse_reset_time),
+        chrono::Local::now(),
+    ) {
+        menu.append(&MenuItem::new(app, &warning, false, None::<&str>)?)?;
+    }
+
+    // At a limit, the countdown to being usable again is what matters most
+    if let Some(resets) = blocking_reset(usage) {
+        menu.append(&MenuItem::new(app, time_to_green_text(resets), false, None::<&str>)?)?;
+    }
+
+    let now = chrono::Local::now();
+    for &metric in visible_metrics(state) {
+        let Some(line) = metric_line(state, metric) else {
+            continue;
+        };
+        menu.append(&MenuItem::new(app, &line, false, None::<&str>)?)?;
+        if let Some(text) = metric_projection(state, metric, now) {
+            menu.append(&MenuItem::new(app, format!("    {}", text), false, None::<&str>)?)?;
+        }
     }
 
     // Timestamp - show absolute time (HH:mm:ss if today, otherwise date + time)
@@ -588,52 +2226,257 @@ fn build_menu<R: Runtime>(app: &tauri::AppHandle<R>, state: &AppState) -> tauri:
 
                 if today == parsed_date {
                     // Same day - just show time
-                    parsed.format("%H:%M:%S").to_string()
+                    parsed.format(locale::clock(true)).to_string()
                 } else {
                     // Different day - show date and time
-                    parsed.format("%b %d %H:%M:%S").to_string()
+                    parsed.format(&locale::date_clock(true)).to_string()
                 }
             } else {
                 ts.clone()
             }
         };
-        menu.append(&MenuItem::new(app, &format!("Updated: {}", display), false, None::<&str>)?)?;
+        menu.append(&MenuItem::new(app, tr(Msg::Updated, &[&display]), false, None::<&str>)?)?;
     }
 
     // Separator and actions
     menu.append(&MenuItem::new(app, "─────────────", false, None::<&str>)?)?;
 
-    let charts = MenuItem::with_id(app, "charts", "Show Charts...", true, None::<&str>)?;
+    if !state.trends.latest.is_empty() {
+        let now = chrono::Local::now();
+        let rows = state
+            .trends
+            .latest
+            .iter()
+            .rev()
+            .map(|reading| MenuItem::new(app, trend::reading_label(reading, now), false, None::<&str>))
+            .collect::<tauri::Result<Vec<_>>>()?;
+        let row_refs: Vec<&dyn tauri::menu::IsMenuItem<R>> =
+            rows.iter().map(|i| i as &dyn tauri::menu::IsMenuItem<R>).collect();
+        menu.append(&Submenu::with_items(app, text(Msg::History), true, &row_refs)?)?;
+    }
+
+    // With sync on and another machine heard from
+    let machine_lines = match state.guest_mode {
+        true => None,
+        false => machines::machines(usage).and_then(|m| machines::menu_lines(&m, visible_metrics(state))),
+    };
+    if let Some(lines) = machine_lines {
+        let rows = lines
+            .iter()
+            .map(|line| MenuItem::new(app, line, false, None::<&str>))
+            .collect::<tauri::Result<Vec<_>>>()?;
+        let row_refs: Vec<&dyn tauri::menu::IsMenuItem<R>> =
+            rows.iter().map(|i| i as &dyn tauri::menu::IsMenuItem<R>).collect();
+        menu.append(&Submenu::with_items(app, text(Msg::Machines), true, &row_refs)?)?;
+    }
+
+    let charts = MenuItem::with_id(app, "charts", text(Msg::ShowCharts), true, None::<&str>)?;
     menu.append(&charts)?;
 
-    let refresh = MenuItem::with_id(app, "refresh", "Refresh Now", true, None::<&str>)?;
-    menu.append(&refresh)?;
+    menu.append(&MenuItem::with_id(app, "copy_json", text(Msg::CopyJson), true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "copy_markdown", text(Msg::CopyMarkdown), true, None::<&str>)?)?;
+
+    if !state.guest_mode {
+        let export_menu = Submenu::with_items(
+            app,
+            text(Msg::ExportHistory),
+            true,
+            &[
+                &MenuItem::with_id(app, "export_csv", text(Msg::AsCsv), true, None::<&str>)?,
+                &MenuItem::with_id(app, "export_json", text(Msg::AsJson), true, None::<&str>)?,
+            ],
+        )?;
+        menu.append(&export_menu)?;
+        menu.append(&MenuItem::with_id(app, "export_report", text(Msg::ExportWeeklyReport), true, None::<&str>)?)?;
+        let calendar = MenuItem::with_id(app, "add_resets_to_calendar", text(Msg::AddResetsToCalendar), true, None::<&str>)?;
+        menu.append(&calendar)?;
+
+        let backup = MenuItem::with_id(app, "backup", text(Msg::BackUpData), true, None::<&str>)?;
+        menu.append(&backup)?;
+
+        let clear = MenuItem::with_id(app, "clear_history", text(Msg::ClearHistory), true, None::<&str>)?;
+        menu.append(&clear)?;
+
+        menu.append(&MenuItem::with_id(app, "open_data_folder", text(Msg::OpenDataFolder), true, None::<&str>)?)?;
+        let log_exists = get_log_path().exists();
+        menu.append(&MenuItem::with_id(app, "open_log", text(Msg::OpenLogFile), log_exists, None::<&str>)?)?;
+        menu.append(&MenuItem::with_id(app, "recent_errors", text(Msg::RecentErrors), true, None::<&str>)?)?;
+    }
+
+    match state.manual_refresh_started {
+        Some(started) => menu.append(&MenuItem::new(app, refreshing_label(started), false, None::<&str>)?)?,
+        None => menu.append(&MenuItem::with_id(app, "refresh", text(Msg::RefreshNow), true, None::<&str>)?)?,
+    }
 
     // Toggle for showing percentages in menu bar
     let toggle_label = if state.show_percentages {
-        "Hide Percentages in Menu Bar"
+        text(Msg::HidePercentages)
     } else {
-        "Show Percentages in Menu Bar"
+        text(Msg::ShowPercentages)
     };
     let toggle = MenuItem::with_id(app, "toggle_percentages", toggle_label, true, None::<&str>)?;
     menu.append(&toggle)?;
 
-    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let profile_list = profiles::all();
+    if !profile_list.is_empty() && !state.guest_mode {
+        let active = profiles::active_name();
+        let mut profile_items = vec![CheckMenuItem::with_id(
+            app,
+            profiles::menu_id(None),
+            text(Msg::DefaultProfile),
+            true,
+            active.is_none(),
+            None::<&str>,
+        )?];
+        for profile in &profile_list {
+            let checked = active.as_deref() == Some(profile.name.as_str());
+            profile_items.push(CheckMenuItem::with_id(
+                app,
+                profiles::menu_id(Some(&profile.name)),
+                &profile.name,
+                true,
+                checked,
+                None::<&str>,
+            )?);
+        }
+        let profile_refs: Vec<&dyn tauri::menu::IsMenuItem<R>> =
+            profile_items.iter().map(|i| i as &dyn tauri::menu::IsMenuItem<R>).collect();
+        menu.append(&Submenu::with_items(app, text(Msg::Profile), true, &profile_refs)?)?;
+    }
+
+    let metric_items = TrayMetric::ALL
+        .into_iter()
+        .map(|m| CheckMenuItem::with_id(app, m.menu_id(), m.label(), true, m == state.tray_metric, None::<&str>))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let metric_refs: Vec<&dyn tauri::menu::IsMenuItem<R>> =
+        metric_items.iter().map(|i| i as &dyn tauri::menu::IsMenuItem<R>).collect();
+    menu.append(&Submenu::with_items(app, text(Msg::MenuBarShows), true, &metric_refs)?)?;
+
+    let guest_label = if state.guest_mode {
+        text(Msg::ExitGuestMode)
+    } else {
+        text(Msg::GuestMode)
+    };
+    let guest = MenuItem::with_id(app, "toggle_guest_mode", guest_label, true, None::<&str>)?;
+    menu.append(&guest)?;
+    if !state.guest_mode {
+        let autostart = autostart::is_enabled(app);
+        menu.append(&CheckMenuItem::with_id(app, "toggle_autostart", text(Msg::StartAtLogin), true, autostart, None::<&str>)?)?;
+    }
+
+    if state.escalation.pending() {
+        menu.append(&MenuItem::with_id(app, "ack_escalation", text(Msg::AcknowledgeAlert), true, None::<&str>)?)?;
+    }
+    if notify::is_snoozed(state, chrono::Local::now()) {
+        menu.append(&MenuItem::with_id(app, "snooze_end", text(Msg::UnsnoozeAlerts), true, None::<&str>)?)?;
+    } else {
+        let snooze_menu = Submenu::with_items(
+            app,
+            text(Msg::SnoozeAlerts),
+            true,
+            &[
+                &MenuItem::with_id(app, "snooze_1h", text(Msg::ForOneHour), true, None::<&str>)?,
+                &MenuItem::with_id(app, "snooze_4h", text(Msg::ForFourHours), true, None::<&str>)?,
+                &MenuItem::with_id(app, "snooze_today", text(Msg::ForToday), true, None::<&str>)?,
+            ],
+        )?;
+        menu.append(&snooze_menu)?;
+    }
+
+    if pause::is_paused(state, chrono::Local::now()) {
+        menu.append(&MenuItem::with_id(app, "pause_resume", text(Msg::ResumeAutoRefresh), true, None::<&str>)?)?;
+    } else {
+        let session_reset = usage.session.resets.as_deref().and_then(parse_reset_time);
+        let pause_menu = Submenu::with_items(
+            app,
+            text(Msg::PauseAutoRefresh),
+            true,
+            &[
+                &MenuItem::with_id(app, "pause_1h", text(Msg::ForOneHour), true, None::<&str>)?,
+                &MenuItem::with_id(app, "pause_reset", text(Msg::UntilSessionReset), session_reset.is_some(), None::<&str>)?,
+                &MenuItem::with_id(app, "pause_indefinite", text(Msg::UntilResumed), true, None::<&str>)?,
+            ],
+        )?;
+        menu.append(&pause_menu)?;
+    }
+
+    if away::is_away(state) {
+        menu.append(&MenuItem::with_id(app, "away_end", text(Msg::ImBack), true, None::<&str>)?)?;
+    } else {
+        let away_menu = Submenu::with_items(
+            app,
+            text(Msg::AwayMode),
+            true,
+            &[
+                &MenuItem::with_id(app, "away_7", text(Msg::AwayOneWeek), true, None::<&str>)?,
+                &MenuItem::with_id(app, "away_14", text(Msg::AwayTwoWeeks), true, None::<&str>)?,
+                &MenuItem::with_id(app, "away_custom", text(Msg::AwayCustom), true, None::<&str>)?,
+            ],
+        )?;
+        menu.append(&away_menu)?;
+    }
+
+    if !state.guest_mode {
+        menu.append(&MenuItem::with_id(app, "settings", text(Msg::Settings), true, None::<&str>)?)?;
+    }
+
+    let about = MenuItem::with_id(app, "about", text(Msg::About), true, None::<&str>)?;
+    menu.append(&about)?;
+    menu.append(&MenuItem::with_id(app, "check_updates", text(Msg::CheckForUpdates), true, None::<&str>)?)?;
+
+    let quit = MenuItem::with_id(app, "quit", text(Msg::Quit), true, None::<&str>)?;
     menu.append(&quit)?;
 
     Ok(menu)
 }
 
+fn update_tray<R: Runtime>(app: &tauri::AppHandle<R>, state: &AppState) {
+    let settings = load_settings();
+    write_status_files(state, &settings);
+    if let Some(tray) = app.tray_by_id("main") {
+        let _ = tray.set_title(Some(&get_tray_title(state)));
+        let style = icon::IconStyle::from_setting(settings.icon_style.as_deref());
+        let appearance = icon::IconAppearance::from_setting(settings.icon_appearance.as_deref());
+        let status = worst_pace_indicator(&state.usage, state.tray_metric, visible_metrics(state));
+        let percent = state.tray_metric.icon_percent(&state.usage);
+        let _ = tray.set_icon(Some(icon::tray_icon(style, appearance, percent, status, &state.trends.session)));
+        let _ = tray.set_icon_as_template(appearance == icon::IconAppearance::Template);
+        let click = popover::TrayClick::from_setting(settings.tray_click.as_deref());
+        let _ = tray.set_show_menu_on_left_click(click == popover::TrayClick::Menu);
+        if let Ok(menu) = build_menu(app, state) {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+}
+
+// The files other tools read the reading from, rewritten with the tray
+fn write_status_files(state: &AppState, settings: &Settings) {
+    tmux::write_status(state, settings);
+    prompt::write_snapshot(state, settings);
+    snapshot::write(state, settings);
+}
+
 fn get_tray_title(state: &AppState) -> String {
     if state.last_error.is_some() {
         "⚠️".to_string()
     } else if state.usage.session.percent.is_some() {
         if state.show_percentages {
-            format!(
-                "{}% {}%",
-                state.usage.session.percent.unwrap_or(0),
-                state.usage.weekly_all.percent.unwrap_or(0)
-            )
+            let followed: &[MenuMetric] = match state.tray_metric {
+                TrayMetric::Both => &[MenuMetric::Session, MenuMetric::Weekly],
+                TrayMetric::Session => &[MenuMetric::Session],
+                TrayMetric::Weekly => &[MenuMetric::Weekly],
+            };
+            let title = followed
+                .iter()
+                .filter(|m| visible_metrics(state).contains(m))
+                .map(|m| format!("{}%", m.item(&state.usage).percent.unwrap_or(0)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if state.stale_title_marker && stale_age(state, chrono::Local::now()).is_some() {
+                format!("{}*", title)
+            } else {
+                title
+            }
         } else {
             // Just show icon (the tray icon), no text
             String::new()
@@ -643,34 +2486,334 @@ fn get_tray_title(state: &AppState) -> String {
     }
 }
 
-// Load the orange asterisk tray icon
-fn load_tray_icon() -> Image<'static> {
-    let icon_bytes = include_bytes!("../icons/tray-icon.png");
-    Image::from_bytes(icon_bytes).expect("Failed to load tray icon")
+// Stands in for "Refresh Now" while its fetch runs (~15s), so it isn't clicked again
+fn refreshing_label(started: chrono::DateTime<chrono::Local>) -> String {
+    tr(Msg::RefreshingStarted, &[&started.format(locale::clock(true))])
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    // Load cached data and settings on startup
-    let initial_usage = load_cached_usage().unwrap_or_default();
-    let settings = load_settings();
+// Open or focus the usage window
+fn show_charts_window<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("usage") {
+        let _ = window.set_focus();
+    } else {
+        let window = WebviewWindowBuilder::new(app, "usage", tauri::WebviewUrl::App("index.html".into()))
+            .title("Claude Usage")
+            .inner_size(700.0, 700.0)
+            .resizable(true)
+            .build();
+        if let Ok(window) = window {
+            windowstate::restore(&window);
+        }
+        dock::update(app, None);
+    }
+}
+
+// A notification button was clicked; called from the notifier's thread
+fn perform_alert_action(app: &tauri::AppHandle, action: notify::AlertAction) {
+    match action {
+        notify::AlertAction::Refresh => {
+            let state: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+            spawn_manual_refresh(app.clone(), state.inner().clone());
+        }
+        notify::AlertAction::ShowCharts => show_charts_window(app),
+    }
+}
+
+fn spawn_manual_refresh<R: Runtime>(app: tauri::AppHandle<R>, state: Arc<Mutex<AppState>>) {
+    spawn_triggered_refresh(app, state, "menu");
+}
+
+// A fetch outside the schedule, logged under `trigger`; ignored while one is
+// already running
+fn spawn_triggered_refresh<R: Runtime>(app: tauri::AppHandle<R>, state: Arc<Mutex<AppState>>, trigger: &'static str) {
+    {
+        let mut state = state.lock().unwrap();
+        if state.manual_refresh_started.is_some() {
+            return;
+        }
+        state.manual_refresh_started = Some(chrono::Local::now());
+        update_tray(&app, &state);
+    }
+    // Run fetch in background to avoid blocking UI
+    std::thread::spawn(move || {
+        let data = fetch_usage_logged(&app, trigger);
+        let mut state = state.lock().unwrap();
+        let effects = apply_fetch_result(&mut state, data);
+        state.manual_refresh_started = None;
+        let state_clone = state.clone();
+        drop(state);
+        emit_fetch_result(&app, effects, &state_clone);
+        update_tray(&app, &state_clone);
+    });
+}
+
+// The refresh loop's sleep: the refresh interval, backed off after errors
+fn secs_until_next_fetch(state: &AppState) -> u64 {
+    let interval = settings::refresh_interval_secs(&load_settings());
+    let mut sleep_secs = if state.consecutive_errors > 0 {
+        interval * std::cmp::min(state.consecutive_errors, 3) as u64
+    } else {
+        interval
+    };
+    // While blocked at a limit, fetch right after the reset to confirm it
+    if let Some(wait) = blocking_reset(&state.usage).and_then(|r| post_reset_wait_secs(r, chrono::Local::now())) {
+        sleep_secs = sleep_secs.min(wait);
+    }
+    // Wake up on the return date rather than up to an interval later
+    if let Some(wait) = away::secs_until_return(state) {
+        sleep_secs = sleep_secs.min(wait.max(1));
+    }
+    if let Some(wait) = pause::secs_until_resume(state, chrono::Local::now()) {
+        sleep_secs = sleep_secs.min(wait.max(1));
+    }
+    sleep_secs
+}
+
+// No fetches while away or paused; once the return date passes, close out the period
+fn scheduled_fetch_due(state: &Arc<Mutex<AppState>>) -> bool {
+    let (away, returning, paused) = {
+        let state = state.lock().unwrap();
+        (
+            away::is_away(&state),
+            state.away_until.is_some(),
+            pause::is_paused(&state, chrono::Local::now()),
+        )
+    };
+    if away || paused {
+        return false;
+    }
+    if returning {
+        away::end_away(state);
+    }
+    true
+}
+
+fn spawn_refresh_loop<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    state: Arc<Mutex<AppState>>,
+    heartbeat: Arc<RefreshHeartbeat>,
+) {
+    let generation = heartbeat.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    // Give the new loop a full interval before the watchdog judges it
+    heartbeat.record_attempt();
+
+    std::thread::spawn(move || {
+        let mut first_run = true;
+        let superseded = || heartbeat.generation.load(Ordering::SeqCst) != generation;
+
+        loop {
+            if !first_run {
+                let sleep_secs = secs_until_next_fetch(&state.lock().unwrap());
+                heartbeat.sleep_secs.store(sleep_secs, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_secs(sleep_secs));
+            }
+            first_run = false;
+
+            if superseded() {
+                return;
+            }
+            heartbeat.record_attempt();
+            if !scheduled_fetch_due(&state) {
+                continue;
+            }
+
+            let data = fetch_usage_logged(&app, "scheduled");
+            if superseded() {
+                return;
+            }
+
+            let mut state = state.lock().unwrap();
+            let effects = apply_fetch_result(&mut state, data);
+            let state_clone = state.clone();
+            drop(state);
+            emit_fetch_result(&app, effects, &state_clone);
+            update_tray(&app, &state_clone);
+        }
+    });
+}
+
+// Seconds until the next minute boundary, so "Xh Ym left" flips on time
+fn secs_until_next_tick(now: chrono::DateTime<chrono::Local>) -> u64 {
+    use chrono::Timelike;
+    (COUNTDOWN_TICK_SECS - now.second() % COUNTDOWN_TICK_SECS) as u64
+}
+
+// Rebuild the title, icon, and menu every minute from the last reading so the
+// countdowns and pace colors stay current between fetches (no fetch happens here)
+fn spawn_countdown_ticker<R: Runtime>(app: tauri::AppHandle<R>, state: Arc<Mutex<AppState>>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(secs_until_next_tick(chrono::Local::now())));
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        minute_tick(&mut state, &load_settings(), chrono::Local::now());
+        update_tray(&app, &state);
+    });
+}
 
-    let app_state: Arc<Mutex<AppState>> = Arc::new(Mutex::new(AppState {
-        usage: initial_usage,
+// What's due each minute besides the tray: held alerts, the daily summaries
+fn minute_tick(state: &mut AppState, settings: &Settings, now: chrono::DateTime<chrono::Local>) {
+    quiet::flush_if_over(state, settings, now);
+    slack::send_daily_summary_if_due(state, settings, now);
+    dailynote::append_if_due(state, settings, now);
+}
+
+// Restart the refresh loop if it stops recording attempts (panicked thread,
+// deadlock, or a fetch that never returns)
+fn spawn_watchdog<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    state: Arc<Mutex<AppState>>,
+    heartbeat: Arc<RefreshHeartbeat>,
+) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(WATCHDOG_CHECK_SECS));
+
+        let now = heartbeat.now();
+        let last_attempt = heartbeat.last_attempt.load(Ordering::SeqCst);
+        let sleep_secs = heartbeat.sleep_secs.load(Ordering::SeqCst);
+        if !is_refresh_stalled(now, last_attempt, sleep_secs) {
+            continue;
+        }
+
+        let incident = format!(
+            "Watchdog: no fetch attempt for {}s (limit {}s), restarting refresh loop",
+            now - last_attempt,
+            sleep_secs * WATCHDOG_STALL_FACTOR
+        );
+        append_log(&incident);
+
+        heartbeat.restarts.fetch_add(1, Ordering::SeqCst);
+        *heartbeat.last_incident.lock().unwrap_or_else(PoisonError::into_inner) = Some(format!(
+            "{} ({})",
+            incident,
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        ));
+
+        // A panic while holding the lock would poison it for the new loop too.
+        // The state lock itself isn't taken here, since a deadlocked loop may
+        // still hold it.
+        if state.is_poisoned() {
+            state.clear_poison();
+        }
+        spawn_refresh_loop(app.clone(), state.clone(), heartbeat.clone());
+    });
+}
+
+// Language, clock format, pace cutoffs, and window lengths, which the tray
+// and the menu bar subcommands format with
+fn apply_display_settings(settings: &Settings) {
+    locale::set_language(locale::Language::from_setting(settings.language.as_deref()));
+    pace::apply(settings);
+    period::apply(settings);
+    locale::set_time_format(locale::TimeFormat::from_setting(settings.time_format.as_deref()));
+}
+
+// The state at startup, from the cached reading and the settings
+fn initial_state(settings: &Settings) -> AppState {
+    let usage = load_cached_usage().unwrap_or_default();
+    AppState {
+        previous_usage: load_previous_reading(&usage),
+        usage,
         has_network: true,
         show_percentages: settings.show_percentages.unwrap_or(true),
+        guest_mode: settings.guest_mode.unwrap_or(false),
+        away_since: away::parse_stored_time(settings.away_since.as_deref()),
+        away_until: away::parse_stored_time(settings.away_until.as_deref()),
+        trends: trend::Trends::load(),
+        tray_metric: TrayMetric::from_setting(settings.tray_metric.as_deref()),
+        stale_after_mins: settings.stale_after_mins,
+        stale_title_marker: settings.stale_title_marker.unwrap_or(false),
+        menu_metrics: MenuMetric::from_setting(settings.menu_metrics.as_deref()),
         ..Default::default()
-    }));
+    }
+}
+
+// For the app and the daemon: bring the data along if its folder was changed,
+// then load the settings from where they now are. One-off subcommands skip the
+// move, so running one with a different CC_USAGE_DATA_DIR can't take the data
+// out from under a running app.
+fn startup_settings() -> Settings {
+    datadir::migrate_on_startup();
+    let settings = load_settings();
+    profiles::apply(&settings);
+    settings
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // The cache is per profile
+    let settings = load_settings();
+    profiles::apply(&settings);
+
+    // `cc-usage swiftbar` and friends print and exit instead (cli.rs)
+    if let Some(code) = cli::run(&std::env::args().skip(1).collect::<Vec<_>>(), &settings) {
+        std::process::exit(code);
+    }
+
+    // Load settings and cached data on startup
+    let settings = startup_settings();
+    apply_display_settings(&settings);
+    let app_state: Arc<Mutex<AppState>> = Arc::new(Mutex::new(initial_state(&settings)));
 
     tauri::Builder::default()
-        .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {
-            // Another instance tried to start - we could focus window here if we had one
-            // For tray-only app, just ignore
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // Another instance tried to start; `cc-usage refresh` starts one
+            // on purpose (scripting.rs), anything else is ignored
+            scripting::handle_forwarded(app, &args);
         }))
+        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, None))
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state.clone())
-        .invoke_handler(tauri::generate_handler![get_current_usage, get_history, refresh_usage])
+        .on_window_event(|window, event| {
+            windowstate::track(window, event);
+            match event {
+                tauri::WindowEvent::Destroyed => dock::update(window.app_handle(), Some(window.label())),
+                tauri::WindowEvent::ThemeChanged(os) => theme::os_changed(window, *os),
+                _ => {}
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_current_usage,
+            get_history,
+            refresh_usage,
+            get_fetch_log,
+            get_limit_windows,
+            limits::get_limit_events,
+            away::get_annotations,
+            info::get_app_info,
+            stats::get_stats,
+            export::export_history,
+            export::import_history,
+            backup::backup_db,
+            backup::restore_db,
+            encryption::set_database_encryption,
+            datadir::set_data_dir,
+            retention::clear_history,
+            locale::get_time_format,
+            autostart::get_launch_at_login,
+            autostart::set_launch_at_login,
+            settings::open_settings,
+            settings::get_settings,
+            settings::set_settings,
+            trend::predict_exhaustion,
+            comparison::get_weekly_comparison,
+            heatmap::get_heatmap,
+            report::generate_report,
+            errorlog::get_error_log,
+            errorlog::open_raw_output,
+            cost::get_cost_breakdown,
+            retention::delete_history_range,
+            info::get_diagnostics,
+            theme::get_theme,
+            trend::get_burn_rate,
+            machines::get_machines,
+        ])
         .setup(move |app| {
             let handle = app.handle().clone();
+            dock::update(&handle, None);
+            theme::apply(&handle, &load_settings());
+            server::apply(&handle, &load_settings());
+            let app_for_alerts = handle.clone();
+            notify::set_action_handler(move |action| perform_alert_action(&app_for_alerts, action));
+            notify::set_app(handle.clone());
             let state_for_tray = app_state.clone();
             let state_for_menu = app_state.clone();
 
@@ -681,12 +2824,29 @@ pub fn run() {
             drop(initial_state);
 
             // Create tray with ID - only one!
-            let tray_icon = load_tray_icon();
+            let tray_icon = icon::static_icon();
+            let click = popover::TrayClick::from_setting(load_settings().tray_click.as_deref());
             let _tray = TrayIconBuilder::with_id("main")
                 .icon(tray_icon)
                 .menu(&initial_menu)
+                .show_menu_on_left_click(click == popover::TrayClick::Menu)
                 .tooltip("Claude Code Usage")
                 .title(&initial_title)
+                .on_tray_icon_event(|tray, event| {
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        rect,
+                        ..
+                    } = event
+                    {
+                        // Read each time so a change in Settings applies without a restart
+                        let click = popover::TrayClick::from_setting(load_settings().tray_click.as_deref());
+                        if click == popover::TrayClick::Popover {
+                            popover::toggle(tray.app_handle(), rect);
+                        }
+                    }
+                })
                 .on_menu_event(move |app, event| {
                     match event.id.as_ref() {
                         "quit" => {
@@ -698,152 +2858,306 @@ pub fn run() {
                             state.show_percentages = !state.show_percentages;
 
                             // Save setting
-                            save_settings(&Settings {
-                                show_percentages: Some(state.show_percentages),
-                            });
+                            let mut settings = load_saved_settings();
+                            settings.show_percentages = Some(state.show_percentages);
+                            save_settings(&settings);
 
                             // Update tray title and menu
-                            if let Some(tray) = app.tray_by_id("main") {
-                                let _ = tray.set_title(Some(&get_tray_title(&state)));
-                                if let Ok(menu) = build_menu(app, &state) {
-                                    let _ = tray.set_menu(Some(menu));
+                            update_tray(app, &state);
+                        }
+                        "toggle_guest_mode" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            state.guest_mode = !state.guest_mode;
+
+                            let mut settings = load_saved_settings();
+                            settings.guest_mode = Some(state.guest_mode);
+                            save_settings(&settings);
+
+                            update_tray(app, &state);
+                        }
+                        "charts" => show_charts_window(app),
+                        "settings" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            settings::open_settings_window(app);
+                        }
+                        "copy_json" | "copy_markdown" => {
+                            let format = if event.id.as_ref() == "copy_json" {
+                                clipboard::CopyFormat::Json
+                            } else {
+                                clipboard::CopyFormat::Markdown
+                            };
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let state = state_arc.lock().unwrap().clone();
+                            std::thread::spawn(move || {
+                                if let Err(e) = clipboard::copy_usage(&state, format) {
+                                    append_log(&format!("Copy failed: {}", e));
+                                }
+                            });
+                        }
+                        "export_csv" | "export_json" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            let format = if event.id.as_ref() == "export_csv" {
+                                export::ExportFormat::Csv
+                            } else {
+                                export::ExportFormat::Json
+                            };
+                            // Dialog blocks, so keep it off the main thread
+                            std::thread::spawn(move || {
+                                if let Err(e) = export::export_with_dialog(format, None, false) {
+                                    append_log(&format!("Export failed: {}", e));
+                                }
+                            });
+                        }
+                        "export_report" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            std::thread::spawn(|| {
+                                if let Err(e) = report::export_with_dialog() {
+                                    append_log(&format!("Report export failed: {}", e));
+                                }
+                            });
+                        }
+                        "add_resets_to_calendar" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let state = state_arc.lock().unwrap();
+                            if state.guest_mode {
+                                return;
+                            }
+                            if let Err(e) = calendar::add_to_calendar(&state.usage) {
+                                append_log(&format!("Calendar export failed: {}", e));
+                            }
+                        }
+                        "backup" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            std::thread::spawn(|| {
+                                if let Err(e) = backup::backup_with_dialog() {
+                                    append_log(&format!("Backup failed: {}", e));
+                                    notify::send_notification(text(Msg::BackupFailedTitle), &e);
+                                }
+                            });
+                        }
+                        "clear_history" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            std::thread::spawn(|| {
+                                if let Err(e) = retention::clear_with_dialog() {
+                                    append_log(&format!("Clear history failed: {}", e));
+                                    notify::send_notification(text(Msg::ClearHistoryFailedTitle), &e);
+                                }
+                            });
+                        }
+                        "view_error_log" => {
+                            let error_log = get_error_log_path();
+                            if error_log.exists() {
+                                open_file(&error_log);
+                            }
+                        }
+                        "recent_errors" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            errorlog::open_window(app);
+                        }
+                        "open_data_folder" | "open_log" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            if state_arc.lock().unwrap().guest_mode {
+                                return;
+                            }
+                            if event.id.as_ref() == "open_log" {
+                                open_file(&get_log_path());
+                            } else {
+                                // Select the database so the cache and log sit right next to it
+                                let db = get_db_path();
+                                if db.exists() {
+                                    reveal_in_file_manager(&db);
+                                } else {
+                                    open_file(&datadir::data_dir());
                                 }
                             }
                         }
-                        "charts" => {
-                            // Open or focus the usage window
-                            if let Some(window) = app.get_webview_window("usage") {
-                                let _ = window.set_focus();
-                            } else {
-                                let _ = WebviewWindowBuilder::new(
-                                    app,
-                                    "usage",
-                                    tauri::WebviewUrl::App("index.html".into())
-                                )
-                                .title("Claude Usage")
-                                .inner_size(700.0, 700.0)
-                                .resizable(true)
-                                .build();
+                        "toggle_autostart" => {
+                            if let Err(e) = autostart::set_enabled(app, !autostart::is_enabled(app)) {
+                                append_log(&format!("Start at Login: {}", e));
                             }
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            update_tray(app, &state_arc.lock().unwrap());
                         }
-                        "view_error_log" => {
-                            let error_log = get_error_log_path();
-                            if error_log.exists() {
-                                // Open the error log file with default application
-                                #[cfg(target_os = "macos")]
-                                let _ = Command::new("open").arg(&error_log).spawn();
-                                #[cfg(target_os = "linux")]
-                                let _ = Command::new("xdg-open").arg(&error_log).spawn();
-                                #[cfg(target_os = "windows")]
-                                let _ = Command::new("notepad").arg(&error_log).spawn();
+                        "ack_escalation" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            state.escalation.acknowledged = true;
+                            update_tray(app, &state);
+                        }
+                        "snooze_1h" | "snooze_4h" | "snooze_today" | "snooze_end" => {
+                            let now = chrono::Local::now();
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            state.alerts_snoozed_until = match event.id.as_ref() {
+                                "snooze_1h" => Some(now + chrono::Duration::hours(1)),
+                                "snooze_4h" => Some(now + chrono::Duration::hours(4)),
+                                "snooze_today" => notify::end_of_day(now),
+                                _ => None,
+                            };
+                            update_tray(app, &state);
+                        }
+                        "pause_1h" | "pause_reset" | "pause_indefinite" | "pause_resume" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            state.refresh_pause = match event.id.as_ref() {
+                                "pause_1h" => Some(pause::RefreshPause::Until(
+                                    chrono::Local::now() + chrono::Duration::hours(1),
+                                )),
+                                "pause_reset" => state
+                                    .usage
+                                    .session
+                                    .resets
+                                    .as_deref()
+                                    .and_then(parse_reset_time)
+                                    .map(pause::RefreshPause::Until),
+                                "pause_indefinite" => Some(pause::RefreshPause::UntilResumed),
+                                _ => None,
+                            };
+                            append_log(&format!("Auto-refresh: {}", event.id.as_ref()));
+                            update_tray(app, &state);
+                            // Catch up right away rather than at the next interval
+                            if event.id.as_ref() == "pause_resume" {
+                                drop(state);
+                                spawn_manual_refresh(app.clone(), state_for_menu.clone());
                             }
                         }
-                        "refresh" => {
-                            // Run fetch in background to avoid blocking UI
+                        "away_7" | "away_14" => {
+                            let days = if event.id.as_ref() == "away_7" { 7 } else { 14 };
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            away::start_away(&mut state, chrono::Local::now() + chrono::Duration::days(days));
+                            update_tray(app, &state);
+                        }
+                        "away_custom" => {
                             let state_clone = state_for_menu.clone();
                             let app_handle = app.clone();
+                            // Dialog blocks, so keep it off the main thread
                             std::thread::spawn(move || {
-                                let data = fetch_usage();
+                                let default = (chrono::Local::now() + chrono::Duration::days(7))
+                                    .format("%Y-%m-%d")
+                                    .to_string();
+                                let Some(input) = prompt_text(text(Msg::AwayPrompt), &default) else {
+                                    return;
+                                };
+                                let Some(until) = away::parse_return_date(&input) else {
+                                    let body = tr(Msg::AwayNotSetBody, &[&input]);
+                                    notify::send_notification(text(Msg::AwayNotSetTitle), &body);
+                                    return;
+                                };
                                 let mut state = state_clone.lock().unwrap();
-                                if let Some(ref err) = data.error {
-                                    state.last_error = Some(err.clone());
-                                } else {
-                                    save_cached_usage(&data);
-                                    save_to_db(&data);
-                                    state.usage = data;
-                                    state.last_error = None;
-                                    state.consecutive_errors = 0;
-                                }
-                                // Update menu
-                                if let Some(tray) = app_handle.tray_by_id("main") {
-                                    let _ = tray.set_title(Some(&get_tray_title(&state)));
-                                    if let Ok(menu) = build_menu(&app_handle, &state) {
-                                        let _ = tray.set_menu(Some(menu));
-                                    }
+                                away::start_away(&mut state, until);
+                                update_tray(&app_handle, &state);
+                            });
+                        }
+                        "away_end" => {
+                            let state_clone = state_for_menu.clone();
+                            let app_handle = app.clone();
+                            std::thread::spawn(move || {
+                                away::end_away(&state_clone);
+                                update_tray(&app_handle, &state_clone.lock().unwrap());
+                                spawn_manual_refresh(app_handle, state_clone);
+                            });
+                        }
+                        "refresh" => {
+                            spawn_manual_refresh(app.clone(), state_for_menu.clone());
+                        }
+                        "about" => {
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let state = state_arc.lock().unwrap();
+                            let include_paths = !state.guest_mode;
+                            let last_fetch = state.usage.timestamp.clone();
+                            drop(state);
+                            // Collecting info runs `claude --version`, and the dialog blocks
+                            std::thread::spawn(move || {
+                                let info = info::collect_app_info();
+                                let message = info::format_app_info(&info, include_paths, last_fetch.as_deref());
+                                show_message(text(Msg::About), &message);
+                            });
+                        }
+                        "check_updates" => {
+                            std::thread::spawn(|| {
+                                if let Err(e) = update::check_with_dialog() {
+                                    append_log(&format!("Update check failed: {}", e));
+                                    show_message(text(Msg::UpdatesTitle), &tr(Msg::UpdateCheckFailed, &[&e]));
                                 }
                             });
                         }
+                        id if id.starts_with(profiles::MENU_PREFIX) => {
+                            let Some(name) = profiles::from_menu_id(id) else {
+                                return;
+                            };
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            if state.guest_mode {
+                                return;
+                            }
+                            profiles::switch(&mut state, name);
+                            update_tray(app, &state);
+                            drop(state);
+                            spawn_manual_refresh(app.clone(), state_for_menu.clone());
+                        }
+                        id if id.starts_with("tray_metric_") => {
+                            let Some(metric) = TrayMetric::ALL.into_iter().find(|m| m.menu_id() == id) else {
+                                return;
+                            };
+                            let state_arc: tauri::State<'_, Arc<Mutex<AppState>>> = app.state();
+                            let mut state = state_arc.lock().unwrap();
+                            state.tray_metric = metric;
+
+                            let mut settings = load_saved_settings();
+                            settings.tray_metric = Some(metric.as_str().to_string());
+                            save_settings(&settings);
+
+                            update_tray(app, &state);
+                        }
                         _ => {}
                     }
                 })
                 .build(app)?;
 
-            // Spawn background data fetch task (every 10 min)
-            let handle_for_refresh = app.handle().clone();
-            let state_for_refresh = app_state.clone();
-
-            std::thread::spawn(move || {
-                let mut first_run = true;
-
-                loop {
-                    if !first_run {
-                        let state = state_for_refresh.lock().unwrap();
-                        let sleep_secs = if state.consecutive_errors > 0 {
-                            600 * std::cmp::min(state.consecutive_errors, 3)
-                        } else {
-                            600 // 10 minutes
-                        };
-                        drop(state);
-                        std::thread::sleep(Duration::from_secs(sleep_secs.into()));
-                    }
-                    first_run = false;
-
-                    let data = fetch_usage();
-                    let mut state = state_for_refresh.lock().unwrap();
-
-                    if let Some(ref err) = data.error {
-                        state.last_error = Some(err.clone());
-                        state.consecutive_errors += 1;
-                        state.has_network = !err.contains("No network");
-                    } else {
-                        save_cached_usage(&data);
-                        save_to_db(&data);
-                        state.usage = data;
-                        state.last_error = None;
-                        state.consecutive_errors = 0;
-                        state.has_network = true;
-                    }
-
-                    let title = get_tray_title(&state);
-                    let state_clone = state.clone();
-                    drop(state);
-
-                    if let Some(tray) = handle_for_refresh.tray_by_id("main") {
-                        let _ = tray.set_title(Some(&title));
-                        if let Ok(menu) = build_menu(&handle_for_refresh, &state_clone) {
-                            let _ = tray.set_menu(Some(menu));
-                        }
-                    }
-                }
-            });
+            // Spawn background data fetch task, supervised by the watchdog
+            let heartbeat = Arc::new(RefreshHeartbeat::new());
+            spawn_refresh_loop(app.handle().clone(), app_state.clone(), heartbeat.clone());
+            spawn_watchdog(app.handle().clone(), app_state.clone(), heartbeat);
+            spawn_countdown_ticker(app.handle().clone(), app_state.clone());
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            // Quit (menu or otherwise): write any readings still queued
+            if let tauri::RunEvent::Exit = event {
+                store::flush();
+                mqtt::publish_offline(&load_settings());
+            }
+        });
 }
 
 // Test-specific database functions that use a custom path
 #[cfg(test)]
 fn init_test_db(path: &std::path::Path) -> Result<Connection, rusqlite::Error> {
     let conn = Connection::open(path)?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS usage_history (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp TEXT NOT NULL,
-            session_percent INTEGER,
-            session_resets TEXT,
-            weekly_percent INTEGER,
-            weekly_resets TEXT,
-            sonnet_percent INTEGER,
-            sonnet_resets TEXT
-        )",
-        [],
-    )?;
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_timestamp ON usage_history(timestamp)",
-        [],
-    )?;
+    init_schema(&conn)?;
     Ok(conn)
 }
 
@@ -902,6 +3216,7 @@ mod tests {
                 percent: Some(10),
                 resets: None,
             },
+            weekly_opus: UsageItem::default(),
             error: None,
         };
 
@@ -975,6 +3290,7 @@ mod tests {
                     session_percent: row.get(1)?,
                     weekly_percent: row.get(2)?,
                     sonnet_percent: row.get(3)?,
+                    opus_percent: None,
                 })
             })
             .unwrap()
@@ -1003,6 +3319,7 @@ mod tests {
                 resets: Some("Jan 29 at 5pm".to_string()),
             },
             weekly_sonnet: UsageItem::default(),
+            weekly_opus: UsageItem::default(),
             error: None,
         };
 
@@ -1096,18 +3413,106 @@ mod tests {
 
     #[test]
     fn test_pace_indicator_under_pace() {
+        use chrono::TimeZone;
         // 30% usage with 50% time elapsed = under pace = green
-        let indicator = get_status_indicator_paced(30, Some("3pm"), 4);
+        let now = chrono::Local.with_ymd_and_hms(2026, 1, 28, 13, 0, 0).unwrap();
+        let reset = now + chrono::Duration::hours(2);
+        let indicator = pace_indicator_at(30, Some(reset), MenuMetric::Session, now);
         assert_eq!(indicator, "🟢", "Under pace should be green");
     }
 
     #[test]
     fn test_pace_indicator_over_pace() {
         // 90% usage = always red regardless of pace
-        let indicator = get_status_indicator_paced(90, Some("3pm"), 4);
+        let indicator = get_status_indicator_paced(90, Some("3pm"), MenuMetric::Session);
         assert_eq!(indicator, "🔴", "90%+ should always be red");
     }
 
+    #[test]
+    fn test_delta_suffix() {
+        assert_eq!(delta_suffix(Some(37), Some(42)), " (↑5)");
+        assert_eq!(delta_suffix(Some(95), Some(2)), " (↓93)");
+        assert_eq!(delta_suffix(Some(42), Some(42)), "");
+        assert_eq!(delta_suffix(None, Some(42)), "");
+    }
+
+    #[test]
+    fn test_countdown_ticks_on_the_minute() {
+        use chrono::TimeZone;
+        let at = |secs: u32| chrono::Local.with_ymd_and_hms(2026, 1, 28, 10, 15, secs).unwrap();
+        assert_eq!(secs_until_next_tick(at(0)), 60);
+        assert_eq!(secs_until_next_tick(at(45)), 15);
+    }
+
+    #[test]
+    fn test_tray_title_follows_metric_setting() {
+        let mut state = AppState {
+            show_percentages: true,
+            ..Default::default()
+        };
+        state.usage.session.percent = Some(12);
+        state.usage.weekly_all.percent = Some(40);
+        assert_eq!(get_tray_title(&state), "12% 40%");
+        state.tray_metric = TrayMetric::from_setting(Some("weekly"));
+        assert_eq!(get_tray_title(&state), "40%");
+        assert_eq!(TrayMetric::from_setting(Some("nonsense")), TrayMetric::Both);
+    }
+
+    #[test]
+    fn test_menu_metrics_setting_hides_and_orders() {
+        let names = ["sonnet", "session", "opus-typo", "session"].map(String::from);
+        let metrics = MenuMetric::from_setting(Some(&names)).unwrap();
+        assert_eq!(metrics, vec![MenuMetric::Sonnet, MenuMetric::Session]);
+        assert_eq!(MenuMetric::from_setting(None), None);
+
+        let mut state = AppState {
+            show_percentages: true,
+            menu_metrics: Some(metrics),
+            ..Default::default()
+        };
+        state.usage.session.percent = Some(12);
+        state.usage.weekly_all.percent = Some(40);
+        assert_eq!(get_tray_title(&state), "12%");
+        assert_eq!(metric_line(&state, MenuMetric::Sonnet), None);
+        state.usage.weekly_sonnet.percent = Some(7);
+        assert_eq!(metric_line(&state, MenuMetric::Sonnet).as_deref(), Some("🟢 Weekly (Sonnet): 7%"));
+    }
+
+    #[test]
+    fn test_stale_reading_marks_title() {
+        let now = chrono::Local::now();
+        let mut state = AppState {
+            show_percentages: true,
+            stale_title_marker: true,
+            ..Default::default()
+        };
+        state.usage.session.percent = Some(12);
+        state.usage.weekly_all.percent = Some(40);
+        state.usage.timestamp = Some((now - chrono::Duration::minutes(10)).format("%Y-%m-%dT%H:%M:%S").to_string());
+        assert_eq!(stale_age(&state, now), None);
+        assert_eq!(get_tray_title(&state), "12% 40%");
+
+        state.usage.timestamp = Some((now - chrono::Duration::minutes(80)).format("%Y-%m-%dT%H:%M:%S").to_string());
+        assert_eq!(stale_age(&state, now).map(stale_text).as_deref(), Some("⏳ stale (1h 20m old)"));
+        assert_eq!(get_tray_title(&state), "12% 40%*");
+        state.stale_after_mins = Some(120);
+        assert_eq!(stale_age(&state, now), None);
+    }
+
+    #[test]
+    fn test_worst_pace_indicator_picks_most_severe() {
+        let mut usage = UsageData::default();
+        assert_eq!(worst_pace_indicator(&usage, TrayMetric::Both, &MenuMetric::ALL), "🟢");
+        usage.session.percent = Some(0);
+        usage.weekly_sonnet.percent = Some(95);
+        assert_eq!(worst_pace_indicator(&usage, TrayMetric::Both, &MenuMetric::ALL), "🔴");
+        // Sonnet isn't followed when only the session is chosen
+        assert_eq!(worst_pace_indicator(&usage, TrayMetric::Session, &MenuMetric::ALL), "🟢");
+        // ...or when it's hidden
+        let visible = [MenuMetric::Weekly, MenuMetric::Session];
+        assert_eq!(worst_pace_indicator(&usage, TrayMetric::Both, &visible), "🟢");
+    }
+
     #[test]
     fn test_parse_time_am_pm() {
         // Test various time formats
@@ -1132,4 +3537,472 @@ mod tests {
         assert_eq!(format_duration(chrono::Duration::hours(48)), "2d 0h left");
         assert_eq!(format_duration(chrono::Duration::hours(49)), "2d 1h left");
     }
+
+    fn insert_test_reading(conn: &Connection, ts: &str, session: i32, weekly: i32) {
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent, weekly_percent, sonnet_percent) VALUES (?1, ?2, ?3, ?4)",
+            params![ts, session, weekly, 0],
+        ).unwrap();
+    }
+
+    fn aggregation_test_db(name: &str) -> (std::path::PathBuf, Connection) {
+        let db_path = std::env::temp_dir().join(format!("test_db_agg_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = init_test_db(&db_path).expect("Should create database");
+
+        // Two hourly buckets: 10:xx has three readings, 11:xx has one
+        insert_test_reading(&conn, "2026-01-28T10:00:00", 10, 40);
+        insert_test_reading(&conn, "2026-01-28T10:20:00", 30, 20);
+        insert_test_reading(&conn, "2026-01-28T10:40:00", 20, 30);
+        insert_test_reading(&conn, "2026-01-28T11:10:00", 50, 60);
+        (db_path, conn)
+    }
+
+    fn run_aggregation(name: &str, aggregation: Aggregation) -> Vec<UsageHistoryRow> {
+        let (db_path, conn) = aggregation_test_db(name);
+        let rows = query_usage_history(&conn, "2026-01-28T00:00:00", Some(3600), aggregation);
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+        rows
+    }
+
+    #[test]
+    fn test_aggregation_min() {
+        let rows = run_aggregation("min", Aggregation::Min);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].timestamp, "2026-01-28T10:00:00");
+        assert_eq!(rows[0].session_percent, Some(10));
+        assert_eq!(rows[0].weekly_percent, Some(20));
+        assert_eq!(rows[1].session_percent, Some(50));
+    }
+
+    #[test]
+    fn test_aggregation_avg() {
+        let rows = run_aggregation("avg", Aggregation::Avg);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].session_percent, Some(20));
+        assert_eq!(rows[0].weekly_percent, Some(30));
+        assert_eq!(rows[1].weekly_percent, Some(60));
+    }
+
+    #[test]
+    fn test_aggregation_max() {
+        let rows = run_aggregation("max", Aggregation::Max);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].session_percent, Some(30));
+        assert_eq!(rows[0].weekly_percent, Some(40));
+    }
+
+    #[test]
+    fn test_aggregation_latest() {
+        let rows = run_aggregation("latest", Aggregation::Latest);
+        assert_eq!(rows.len(), 2);
+        // Newest reading in the 10:xx bucket is 10:40
+        assert_eq!(rows[0].session_percent, Some(20));
+        assert_eq!(rows[0].weekly_percent, Some(30));
+        assert_eq!(rows[1].session_percent, Some(50));
+    }
+
+    #[test]
+    fn test_history_without_bucket_returns_raw_rows() {
+        let (db_path, conn) = aggregation_test_db("raw");
+        let rows = query_usage_history(&conn, "2026-01-28T00:00:00", None, Aggregation::Max);
+        assert_eq!(rows.len(), 4);
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_history_range_filters_and_pages() {
+        let row = |timestamp: &str, session: Option<i32>, opus: Option<i32>| UsageHistoryRow {
+            timestamp: timestamp.to_string(),
+            session_percent: session,
+            weekly_percent: Some(30),
+            sonnet_percent: None,
+            opus_percent: opus,
+        };
+        let rows = vec![
+            row("2026-01-28T10:00:00.123456", Some(10), None),
+            row("2026-01-28T11:00:00", Some(20), Some(5)),
+            row("2026-01-28T12:00:00", None, Some(6)),
+            row("2026-01-28T13:00:00", Some(40), None),
+        ];
+        let range = HistoryRange {
+            metrics: Some(vec!["opus".to_string()]),
+            ..Default::default()
+        };
+        let metrics = range.metric_filter().unwrap();
+        let opus = range.apply(rows.clone(), Some("2026-01-28T12:00:00"), metrics.as_deref());
+        assert_eq!(opus.len(), 2);
+        assert_eq!(opus[0].weekly_percent, None);
+        assert_eq!(opus[1].opus_percent, Some(6));
+
+        let page = HistoryRange {
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        };
+        let paged = page.apply(rows, None, None);
+        assert_eq!(paged.iter().map(|r| r.session_percent).collect::<Vec<_>>(), vec![Some(20), None]);
+
+        let unknown = HistoryRange {
+            metrics: Some(vec!["haiku".to_string()]),
+            ..Default::default()
+        };
+        assert!(unknown.metric_filter().is_err());
+        assert_eq!(parse_history_bound("2026-01-28", true).unwrap(), "2026-01-28T23:59:59");
+        assert_eq!(parse_history_bound("2026-01-28T09:30", false).unwrap(), "2026-01-28T09:30:00");
+        assert!(parse_history_bound("yesterday", false).is_err());
+    }
+
+    #[test]
+    fn test_refresh_stalled_after_three_intervals() {
+        let now = 1_000_000;
+        assert!(!is_refresh_stalled(now, now - 600, 600));
+        assert!(!is_refresh_stalled(now, now - 1800, 600));
+        assert!(is_refresh_stalled(now, now - 1801, 600));
+    }
+
+    #[test]
+    fn test_refresh_stall_limit_follows_backoff() {
+        // With error backoff the loop sleeps 30 min, so 35 min of silence is normal
+        let now = 1_000_000;
+        assert!(!is_refresh_stalled(now, now - 2100, 1800));
+        assert!(is_refresh_stalled(now, now - 5401, 1800));
+    }
+
+    #[test]
+    fn test_guest_view_redacts_error_keeps_usage() {
+        let usage = UsageData {
+            session: UsageItem {
+                percent: Some(42),
+                resets: Some("3pm".to_string()),
+            },
+            error: Some("Parse error (see /Users/me/.claude/cc-usage-last-error.txt)".to_string()),
+            ..Default::default()
+        };
+        let view = guest_view(&usage);
+        assert_eq!(view.session.percent, Some(42));
+        assert_eq!(view.session.resets.as_deref(), Some("3pm"));
+        assert!(!view.error.unwrap().contains("/Users"));
+    }
+
+    #[test]
+    fn test_privacy_mode_writes_nothing() {
+        let dir = std::env::temp_dir().join(format!("test_privacy_mode_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let sync_dir = dir.join("sync");
+        fs::create_dir_all(&sync_dir).unwrap();
+        let settings = Settings {
+            privacy_mode: Some(true),
+            sync_dir: Some(sync_dir.display().to_string()),
+            machine_name: Some("desktop".to_string()),
+            ..Default::default()
+        };
+        fs::write(dir.join(SETTINGS_FILE), serde_json::to_string(&settings).unwrap()).unwrap();
+        // Another machine's reading waiting in the sync folder
+        fs::write(
+            sync_dir.join("laptop.jsonl"),
+            "{\"timestamp\":\"2026-01-28T10:00:00\",\"session_percent\":10}\n",
+        )
+        .unwrap();
+        let db_path = dir.join(DB_FILE);
+        drop(init_test_db(&db_path).unwrap());
+
+        datadir::TEST_DATA_DIR.with(|d| *d.borrow_mut() = Some(dir.clone()));
+        let mut state = AppState::default();
+        let usage = UsageData {
+            timestamp: Some("2026-01-28T10:10:00".to_string()),
+            session: UsageItem {
+                percent: Some(12),
+                resets: None,
+            },
+            ..Default::default()
+        };
+        let effects = apply_fetch_result(&mut state, usage);
+        publish_fetch_result(effects, &state);
+        let failed = FetchLogEntry {
+            timestamp: "2026-01-28T10:20:00".to_string(),
+            duration_ms: 900,
+            strategy: FETCH_STRATEGY.to_string(),
+            trigger: "scheduled".to_string(),
+            success: false,
+            error: Some("Parse error".to_string()),
+            raw_output: None,
+        };
+        log_fetch(&settings, failed, Some("unparseable output".to_string()));
+        // Anything queued would be written here
+        store::close();
+        datadir::TEST_DATA_DIR.with(|d| *d.borrow_mut() = None);
+
+        // The tray still gets the reading
+        assert_eq!(state.usage.session.percent, Some(12));
+        let conn = Connection::open(&db_path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!(count("usage_history"), 0);
+        assert_eq!(count("fetch_log"), 0);
+        assert!(!dir.join(CACHE_FILE).exists());
+        assert!(!dir.join("cc-usage-errors").exists());
+        assert!(!sync_dir.join("desktop.jsonl").exists());
+
+        drop(conn);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_ensure_not_guest() {
+        let mut state = AppState::default();
+        assert!(ensure_not_guest(&state).is_ok());
+        state.guest_mode = true;
+        assert_eq!(ensure_not_guest(&state), Err(GUEST_MODE_ERROR.to_string()));
+    }
+
+    #[test]
+    fn test_became_usable_only_after_cap() {
+        assert!(became_usable(Some(100), Some(3)));
+        assert!(became_usable(Some(100), Some(99)));
+        assert!(!became_usable(Some(100), Some(100)));
+        assert!(!became_usable(Some(80), Some(3)));
+        assert!(!became_usable(None, Some(3)));
+    }
+
+    #[test]
+    fn test_post_reset_wait_stops_once_the_reset_is_past() {
+        use chrono::TimeZone;
+        let at = |h, m, sec| chrono::Local.with_ymd_and_hms(2026, 1, 29, h, m, sec).unwrap();
+        assert_eq!(post_reset_wait_secs("Jan 29 at 5pm", at(16, 0, 0)), Some(3660));
+        assert_eq!(post_reset_wait_secs("Jan 29 at 5pm", at(17, 0, 30)), Some(30));
+        // The post-reset fetch has run; don't keep fetching every minute
+        assert_eq!(post_reset_wait_secs("Jan 29 at 5pm", at(17, 1, 0)), None);
+        assert_eq!(post_reset_wait_secs("Jan 29 at 5pm", at(20, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_blocking_reset_picks_capped_metric() {
+        let mut usage = UsageData {
+            session: UsageItem {
+                percent: Some(100),
+                resets: Some("11:59pm".to_string()),
+            },
+            weekly_all: UsageItem {
+                percent: Some(40),
+                resets: Some("Dec 31 at 11:59pm".to_string()),
+            },
+            ..Default::default()
+        };
+        assert_eq!(blocking_reset(&usage), Some("11:59pm"));
+
+        usage.session.percent = Some(50);
+        assert_eq!(blocking_reset(&usage), None);
+    }
+
+    #[test]
+    fn test_format_hours_minutes() {
+        assert_eq!(format_hours_minutes(chrono::Duration::minutes(107)), "1h 47m");
+        assert_eq!(format_hours_minutes(chrono::Duration::minutes(47)), "47m");
+        assert_eq!(format_hours_minutes(chrono::Duration::seconds(20)), "1m");
+    }
+
+    #[test]
+    fn test_fetch_log_insert_and_query_newest_first() {
+        let db_path = std::env::temp_dir().join(format!("test_db_fetch_log_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = init_test_db(&db_path).expect("Should create database");
+
+        let now = chrono::Local::now();
+        for (i, error) in [None, Some("No network connection".to_string())].into_iter().enumerate() {
+            let entry = FetchLogEntry {
+                timestamp: (now + chrono::Duration::minutes(i as i64)).format("%Y-%m-%dT%H:%M:%S").to_string(),
+                duration_ms: 15000,
+                strategy: FETCH_STRATEGY.to_string(),
+                trigger: "scheduled".to_string(),
+                success: error.is_none(),
+                error,
+                raw_output: None,
+            };
+            insert_fetch_log(&conn, &entry).unwrap();
+        }
+
+        let log = query_fetch_log(&conn, 10, false);
+        assert_eq!(log.len(), 2);
+        assert!(!log[0].success);
+        assert_eq!(log[0].error.as_deref(), Some("No network connection"));
+        assert!(log[1].success);
+        assert_eq!(query_fetch_log(&conn, 1, false).len(), 1);
+        assert_eq!(query_fetch_log(&conn, 10, true).len(), 1);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_script_progress_markers() {
+        let script = get_usage_script();
+        for line in ["progress:launched", "progress:captured"] {
+            assert!(script.contains(&format!("echo \"{}\" >&2", line)));
+        }
+        assert_eq!(FetchStage::from_marker("progress:launched\n"), Some(FetchStage::Launched));
+        assert_eq!(FetchStage::from_marker("progress:captured"), Some(FetchStage::Captured));
+        assert_eq!(FetchStage::from_marker("no server running"), None);
+    }
+
+    #[test]
+    fn test_parse_reset_time_at_is_relative_to_reading() {
+        use chrono::TimeZone;
+        let read_at = chrono::Local.with_ymd_and_hms(2026, 1, 28, 16, 0, 0).unwrap();
+        // 3pm has passed at 4pm, so it means tomorrow
+        let reset = parse_reset_time_at("3pm", read_at).unwrap();
+        assert_eq!(format_reset_at(reset), "2026-01-29T15:00:00");
+        let reset = parse_reset_time_at("Feb 3 at 5:59pm", read_at).unwrap();
+        assert_eq!(format_reset_at(reset), "2026-02-03T17:59:00");
+    }
+
+    #[test]
+    fn test_period_id_is_stable_across_minute_drift() {
+        use chrono::TimeZone;
+        let a = chrono::Local.with_ymd_and_hms(2026, 2, 3, 17, 59, 0).unwrap();
+        let b = chrono::Local.with_ymd_and_hms(2026, 2, 3, 18, 0, 0).unwrap();
+        assert_eq!(period_id(a, WEEKLY_PERIOD_HOURS), "2026-01-27T18:00");
+        assert_eq!(period_id(a, WEEKLY_PERIOD_HOURS), period_id(b, WEEKLY_PERIOD_HOURS));
+    }
+
+    #[test]
+    fn test_migration_backfills_existing_rows() {
+        let db_path = std::env::temp_dir().join(format!("test_db_migrate_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+
+        // A database from before the migration framework existed
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE usage_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                session_percent INTEGER,
+                session_resets TEXT,
+                weekly_percent INTEGER,
+                weekly_resets TEXT,
+                sonnet_percent INTEGER,
+                sonnet_resets TEXT
+            );
+            INSERT INTO usage_history (timestamp, session_percent, session_resets, weekly_percent, weekly_resets)
+            VALUES ('2026-01-28T16:00:00.123456', 20, '3pm', 40, 'Feb 3 at 5:59pm');",
+        )
+        .unwrap();
+        init_schema(&conn).expect("Should migrate");
+
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        let row: (String, String, String) = conn
+            .query_row(
+                "SELECT session_reset_at, weekly_reset_at, weekly_period FROM usage_history WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(row.0, "2026-01-29T15:00:00");
+        assert_eq!(row.1, "2026-02-03T17:59:00");
+        assert_eq!(row.2, "2026-01-27T18:00");
+
+        // Running again is a no-op
+        init_schema(&conn).expect("Should be idempotent");
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_limit_windows_group_by_period() {
+        let db_path = std::env::temp_dir().join(format!("test_db_windows_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = init_test_db(&db_path).unwrap();
+
+        for (ts, pct, resets) in [
+            ("2026-01-20T10:00:00", 30, "Jan 27 at 6pm"),
+            ("2026-01-26T10:00:00", 90, "Jan 27 at 5:59pm"),
+            ("2026-01-28T10:00:00", 5, "Feb 3 at 6pm"),
+        ] {
+            let usage = UsageData {
+                timestamp: Some(ts.to_string()),
+                weekly_all: UsageItem {
+                    percent: Some(pct),
+                    resets: Some(resets.to_string()),
+                },
+                ..Default::default()
+            };
+            insert_usage(&conn, &usage).unwrap();
+        }
+
+        let windows = query_limit_windows(&conn, LimitMetric::Weekly, 10);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].period, "2026-01-27T18:00");
+        assert_eq!(windows[0].readings, 1);
+        assert_eq!(windows[1].period, "2026-01-20T18:00");
+        assert_eq!(windows[1].max_percent, Some(90));
+        assert_eq!(windows[1].readings, 2);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_granularity_auto_bounds_points() {
+        assert_eq!(Granularity::Auto.resolve(1), Granularity::Raw);
+        assert_eq!(Granularity::Auto.resolve(7), Granularity::Hourly);
+        assert_eq!(Granularity::Auto.resolve(30), Granularity::Hourly);
+        assert_eq!(Granularity::Auto.resolve(90), Granularity::Daily);
+        assert_eq!(Granularity::Auto.resolve(2000), Granularity::Weekly);
+        assert_eq!(Granularity::Raw.resolve(90), Granularity::Raw);
+        assert_eq!(Granularity::Daily.bucket_secs(1), Some(86400));
+        assert_eq!(Granularity::Raw.bucket_secs(30), None);
+    }
+
+    #[test]
+    fn test_daily_buckets_align_to_local_midnight() {
+        let db_path = std::env::temp_dir().join(format!("test_db_daily_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = init_test_db(&db_path).unwrap();
+        insert_test_reading(&conn, "2026-01-28T00:10:00", 10, 40);
+        insert_test_reading(&conn, "2026-01-28T23:50:00", 30, 45);
+        insert_test_reading(&conn, "2026-01-29T00:10:00", 5, 50);
+
+        let rows = query_usage_history(
+            &conn,
+            "2026-01-01T00:00:00",
+            Granularity::Daily.bucket_secs(90),
+            Aggregation::Max,
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].timestamp, "2026-01-28T00:10:00");
+        assert_eq!(rows[0].weekly_percent, Some(45));
+        assert_eq!(rows[1].weekly_percent, Some(50));
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_should_record_only_changes_or_hourly() {
+        let now = chrono::Local::now();
+        let reading = |session| UsageData {
+            session: UsageItem {
+                percent: Some(session),
+                resets: None,
+            },
+            ..Default::default()
+        };
+        assert!(should_record(&reading(10), &reading(10), None, now));
+        assert!(!should_record(&reading(10), &reading(10), Some(now - chrono::Duration::minutes(20)), now));
+        assert!(should_record(&reading(10), &reading(11), Some(now - chrono::Duration::minutes(10)), now));
+        assert!(should_record(&reading(10), &reading(10), Some(now - chrono::Duration::minutes(60)), now));
+    }
+
+    #[test]
+    fn test_refreshing_label_shows_start_time() {
+        use chrono::TimeZone;
+        let started = chrono::Local.with_ymd_and_hms(2026, 1, 28, 14, 2, 10).unwrap();
+        assert_eq!(refreshing_label(started), "Refreshing… (started 14:02:10)");
+    }
 }
@@ -0,0 +1,151 @@
+// "Limit hit" events: the first reading at 100% in each limit window, and how
+// early in the window it came
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{format_reset_at, init_db, period_id, LimitMetric};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct LimitEvent {
+    pub(crate) metric: String,
+    pub(crate) timestamp: String,
+    period: String,
+    reset_at: String,
+    minutes_into_window: i64,
+    pub(crate) minutes_before_reset: i64,
+}
+
+// At most one event per metric and window. A reading older than the stored
+// event (e.g. from an import) replaces it, so the event stays the first hit.
+pub(crate) fn record_limit_hit(
+    conn: &Connection,
+    metric: LimitMetric,
+    read_at: chrono::DateTime<chrono::Local>,
+    reset_at: Option<chrono::DateTime<chrono::Local>>,
+) -> Result<(), rusqlite::Error> {
+    let Some(reset_at) = reset_at else {
+        return Ok(());
+    };
+    let period_minutes = metric.period_hours() * 60;
+    let before_reset = reset_at
+        .signed_duration_since(read_at)
+        .num_minutes()
+        .clamp(0, period_minutes);
+    conn.execute(
+        "INSERT INTO limit_events
+            (metric, timestamp, period, reset_at, minutes_into_window, minutes_before_reset)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(metric, period) DO UPDATE SET
+            timestamp = excluded.timestamp,
+            reset_at = excluded.reset_at,
+            minutes_into_window = excluded.minutes_into_window,
+            minutes_before_reset = excluded.minutes_before_reset
+         WHERE excluded.timestamp < limit_events.timestamp",
+        params![
+            metric.as_str(),
+            read_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            period_id(reset_at, metric.period_hours()),
+            format_reset_at(reset_at),
+            period_minutes - before_reset,
+            before_reset,
+        ],
+    )?;
+    Ok(())
+}
+
+// Events since `cutoff`, newest first
+pub(crate) fn query_limit_events(conn: &Connection, cutoff: &str) -> Vec<LimitEvent> {
+    conn.prepare(
+        "SELECT metric, timestamp, period, reset_at, minutes_into_window, minutes_before_reset
+         FROM limit_events
+         WHERE timestamp >= ?1
+         ORDER BY timestamp DESC",
+    )
+    .and_then(|mut stmt| {
+        stmt.query_map(params![cutoff], |row| {
+            Ok(LimitEvent {
+                metric: row.get(0)?,
+                timestamp: row.get(1)?,
+                period: row.get(2)?,
+                reset_at: row.get(3)?,
+                minutes_into_window: row.get(4)?,
+                minutes_before_reset: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .unwrap_or_default()
+}
+
+#[tauri::command]
+pub(crate) fn get_limit_events(days: i32) -> Result<Vec<LimitEvent>, String> {
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let cutoff = chrono::Local::now() - chrono::Duration::days(days as i64);
+    Ok(query_limit_events(&conn, &cutoff.format("%Y-%m-%dT%H:%M:%S").to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{insert_usage, UsageData, UsageItem};
+    use std::fs;
+
+    fn reading(timestamp: &str, session: i32, resets: &str) -> UsageData {
+        UsageData {
+            timestamp: Some(timestamp.to_string()),
+            session: UsageItem {
+                percent: Some(session),
+                resets: Some(resets.to_string()),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_capped_reading_per_window() {
+        let db_path = std::env::temp_dir().join(format!("test_db_limit_events_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+
+        insert_usage(&conn, &reading("2026-01-28T13:00:00", 90, "3pm")).unwrap();
+        insert_usage(&conn, &reading("2026-01-28T14:00:00", 100, "3pm")).unwrap();
+        insert_usage(&conn, &reading("2026-01-28T14:30:00", 100, "3pm")).unwrap();
+        // Imported out of order: an earlier hit in the same window wins
+        insert_usage(&conn, &reading("2026-01-28T13:45:00", 100, "3pm")).unwrap();
+
+        let events = query_limit_events(&conn, "2026-01-01T00:00:00");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metric, "session");
+        assert_eq!(events[0].timestamp, "2026-01-28T13:45:00");
+        assert_eq!(events[0].minutes_before_reset, 75);
+        assert_eq!(events[0].minutes_into_window, 165);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_migration_backfills_limit_events() {
+        let db_path = std::env::temp_dir().join(format!("test_db_limit_backfill_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        // Rows as written before the limit_events migration existed
+        conn.execute_batch(
+            "DROP TABLE limit_events;
+             INSERT INTO usage_history (timestamp, session_percent, session_reset_at, session_period)
+             VALUES ('2026-01-28T14:00:00', 100, '2026-01-28T15:00:00', '2026-01-28T11:00'),
+                    ('2026-01-28T14:10:00', 100, '2026-01-28T15:00:00', '2026-01-28T11:00');",
+        )
+        .unwrap();
+        crate::migrate_add_limit_events(&conn).unwrap();
+
+        let events = query_limit_events(&conn, "2026-01-01T00:00:00");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp, "2026-01-28T14:00:00");
+        assert_eq!(events[0].minutes_before_reset, 60);
+        assert_eq!(events[0].minutes_into_window, 180);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
@@ -0,0 +1,566 @@
+// Translations for the tray menu, its formatters, and notifications. Each
+// string has a `Msg` key; English is the source table and other languages fall
+// back to it for anything they don't translate yet. Templates use `{}`
+// placeholders, filled in order by `tr`. The charts and settings windows are
+// still English only. Clock times follow the `time_format` setting (12h/24h).
+use std::fmt::Display;
+use std::process::Command;
+use std::sync::RwLock;
+
+// "de_DE.UTF-8" from the environment, or the macOS region ("en_US") for apps
+// started from Finder, which get no LANG
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+        .or_else(|| {
+            let output = Command::new("defaults").args(["read", "-g", "AppleLocale"]).output().ok()?;
+            let locale = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (output.status.success() && !locale.is_empty()).then_some(locale)
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub(crate) const ALL: [Language; 2] = [Language::English, Language::German];
+
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    // "de", "de-AT", "de_DE.UTF-8" -> German
+    fn from_code(code: &str) -> Option<Self> {
+        let prefix = code.get(..2)?.to_ascii_lowercase();
+        Language::ALL.into_iter().find(|l| l.code() == prefix)
+    }
+
+    // `language` setting, else the system locale, else English
+    pub(crate) fn from_setting(value: Option<&str>) -> Self {
+        value
+            .and_then(Language::from_code)
+            .or_else(|| system_locale().and_then(|locale| Language::from_code(&locale)))
+            .unwrap_or_default()
+    }
+}
+
+static CURRENT: RwLock<Language> = RwLock::new(Language::English);
+
+pub(crate) fn set_language(language: Language) {
+    *CURRENT.write().unwrap() = language;
+}
+
+pub(crate) fn language() -> Language {
+    *CURRENT.read().unwrap()
+}
+
+// Regions whose locale writes times as "3:05 PM"; everywhere else gets 24h
+const TWELVE_HOUR_REGIONS: [&str; 8] = ["US", "CA", "AU", "NZ", "PH", "IN", "PK", "EG"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum TimeFormat {
+    TwelveHour,
+    #[default]
+    TwentyFourHour,
+}
+
+impl TimeFormat {
+    pub(crate) const ALL: [TimeFormat; 2] = [TimeFormat::TwelveHour, TimeFormat::TwentyFourHour];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TimeFormat::TwelveHour => "12h",
+            TimeFormat::TwentyFourHour => "24h",
+        }
+    }
+
+    // "en_US.UTF-8" / "en-US" -> 12h
+    fn from_locale(locale: &str) -> Self {
+        let region = locale.split('.').next().unwrap_or(locale).split(['_', '-']).nth(1);
+        match region {
+            Some(r) if TWELVE_HOUR_REGIONS.contains(&r.to_ascii_uppercase().as_str()) => TimeFormat::TwelveHour,
+            _ => TimeFormat::TwentyFourHour,
+        }
+    }
+
+    // `time_format` setting ("12h" / "24h"), else what the system locale uses
+    pub(crate) fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("12h") => TimeFormat::TwelveHour,
+            Some("24h") => TimeFormat::TwentyFourHour,
+            _ => system_locale().map(|l| TimeFormat::from_locale(&l)).unwrap_or_default(),
+        }
+    }
+
+    // strftime pattern for a clock time: "15:05:09" / "3:05:09 PM"
+    pub(crate) fn clock(self, seconds: bool) -> &'static str {
+        match (self, seconds) {
+            (TimeFormat::TwelveHour, true) => "%-I:%M:%S %p",
+            (TimeFormat::TwelveHour, false) => "%-I:%M %p",
+            (TimeFormat::TwentyFourHour, true) => "%H:%M:%S",
+            (TimeFormat::TwentyFourHour, false) => "%H:%M",
+        }
+    }
+}
+
+static TIME_FORMAT: RwLock<TimeFormat> = RwLock::new(TimeFormat::TwentyFourHour);
+
+pub(crate) fn set_time_format(format: TimeFormat) {
+    *TIME_FORMAT.write().unwrap() = format;
+}
+
+pub(crate) fn time_format() -> TimeFormat {
+    *TIME_FORMAT.read().unwrap()
+}
+
+// strftime pattern for a clock time in the current format
+pub(crate) fn clock(seconds: bool) -> &'static str {
+    time_format().clock(seconds)
+}
+
+// "Jan 29 " plus the clock, for times that aren't today
+pub(crate) fn date_clock(seconds: bool) -> String {
+    format!("%b %d {}", clock(seconds))
+}
+
+// For the charts and popover windows, which format times themselves
+#[tauri::command]
+pub(crate) fn get_time_format() -> &'static str {
+    time_format().as_str()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Msg {
+    // Formatters
+    DaysHoursLeft,
+    HoursLeft,
+    MinutesLeft,
+    Soon,
+    Resets,
+    ResetsToday,
+    Updated,
+    Stale,
+    UsableAgainIn,
+    LimitShouldHaveReset,
+    LimitReached,
+    RefreshingStarted,
+    LastRefreshFailed,
+    AwayUntil,
+    // Metric names
+    Session,
+    WeeklyAll,
+    WeeklySonnet,
+    WeeklyOpus,
+    SessionAndWeekly,
+    SessionOnly,
+    WeeklyOnly,
+    // Menu items
+    ViewErrorLog,
+    History,
+    Machines,
+    ThisMachine,
+    MachinesCombined,
+    ShowCharts,
+    CopyJson,
+    CopyMarkdown,
+    ExportHistory,
+    AsCsv,
+    AsJson,
+    ExportWeeklyReport,
+    AddResetsToCalendar,
+    BackUpData,
+    ClearHistory,
+    OpenDataFolder,
+    OpenLogFile,
+    RecentErrors,
+    RefreshNow,
+    RefreshAction,
+    ShowChartsAction,
+    HidePercentages,
+    ShowPercentages,
+    MenuBarShows,
+    ExitGuestMode,
+    GuestMode,
+    UnsnoozeAlerts,
+    SnoozeAlerts,
+    ForOneHour,
+    ForFourHours,
+    ForToday,
+    ResumeAutoRefresh,
+    PauseAutoRefresh,
+    UntilSessionReset,
+    UntilResumed,
+    ImBack,
+    AwayMode,
+    AwayOneWeek,
+    AwayTwoWeeks,
+    AwayCustom,
+    Settings,
+    About,
+    CheckForUpdates,
+    Quit,
+    // Notifications
+    UsableAgainTitle,
+    UsableAgainBody,
+    ThresholdTitle,
+    PercentUsed,
+    ResetTitle,
+    ResetBody,
+    PaceTitle,
+    PaceBody,
+    EarlyWarningTitle,
+    EarlyWarningBody,
+    EscalationTitle,
+    EscalationBody,
+    AcknowledgeAlert,
+    StartAtLogin,
+    Profile,
+    DefaultProfile,
+    QuietSummaryTitle,
+    AndMore,
+    DailySummaryTitle,
+    DailyNoteSummary,
+    DailyNotePeakSession,
+    DailyNoteWeeklyAtEnd,
+    DailyNoteCost,
+    LimitHitTitle,
+    LimitHitBody,
+    BackupSavedTitle,
+    BackupFailedTitle,
+    UsageCopiedTitle,
+    UsageCopiedBody,
+    NothingToClearTitle,
+    NothingToClearBody,
+    HistoryClearedTitle,
+    HistoryClearedBody,
+    ClearHistoryFailedTitle,
+    AwayNotSetTitle,
+    AwayNotSetBody,
+    // Dialogs
+    UpdatesTitle,
+    UpdateCheckFailed,
+    UpToDate,
+    UpdateAvailable,
+    OpenDownloadPage,
+    Cancel,
+    BackupPrompt,
+    ExportHistoryPrompt,
+    ExportReportPrompt,
+    AwayPrompt,
+    ClearHistoryPrompt,
+    ClearHistoryQuestion,
+    ClearBefore,
+    ClearAll,
+    ClearHistoryButton,
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::DaysHoursLeft => "{}d {}h left",
+        Msg::HoursLeft => "{}h left",
+        Msg::MinutesLeft => "{}m left",
+        Msg::Soon => "soon",
+        Msg::Resets => "Resets {}",
+        Msg::ResetsToday => "Resets today {}",
+        Msg::Updated => "Updated: {}",
+        Msg::Stale => "⏳ stale ({} old)",
+        Msg::UsableAgainIn => "⛔ Usable again in {}",
+        Msg::LimitShouldHaveReset => "⛔ Limit should have reset, confirming...",
+        Msg::LimitReached => "⛔ Limit reached, resets {}",
+        Msg::RefreshingStarted => "Refreshing… (started {})",
+        Msg::LastRefreshFailed => "⚠️ Last refresh failed",
+        Msg::AwayUntil => "🏖 Away until {} (refresh paused)",
+        Msg::Session => "Session",
+        Msg::WeeklyAll => "Weekly (all)",
+        Msg::WeeklySonnet => "Weekly (Sonnet)",
+        Msg::WeeklyOpus => "Weekly (Opus)",
+        Msg::SessionAndWeekly => "Session and Weekly",
+        Msg::SessionOnly => "Session Only",
+        Msg::WeeklyOnly => "Weekly Only",
+        Msg::ViewErrorLog => "View Error Log...",
+        Msg::History => "History",
+        Msg::Machines => "Machines",
+        Msg::ThisMachine => "{} (this machine)",
+        Msg::MachinesCombined => "Combined: {}",
+        Msg::ShowCharts => "Show Charts...",
+        Msg::CopyJson => "Copy Usage (JSON)",
+        Msg::CopyMarkdown => "Copy Usage (Markdown)",
+        Msg::ExportHistory => "Export History",
+        Msg::AsCsv => "As CSV...",
+        Msg::AsJson => "As JSON...",
+        Msg::ExportWeeklyReport => "Export Weekly Report...",
+        Msg::AddResetsToCalendar => "Add Resets to Calendar...",
+        Msg::BackUpData => "Back Up Data...",
+        Msg::ClearHistory => "Clear History...",
+        Msg::OpenDataFolder => "Open Data Folder",
+        Msg::OpenLogFile => "Open Log File",
+        Msg::RecentErrors => "Recent Errors...",
+        Msg::RefreshNow => "Refresh Now",
+        Msg::RefreshAction => "Refresh",
+        Msg::ShowChartsAction => "Show Charts",
+        Msg::HidePercentages => "Hide Percentages in Menu Bar",
+        Msg::ShowPercentages => "Show Percentages in Menu Bar",
+        Msg::MenuBarShows => "Menu Bar Shows",
+        Msg::ExitGuestMode => "Exit Guest Mode",
+        Msg::GuestMode => "Guest Mode (Hide Details)",
+        Msg::UnsnoozeAlerts => "Unsnooze Alerts",
+        Msg::SnoozeAlerts => "Snooze Alerts",
+        Msg::ForOneHour => "For 1 Hour",
+        Msg::ForFourHours => "For 4 Hours",
+        Msg::ForToday => "For Today",
+        Msg::ResumeAutoRefresh => "Resume Auto-Refresh",
+        Msg::PauseAutoRefresh => "Pause Auto-Refresh",
+        Msg::UntilSessionReset => "Until Session Reset",
+        Msg::UntilResumed => "Until Resumed",
+        Msg::ImBack => "I'm Back",
+        Msg::AwayMode => "Away Mode",
+        Msg::AwayOneWeek => "Away for 1 Week",
+        Msg::AwayTwoWeeks => "Away for 2 Weeks",
+        Msg::AwayCustom => "Away Until...",
+        Msg::Settings => "Settings...",
+        Msg::About => "About Claude Usage",
+        Msg::CheckForUpdates => "Check for Updates...",
+        Msg::Quit => "Quit",
+        Msg::UsableAgainTitle => "Claude is usable again",
+        Msg::UsableAgainBody => "{} usage is back to {}%",
+        Msg::ThresholdTitle => "{} at {}%",
+        Msg::PercentUsed => "{}% used",
+        Msg::ResetTitle => "{} budget reset",
+        Msg::ResetBody => "You're back to {}%",
+        Msg::PaceTitle => "{} {} is ahead of pace",
+        Msg::PaceBody => "{}% used with {}% of the window gone",
+        Msg::EarlyWarningTitle => "Weekly budget running out early",
+        Msg::EarlyWarningBody => "You'll run out ~{}h early at this pace",
+        Msg::EscalationTitle => "{} still at {}%",
+        Msg::EscalationBody => "Usage is still climbing. Acknowledge from the menu to stop these.",
+        Msg::AcknowledgeAlert => "Acknowledge Usage Alert",
+        Msg::StartAtLogin => "Start at Login",
+        Msg::Profile => "Profile",
+        Msg::DefaultProfile => "Default",
+        Msg::QuietSummaryTitle => "While you were away",
+        Msg::AndMore => "; … and {} more",
+        Msg::DailySummaryTitle => "Claude usage today",
+        Msg::DailyNoteSummary => "Claude usage: {}",
+        Msg::DailyNotePeakSession => "peak session {}%",
+        Msg::DailyNoteWeeklyAtEnd => "weekly {}% at end of day",
+        Msg::DailyNoteCost => "est. ${} at API prices",
+        Msg::LimitHitTitle => "{} limit reached",
+        Msg::LimitHitBody => "Usable again in {} (at {})",
+        Msg::BackupSavedTitle => "Backup saved",
+        Msg::BackupFailedTitle => "Backup failed",
+        Msg::UsageCopiedTitle => "Usage copied",
+        Msg::UsageCopiedBody => "Paste it anywhere",
+        Msg::NothingToClearTitle => "Nothing to clear",
+        Msg::NothingToClearBody => "No readings in that range",
+        Msg::HistoryClearedTitle => "History cleared",
+        Msg::HistoryClearedBody => "Deleted {} readings",
+        Msg::ClearHistoryFailedTitle => "Clear history failed",
+        Msg::AwayNotSetTitle => "Away mode not set",
+        Msg::AwayNotSetBody => "'{}' is not a future date",
+        Msg::UpdatesTitle => "Check for Updates",
+        Msg::UpdateCheckFailed => "Couldn't check for updates: {}",
+        Msg::UpToDate => "Claude Usage {} is the latest version.",
+        Msg::UpdateAvailable => "Claude Usage {} is available (you have {}).",
+        Msg::OpenDownloadPage => "Open Download Page",
+        Msg::Cancel => "Cancel",
+        Msg::BackupPrompt => "Back up Claude Usage data to:",
+        Msg::ExportHistoryPrompt => "Export usage history",
+        Msg::ExportReportPrompt => "Export weekly report",
+        Msg::AwayPrompt => "Away until (YYYY-MM-DD):",
+        Msg::ClearHistoryPrompt => "Clear readings before (YYYY-MM-DD), or leave empty to clear all history:",
+        Msg::ClearHistoryQuestion => "Delete {} readings ({})? This can't be undone - use Back Up Data first to keep a copy.",
+        Msg::ClearBefore => "before {}",
+        Msg::ClearAll => "all history",
+        Msg::ClearHistoryButton => "Clear History",
+    }
+}
+
+fn german(msg: Msg) -> Option<&'static str> {
+    Some(match msg {
+        Msg::DaysHoursLeft => "noch {}d {}h",
+        Msg::HoursLeft => "noch {}h",
+        Msg::MinutesLeft => "noch {}m",
+        Msg::Soon => "gleich",
+        Msg::Resets => "Zurückgesetzt {}",
+        Msg::ResetsToday => "Zurückgesetzt heute {}",
+        Msg::Updated => "Aktualisiert: {}",
+        Msg::Stale => "⏳ veraltet ({} alt)",
+        Msg::UsableAgainIn => "⛔ Wieder nutzbar in {}",
+        Msg::LimitShouldHaveReset => "⛔ Limit sollte zurückgesetzt sein, wird geprüft...",
+        Msg::LimitReached => "⛔ Limit erreicht, zurückgesetzt {}",
+        Msg::RefreshingStarted => "Aktualisiere… (seit {})",
+        Msg::LastRefreshFailed => "⚠️ Letzte Aktualisierung fehlgeschlagen",
+        Msg::AwayUntil => "🏖 Abwesend bis {} (Aktualisierung pausiert)",
+        Msg::Session => "Sitzung",
+        Msg::WeeklyAll => "Woche (alle)",
+        Msg::WeeklySonnet => "Woche (Sonnet)",
+        Msg::WeeklyOpus => "Woche (Opus)",
+        Msg::SessionAndWeekly => "Sitzung und Woche",
+        Msg::SessionOnly => "Nur Sitzung",
+        Msg::WeeklyOnly => "Nur Woche",
+        Msg::ViewErrorLog => "Fehlerprotokoll anzeigen...",
+        Msg::History => "Verlauf",
+        Msg::Machines => "Rechner",
+        Msg::ThisMachine => "{} (dieser Rechner)",
+        Msg::MachinesCombined => "Zusammen: {}",
+        Msg::ShowCharts => "Diagramme anzeigen...",
+        Msg::CopyJson => "Nutzung kopieren (JSON)",
+        Msg::CopyMarkdown => "Nutzung kopieren (Markdown)",
+        Msg::ExportHistory => "Verlauf exportieren",
+        Msg::AsCsv => "Als CSV...",
+        Msg::AsJson => "Als JSON...",
+        Msg::ExportWeeklyReport => "Wochenbericht exportieren...",
+        Msg::AddResetsToCalendar => "Resets zum Kalender hinzufügen...",
+        Msg::BackUpData => "Daten sichern...",
+        Msg::ClearHistory => "Verlauf löschen...",
+        Msg::OpenDataFolder => "Datenordner öffnen",
+        Msg::OpenLogFile => "Protokoll öffnen",
+        Msg::RecentErrors => "Letzte Fehler...",
+        Msg::RefreshNow => "Jetzt aktualisieren",
+        Msg::RefreshAction => "Aktualisieren",
+        Msg::ShowChartsAction => "Diagramme anzeigen",
+        Msg::HidePercentages => "Prozente in der Menüleiste ausblenden",
+        Msg::ShowPercentages => "Prozente in der Menüleiste anzeigen",
+        Msg::MenuBarShows => "Menüleiste zeigt",
+        Msg::ExitGuestMode => "Gastmodus beenden",
+        Msg::GuestMode => "Gastmodus (Details ausblenden)",
+        Msg::UnsnoozeAlerts => "Hinweise fortsetzen",
+        Msg::SnoozeAlerts => "Hinweise stummschalten",
+        Msg::ForOneHour => "Für 1 Stunde",
+        Msg::ForFourHours => "Für 4 Stunden",
+        Msg::ForToday => "Für heute",
+        Msg::ResumeAutoRefresh => "Automatische Aktualisierung fortsetzen",
+        Msg::PauseAutoRefresh => "Automatische Aktualisierung pausieren",
+        Msg::UntilSessionReset => "Bis zum Sitzungsreset",
+        Msg::UntilResumed => "Bis zur Fortsetzung",
+        Msg::ImBack => "Ich bin zurück",
+        Msg::AwayMode => "Abwesenheit",
+        Msg::AwayOneWeek => "1 Woche abwesend",
+        Msg::AwayTwoWeeks => "2 Wochen abwesend",
+        Msg::AwayCustom => "Abwesend bis...",
+        Msg::Settings => "Einstellungen...",
+        Msg::About => "Über Claude Usage",
+        Msg::CheckForUpdates => "Nach Updates suchen...",
+        Msg::Quit => "Beenden",
+        Msg::UsableAgainTitle => "Claude ist wieder nutzbar",
+        Msg::UsableAgainBody => "{}-Nutzung wieder bei {}%",
+        Msg::ThresholdTitle => "{} bei {}%",
+        Msg::PercentUsed => "{}% verbraucht",
+        Msg::ResetTitle => "{}-Budget zurückgesetzt",
+        Msg::ResetBody => "Du bist wieder bei {}%",
+        Msg::PaceTitle => "{} {} liegt über dem Plan",
+        Msg::PaceBody => "{}% verbraucht bei {}% abgelaufener Zeit",
+        Msg::EarlyWarningTitle => "Wochenbudget reicht nicht bis zum Reset",
+        Msg::EarlyWarningBody => "Bei diesem Tempo ist es ~{} Std. zu früh aufgebraucht",
+        Msg::EscalationTitle => "{} weiterhin bei {}%",
+        Msg::EscalationBody => "Die Nutzung steigt weiter. Im Menü bestätigen, um diese Hinweise zu beenden.",
+        Msg::AcknowledgeAlert => "Nutzungshinweis bestätigen",
+        Msg::StartAtLogin => "Bei Anmeldung starten",
+        Msg::Profile => "Profil",
+        Msg::DefaultProfile => "Standard",
+        Msg::QuietSummaryTitle => "Während du weg warst",
+        Msg::AndMore => "; … und {} weitere",
+        Msg::DailySummaryTitle => "Claude-Nutzung heute",
+        Msg::DailyNoteSummary => "Claude-Nutzung: {}",
+        Msg::DailyNotePeakSession => "Sitzung höchstens {}%",
+        Msg::DailyNoteWeeklyAtEnd => "Woche {}% am Tagesende",
+        Msg::DailyNoteCost => "ca. {} $ zu API-Preisen",
+        Msg::LimitHitTitle => "{}-Limit erreicht",
+        Msg::LimitHitBody => "Wieder nutzbar in {} (um {})",
+        Msg::BackupSavedTitle => "Sicherung gespeichert",
+        Msg::BackupFailedTitle => "Sicherung fehlgeschlagen",
+        Msg::UsageCopiedTitle => "Nutzung kopiert",
+        Msg::UsageCopiedBody => "Überall einfügbar",
+        Msg::NothingToClearTitle => "Nichts zu löschen",
+        Msg::NothingToClearBody => "Keine Messwerte in diesem Zeitraum",
+        Msg::HistoryClearedTitle => "Verlauf gelöscht",
+        Msg::HistoryClearedBody => "{} Messwerte gelöscht",
+        Msg::ClearHistoryFailedTitle => "Verlauf löschen fehlgeschlagen",
+        Msg::AwayNotSetTitle => "Abwesenheit nicht gesetzt",
+        Msg::AwayNotSetBody => "„{}“ ist kein Datum in der Zukunft",
+        Msg::UpdatesTitle => "Nach Updates suchen",
+        Msg::UpdateCheckFailed => "Suche nach Updates fehlgeschlagen: {}",
+        Msg::UpToDate => "Claude Usage {} ist die neueste Version.",
+        Msg::UpdateAvailable => "Claude Usage {} ist verfügbar (installiert: {}).",
+        Msg::OpenDownloadPage => "Downloadseite öffnen",
+        Msg::Cancel => "Abbrechen",
+        Msg::BackupPrompt => "Claude-Usage-Daten sichern nach:",
+        Msg::ExportHistoryPrompt => "Nutzungsverlauf exportieren",
+        Msg::ExportReportPrompt => "Wochenbericht exportieren",
+        Msg::AwayPrompt => "Abwesend bis (JJJJ-MM-TT):",
+        Msg::ClearHistoryPrompt => "Messwerte vor (JJJJ-MM-TT) löschen, oder leer lassen, um den ganzen Verlauf zu löschen:",
+        Msg::ClearHistoryQuestion => "{} Messwerte löschen ({})? Das lässt sich nicht rückgängig machen – mit „Daten sichern“ vorher eine Kopie behalten.",
+        Msg::ClearBefore => "vor {}",
+        Msg::ClearAll => "gesamter Verlauf",
+        Msg::ClearHistoryButton => "Verlauf löschen",
+    })
+}
+
+pub(crate) fn lookup(language: Language, msg: Msg) -> &'static str {
+    match language {
+        Language::English => english(msg),
+        Language::German => german(msg).unwrap_or_else(|| english(msg)),
+    }
+}
+
+// Replaces each `{}` in order; extra placeholders are left as they are
+pub(crate) fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        out.push_str(&rest[..pos]);
+        match args.next() {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str("{}"),
+        }
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// A string in the current language
+pub(crate) fn text(msg: Msg) -> &'static str {
+    lookup(language(), msg)
+}
+
+// A template in the current language, filled in
+pub(crate) fn tr(msg: Msg, args: &[&dyn Display]) -> String {
+    fill(text(msg), args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_codes_and_fallback() {
+        assert_eq!(Language::from_setting(Some("de_DE.UTF-8")), Language::German);
+        assert_eq!(Language::from_setting(Some("en-GB")), Language::English);
+        assert_eq!(lookup(Language::German, Msg::Quit), "Beenden");
+        assert_eq!(fill(lookup(Language::English, Msg::DaysHoursLeft), &[&2, &5]), "2d 5h left");
+        assert_eq!(fill(lookup(Language::German, Msg::Updated), &[&"10:40"]), "Aktualisiert: 10:40");
+        assert_eq!(fill("{} of {}", &[&1]), "1 of {}");
+    }
+
+    #[test]
+    fn test_time_format_from_locale() {
+        assert_eq!(TimeFormat::from_locale("en_US.UTF-8"), TimeFormat::TwelveHour);
+        assert_eq!(TimeFormat::from_locale("en-GB"), TimeFormat::TwentyFourHour);
+        assert_eq!(TimeFormat::from_locale("de_DE"), TimeFormat::TwentyFourHour);
+        assert_eq!(TimeFormat::from_setting(Some("24h")), TimeFormat::TwentyFourHour);
+        let at = chrono::NaiveDate::from_ymd_opt(2026, 1, 28).unwrap().and_hms_opt(15, 5, 9).unwrap();
+        assert_eq!(at.format(TimeFormat::TwelveHour.clock(true)).to_string(), "3:05:09 PM");
+        assert_eq!(at.format(TimeFormat::TwentyFourHour.clock(false)).to_string(), "15:05");
+    }
+}
@@ -0,0 +1,218 @@
+// With sync on, the latest reading from each machine and a merged maximum.
+// Every machine reads the same account's limits, but each only sees them
+// when it fetches, so the highest current reading across machines is the
+// best guess at where the account stands. A machine's reading counts toward
+// the maximum only while its window is still open: until its reset time, or
+// for one window length after the reading when the reset can't be parsed.
+// Shown as the menu's Machines submenu and returned by `get_machines`.
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::{
+    export, init_db, load_settings, locale, parse_reading_timestamp, resolve_reset, sync, AppState, MenuMetric,
+    UsageData,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct MachineReading {
+    machine: String,
+    this_machine: bool,
+    usage: UsageData,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct MergedLimit {
+    metric: &'static str,
+    percent: i32,
+    // Whose reading it is
+    machine: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Machines {
+    machines: Vec<MachineReading>,
+    merged: Vec<MergedLimit>,
+}
+
+// Each synced machine's newest reading, by name
+fn latest_remote(conn: &Connection) -> Result<Vec<(String, UsageData)>, rusqlite::Error> {
+    conn.prepare(
+        "SELECT machine, timestamp, session_percent, session_resets, weekly_percent, weekly_resets, sonnet_percent,
+                sonnet_resets, opus_percent, opus_resets, profile
+         FROM usage_history u
+         WHERE machine IS NOT NULL
+           AND timestamp = (SELECT MAX(timestamp) FROM usage_history WHERE machine = u.machine)
+         ORDER BY machine",
+    )?
+    .query_map([], |row| {
+        let record = export::HistoryRecord {
+            timestamp: row.get(1)?,
+            session_percent: row.get(2)?,
+            session_resets: row.get(3)?,
+            weekly_percent: row.get(4)?,
+            weekly_resets: row.get(5)?,
+            sonnet_percent: row.get(6)?,
+            sonnet_resets: row.get(7)?,
+            opus_percent: row.get(8)?,
+            opus_resets: row.get(9)?,
+            profile: row.get(10)?,
+        };
+        Ok((row.get(0)?, record.to_usage()))
+    })?
+    .collect()
+}
+
+fn in_current_window(usage: &UsageData, metric: MenuMetric, now: DateTime<Local>) -> bool {
+    let Some(read_at) = usage.timestamp.as_deref().and_then(parse_reading_timestamp) else {
+        return false;
+    };
+    match resolve_reset(metric.item(usage).resets.as_deref(), read_at) {
+        Some(reset_at) => reset_at > now,
+        None => now.signed_duration_since(read_at) < chrono::Duration::hours(metric.period_hours()),
+    }
+}
+
+// The highest current percentage per metric; a tie goes to the first machine
+pub(crate) fn merge(machines: &[MachineReading], now: DateTime<Local>) -> Vec<MergedLimit> {
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            machines
+                .iter()
+                .filter(|m| in_current_window(&m.usage, metric, now))
+                .filter_map(|m| Some((metric.item(&m.usage).percent?, m)))
+                .fold(None, |best: Option<(i32, &MachineReading)>, (percent, m)| match best {
+                    Some((top, _)) if top >= percent => best,
+                    _ => Some((percent, m)),
+                })
+                .map(|(percent, m)| MergedLimit {
+                    metric: metric.as_str(),
+                    percent,
+                    machine: m.machine.clone(),
+                })
+        })
+        .collect()
+}
+
+// None without a sync folder. This machine comes first, from the live reading.
+pub(crate) fn machines(usage: &UsageData) -> Option<Machines> {
+    load_settings().sync_dir.as_ref()?;
+    let mut machines = vec![MachineReading {
+        machine: sync::machine_name(),
+        this_machine: true,
+        usage: usage.clone(),
+    }];
+    let remote = init_db().and_then(|conn| latest_remote(&conn)).unwrap_or_default();
+    machines.extend(remote.into_iter().map(|(machine, usage)| MachineReading {
+        machine,
+        this_machine: false,
+        usage,
+    }));
+    let merged = merge(&machines, Local::now());
+    Some(Machines { machines, merged })
+}
+
+fn percents(usage: &UsageData, metrics: &[MenuMetric]) -> String {
+    let parts: Vec<String> = metrics
+        .iter()
+        .filter_map(|m| Some(format!("{} {}%", m.label(), m.item(usage).percent?)))
+        .collect();
+    if parts.is_empty() {
+        "--".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+// Submenu lines: "laptop: Session 42%, Weekly (all) 67% (10:40)" per machine,
+// then the merged maximum. None until another machine has synced.
+pub(crate) fn menu_lines(machines: &Machines, metrics: &[MenuMetric]) -> Option<Vec<String>> {
+    if machines.machines.len() < 2 {
+        return None;
+    }
+    let mut lines: Vec<String> = machines
+        .machines
+        .iter()
+        .map(|m| {
+            let name = if m.this_machine {
+                locale::tr(locale::Msg::ThisMachine, &[&m.machine])
+            } else {
+                m.machine.clone()
+            };
+            let at = m.usage.timestamp.as_deref().and_then(parse_reading_timestamp);
+            let at = at.map(|at| format!(" ({})", at.format(&locale::date_clock(true)))).unwrap_or_default();
+            format!("{}: {}{}", name, percents(&m.usage, metrics), at)
+        })
+        .collect();
+    let merged: Vec<String> = metrics
+        .iter()
+        .filter_map(|metric| {
+            let limit = machines.merged.iter().find(|l| l.metric == metric.as_str())?;
+            Some(format!("{} {}%", metric.label(), limit.percent))
+        })
+        .collect();
+    if !merged.is_empty() {
+        lines.push(locale::tr(locale::Msg::MachinesCombined, &[&merged.join(", ")]));
+    }
+    Some(lines)
+}
+
+// The latest reading per machine and the merged maximum; null with sync off
+#[tauri::command]
+pub(crate) fn get_machines(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<Option<Machines>, String> {
+    let state = state.lock().unwrap();
+    if state.guest_mode {
+        return Err("Not available in guest mode".to_string());
+    }
+    let usage = state.usage.clone();
+    drop(state);
+    Ok(machines(&usage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn reading(machine: &str, timestamp: &str, session: i32, session_resets: &str, weekly: i32) -> MachineReading {
+        let mut usage = UsageData {
+            timestamp: Some(timestamp.to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(session);
+        usage.session.resets = Some(session_resets.to_string());
+        usage.weekly_all.percent = Some(weekly);
+        MachineReading {
+            machine: machine.to_string(),
+            this_machine: machine == "desktop",
+            usage,
+        }
+    }
+
+    #[test]
+    fn test_merge_takes_highest_current_reading() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 30, 0).unwrap();
+        let machines = [
+            reading("desktop", "2026-01-28T14:25:00", 30, "4pm", 60),
+            reading("laptop", "2026-01-28T14:10:00", 45, "4pm", 58),
+            // Its session window closed at noon, so only its weekly counts
+            reading("old-mac", "2026-01-28T09:00:00", 90, "12pm", 62),
+        ];
+        let merged = merge(&machines, now);
+        assert_eq!(
+            merged[0],
+            MergedLimit {
+                metric: "session",
+                percent: 45,
+                machine: "laptop".to_string()
+            }
+        );
+        // No weekly reset time: counted for a week after the reading
+        assert_eq!(merged[1].percent, 62);
+        assert_eq!(merged[1].machine, "old-mac");
+        assert_eq!(merged.len(), 2);
+    }
+}
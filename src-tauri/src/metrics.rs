@@ -0,0 +1,109 @@
+// Prometheus text format for the local server's `/metrics`: each limit's
+// percentage and seconds to reset from the latest reading, plus fetch counts
+// and the last fetch's duration. The counts are kept in memory since the app
+// started, as Prometheus expects of counters.
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+
+use crate::{parse_reading_timestamp, resolve_reset, MenuMetric, UsageData};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct FetchCounts {
+    total: u64,
+    errors: u64,
+    last_duration_ms: Option<i64>,
+}
+
+static FETCHES: Mutex<FetchCounts> = Mutex::new(FetchCounts {
+    total: 0,
+    errors: 0,
+    last_duration_ms: None,
+});
+
+// After every fetch, including ones privacy mode keeps out of the fetch log
+pub(crate) fn record_fetch(duration_ms: i64, success: bool) {
+    let mut fetches = FETCHES.lock().unwrap();
+    fetches.total += 1;
+    fetches.errors += u64::from(!success);
+    fetches.last_duration_ms = Some(duration_ms);
+}
+
+pub(crate) fn fetch_counts() -> FetchCounts {
+    *FETCHES.lock().unwrap()
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// Limits without a reading (or a reset time) are left out rather than
+// reported as zero
+pub(crate) fn render(usage: &UsageData, consecutive_errors: u32, fetches: FetchCounts, now: DateTime<Local>) -> String {
+    let mut out = String::new();
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp);
+
+    family(&mut out, "cc_usage_percent", "gauge", "Usage of each limit in percent.");
+    for metric in MenuMetric::ALL {
+        if let Some(percent) = metric.item(usage).percent {
+            let _ = writeln!(out, "cc_usage_percent{{metric=\"{}\"}} {}", metric.as_str(), percent);
+        }
+    }
+    family(&mut out, "cc_usage_reset_seconds", "gauge", "Seconds until each limit resets.");
+    for metric in MenuMetric::ALL {
+        let reset_at = read_at.and_then(|at| resolve_reset(metric.item(usage).resets.as_deref(), at));
+        if let Some(reset_at) = reset_at {
+            let secs = reset_at.signed_duration_since(now).num_seconds().max(0);
+            let _ = writeln!(out, "cc_usage_reset_seconds{{metric=\"{}\"}} {}", metric.as_str(), secs);
+        }
+    }
+    if let Some(at) = read_at {
+        family(&mut out, "cc_usage_last_reading_timestamp_seconds", "gauge", "When the latest reading was taken.");
+        let _ = writeln!(out, "cc_usage_last_reading_timestamp_seconds {}", at.timestamp());
+    }
+    family(&mut out, "cc_usage_fetches_total", "counter", "Fetches since the app started.");
+    let _ = writeln!(out, "cc_usage_fetches_total {}", fetches.total);
+    family(&mut out, "cc_usage_fetch_errors_total", "counter", "Failed fetches since the app started.");
+    let _ = writeln!(out, "cc_usage_fetch_errors_total {}", fetches.errors);
+    family(&mut out, "cc_usage_consecutive_fetch_errors", "gauge", "Failed fetches since the last success.");
+    let _ = writeln!(out, "cc_usage_consecutive_fetch_errors {}", consecutive_errors);
+    if let Some(ms) = fetches.last_duration_ms {
+        family(&mut out, "cc_usage_fetch_duration_seconds", "gauge", "How long the last fetch took.");
+        let _ = writeln!(out, "cc_usage_fetch_duration_seconds {}", ms as f64 / 1000.0);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T14:00:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(67);
+        let fetches = FetchCounts {
+            total: 12,
+            errors: 2,
+            last_duration_ms: Some(8450),
+        };
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 30, 0).unwrap();
+        let text = render(&usage, 0, fetches, now);
+        assert!(text.contains("# TYPE cc_usage_percent gauge\n"));
+        assert!(text.contains("cc_usage_percent{metric=\"session\"} 42\n"));
+        assert!(text.contains("cc_usage_percent{metric=\"weekly\"} 67\n"));
+        assert!(!text.contains("metric=\"opus\""));
+        assert!(text.contains("cc_usage_reset_seconds{metric=\"session\"} 5400\n"));
+        assert!(!text.contains("cc_usage_reset_seconds{metric=\"weekly\"}"));
+        assert!(text.contains("cc_usage_fetch_errors_total 2\n"));
+        assert!(text.contains("cc_usage_fetch_duration_seconds 8.45\n"));
+    }
+}
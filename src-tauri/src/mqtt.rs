@@ -0,0 +1,268 @@
+// MQTT for Home Assistant and other smart-home hubs, off unless `mqtt_broker`
+// ("host" or "host:port", 1883 by default) is set. After every successful
+// fetch the reading goes to `<mqtt_topic>/state` as JSON, "online" to
+// `<mqtt_topic>/availability`, and a Home Assistant discovery config per
+// sensor under `<mqtt_discovery_prefix>/sensor/`, all retained. "offline" is
+// sent on quit; the sensors' `expire_after` covers a crash. Each publish is
+// its own short connection speaking just enough MQTT 3.1.1 (QoS 0, no TLS),
+// in the background; failures only go to the app log.
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Map, Value};
+
+use crate::{append_log, parse_reading_timestamp, resolve_reset, settings, MenuMetric, Settings, UsageData};
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TOPIC: &str = "cc-usage";
+const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+const TIMEOUT_SECS: u64 = 10;
+const KEEP_ALIVE_SECS: u16 = 30;
+// Readings missed before Home Assistant shows the sensors as unavailable
+const EXPIRE_AFTER_REFRESHES: u64 = 3;
+
+// (host, port) from "host" or "host:port"
+pub(crate) fn parse_broker(broker: &str) -> Option<(&str, u16)> {
+    let broker = broker.trim();
+    let (host, port) = match broker.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (broker, DEFAULT_PORT),
+    };
+    (!host.is_empty() && !host.contains('/')).then_some((host, port))
+}
+
+pub(crate) fn check_topic(label: &str, topic: Option<&str>) -> Result<(), String> {
+    match topic {
+        Some(t) if t.contains(['#', '+']) || t.starts_with('/') || t.ends_with('/') => {
+            Err(format!("MQTT {} can't contain # or + or start or end with / (got {})", label, t))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn push_length(out: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![kind];
+    push_length(&mut out, body.len());
+    out.extend_from_slice(body);
+    out
+}
+
+fn connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_string(&mut body, "MQTT");
+    // Protocol level 4 (3.1.1); clean session
+    body.push(4);
+    let mut flags = 0x02;
+    if username.is_some() {
+        flags |= 0x80;
+        if password.is_some() {
+            flags |= 0x40;
+        }
+    }
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    push_string(&mut body, client_id);
+    if let Some(username) = username {
+        push_string(&mut body, username);
+        if let Some(password) = password {
+            push_string(&mut body, password);
+        }
+    }
+    packet(0x10, &body)
+}
+
+fn publish_packet(topic: &str, payload: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_string(&mut body, topic);
+    body.extend_from_slice(payload.as_bytes());
+    // QoS 0, retained
+    packet(0x31, &body)
+}
+
+fn object_id(topic: &str) -> String {
+    topic.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+fn sensor_config(base: &str, metric: &str, name: &str, expire_after: u64, timestamp: bool) -> Value {
+    let id = object_id(base);
+    let mut config = json!({
+        "name": name,
+        "unique_id": format!("{}_{}", id, metric),
+        "state_topic": format!("{}/state", base),
+        "value_template": format!("{{{{ value_json.{} }}}}", metric),
+        "availability_topic": format!("{}/availability", base),
+        "expire_after": expire_after,
+        "device": {"identifiers": [id], "name": "Claude usage", "manufacturer": "cc-usage"},
+    });
+    if timestamp {
+        config["device_class"] = json!("timestamp");
+    } else {
+        config["unit_of_measurement"] = json!("%");
+        config["state_class"] = json!("measurement");
+    }
+    config
+}
+
+// Every (topic, payload) for one reading, in the order they're sent
+fn messages(settings: &Settings, usage: &UsageData, now: DateTime<Local>) -> Vec<(String, String)> {
+    let base = settings.mqtt_topic.as_deref().unwrap_or(DEFAULT_TOPIC);
+    let discovery = settings.mqtt_discovery_prefix.as_deref().unwrap_or(DEFAULT_DISCOVERY_PREFIX);
+    let expire_after = settings::refresh_interval_secs(settings) * EXPIRE_AFTER_REFRESHES;
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    let mut state = Map::new();
+    let mut out = vec![(format!("{}/availability", base), "online".to_string())];
+    for metric in MenuMetric::ALL {
+        let item = metric.item(usage);
+        let Some(percent) = item.percent else {
+            continue;
+        };
+        let key = format!("{}_percent", metric.as_str());
+        state.insert(key.clone(), json!(percent));
+        let config = sensor_config(base, &key, &format!("{} usage", metric.label()), expire_after, false);
+        out.push((format!("{}/sensor/{}/{}/config", discovery, object_id(base), key), config.to_string()));
+        if let Some(reset_at) = resolve_reset(item.resets.as_deref(), read_at) {
+            let key = format!("{}_resets_at", metric.as_str());
+            state.insert(key.clone(), json!(reset_at.to_rfc3339()));
+            let config = sensor_config(base, &key, &format!("{} reset", metric.label()), expire_after, true);
+            out.push((format!("{}/sensor/{}/{}/config", discovery, object_id(base), key), config.to_string()));
+        }
+    }
+    state.insert("timestamp".to_string(), json!(read_at.to_rfc3339()));
+    out.push((format!("{}/state", base), Value::Object(state).to_string()));
+    out
+}
+
+// Blocking: connect, publish each message, disconnect
+fn send(settings: &Settings, messages: &[(String, String)]) -> Result<(), String> {
+    let broker = settings.mqtt_broker.as_deref().unwrap_or_default();
+    let (host, port) = parse_broker(broker).ok_or_else(|| format!("Bad broker address: {}", broker))?;
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("No address for {}", host))?;
+    let timeout = Duration::from_secs(TIMEOUT_SECS);
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    let client_id = format!("cc-usage-{}", std::process::id());
+    let connect = connect_packet(&client_id, settings.mqtt_username.as_deref(), settings.mqtt_password.as_deref());
+    stream.write_all(&connect).map_err(|e| e.to_string())?;
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack).map_err(|e| e.to_string())?;
+    match connack {
+        [0x20, 0x02, _, 0] => {}
+        [0x20, 0x02, _, 4 | 5] => return Err("Broker rejected the username or password".to_string()),
+        [0x20, 0x02, _, code] => return Err(format!("Broker refused the connection (code {})", code)),
+        _ => return Err("Not an MQTT broker".to_string()),
+    }
+    for (topic, payload) in messages {
+        stream.write_all(&publish_packet(topic, payload)).map_err(|e| e.to_string())?;
+    }
+    stream.write_all(&[0xe0, 0x00]).map_err(|e| e.to_string())
+}
+
+// After a successful fetch
+pub(crate) fn publish(settings: &Settings, usage: &UsageData) {
+    if settings.mqtt_broker.is_none() {
+        return;
+    }
+    let messages = messages(settings, usage, Local::now());
+    let settings = settings.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = send(&settings, &messages) {
+            append_log(&format!("MQTT publish failed: {}", e));
+        }
+    });
+}
+
+// On quit, so Home Assistant shows the sensors as unavailable right away
+pub(crate) fn publish_offline(settings: &Settings) {
+    if settings.mqtt_broker.is_none() {
+        return;
+    }
+    let base = settings.mqtt_topic.as_deref().unwrap_or(DEFAULT_TOPIC);
+    let _ = send(settings, &[(format!("{}/availability", base), "offline".to_string())]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_packets() {
+        let mut len = Vec::new();
+        push_length(&mut len, 321);
+        assert_eq!(len, [0xc1, 0x02]);
+        assert_eq!(
+            publish_packet("a/b", "on"),
+            [0x31, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'o', b'n']
+        );
+        let connect = connect_packet("id", Some("u"), None);
+        // Fixed header, "MQTT", level, flags: username and clean session
+        assert_eq!(&connect[..10], [0x10, 0x11, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x82]);
+        assert_eq!(parse_broker("nas.local"), Some(("nas.local", 1883)));
+        assert_eq!(parse_broker("10.0.0.5:8883"), Some(("10.0.0.5", 8883)));
+        assert_eq!(parse_broker("mqtt://nas.local"), None);
+        assert!(check_topic("topic", Some("home/claude")).is_ok());
+        assert!(check_topic("topic", Some("home/#")).is_err());
+    }
+
+    #[test]
+    fn test_messages_for_home_assistant() {
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T14:00:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(67);
+        let settings = Settings {
+            mqtt_topic: Some("office/claude".to_string()),
+            refresh_interval_mins: Some(5),
+            ..Default::default()
+        };
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 0, 30).unwrap();
+        let messages = messages(&settings, &usage, now);
+        let topics: Vec<&str> = messages.iter().map(|(topic, _)| topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            [
+                "office/claude/availability",
+                "homeassistant/sensor/office_claude/session_percent/config",
+                "homeassistant/sensor/office_claude/session_resets_at/config",
+                "homeassistant/sensor/office_claude/weekly_percent/config",
+                "office/claude/state",
+            ]
+        );
+        let config: Value = serde_json::from_str(&messages[1].1).unwrap();
+        assert_eq!(config["value_template"], "{{ value_json.session_percent }}");
+        assert_eq!(config["expire_after"], 900);
+        let state: Value = serde_json::from_str(&messages[4].1).unwrap();
+        assert_eq!(state["session_percent"], 42);
+        assert_eq!(state["weekly_percent"], 67);
+        assert!(state["session_resets_at"].as_str().unwrap().starts_with("2026-01-28T16:00:00"));
+    }
+}
@@ -0,0 +1,203 @@
+// Desktop notifications through tauri-plugin-notification. The plugin can't
+// put buttons on desktop notifications, so on Linux usage alerts go to
+// notify-send instead, which can, for Refresh / Show Charts.
+#[cfg(target_os = "linux")]
+use std::process::Command;
+use std::sync::OnceLock;
+
+use tauri_plugin_notification::NotificationExt;
+
+use crate::locale::{text, Msg};
+use crate::{append_log, away, locale, AppState, Settings};
+
+type ActionHandler = Box<dyn Fn(AlertAction) + Send + Sync>;
+
+// Performs a clicked action; set once in setup
+static ACTION_HANDLER: OnceLock<ActionHandler> = OnceLock::new();
+
+// Shows the notifications; set once in setup. The daemon never sets it, having
+// no desktop to show them on.
+static APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+pub(crate) fn set_action_handler(handler: impl Fn(AlertAction) + Send + Sync + 'static) {
+    let _ = ACTION_HANDLER.set(Box::new(handler));
+}
+
+pub(crate) fn set_app(app: tauri::AppHandle) {
+    let _ = APP.set(app);
+}
+
+// System sounds offered in Settings (the names macOS knows; on Linux they're
+// passed along as the freedesktop sound-name hint)
+pub(crate) const SOUNDS: [&str; 14] = [
+    "Basso", "Blow", "Bottle", "Frog", "Funk", "Glass", "Hero", "Morse", "Ping", "Pop", "Purr", "Sosumi", "Submarine",
+    "Tink",
+];
+
+// How urgent a usage alert is; each level can have its own sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    // Good news: usable again, a window reset
+    Info,
+    // Thresholds and pace turning orange
+    Warning,
+    // 95% and up, pace turning red
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Alert {
+    pub(crate) title: String,
+    pub(crate) body: String,
+    pub(crate) severity: Severity,
+}
+
+impl Alert {
+    pub(crate) fn new(title: impl Into<String>, body: impl Into<String>, severity: Severity) -> Self {
+        Alert {
+            title: title.into(),
+            body: body.into(),
+            severity,
+        }
+    }
+}
+
+// Buttons on usage alerts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AlertAction {
+    Refresh,
+    ShowCharts,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl AlertAction {
+    const ALL: [AlertAction; 2] = [AlertAction::Refresh, AlertAction::ShowCharts];
+
+    // Same ids as the tray menu items
+    fn id(self) -> &'static str {
+        match self {
+            AlertAction::Refresh => "refresh",
+            AlertAction::ShowCharts => "charts",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AlertAction::Refresh => text(Msg::RefreshAction),
+            AlertAction::ShowCharts => text(Msg::ShowChartsAction),
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.id() == id)
+    }
+}
+
+// `sound_info` / `sound_warning` / `sound_critical`; none by default
+pub(crate) fn sound_for(settings: &Settings, severity: Severity) -> Option<&str> {
+    match severity {
+        Severity::Info => settings.sound_info.as_deref(),
+        Severity::Warning => settings.sound_warning.as_deref(),
+        Severity::Critical => settings.sound_critical.as_deref(),
+    }
+}
+
+pub(crate) fn send_notification(title: &str, body: &str) {
+    send_notification_with_sound(title, body, None);
+}
+
+pub(crate) fn send_alert(alert: &Alert, settings: &Settings) {
+    let sound = sound_for(settings, alert.severity);
+    #[cfg(target_os = "linux")]
+    if let Some(handler) = ACTION_HANDLER.get() {
+        send_with_actions(handler, alert.clone(), sound.map(str::to_string));
+        return;
+    }
+    send_notification_with_sound(&alert.title, &alert.body, sound);
+}
+
+// notify-send stays running while the notification is up and prints the id of
+// the clicked action. Versions before 0.7.9 don't know --action and exit with
+// an error, so those get a plain notification instead.
+#[cfg(target_os = "linux")]
+fn send_with_actions(handler: &'static ActionHandler, alert: Alert, sound: Option<String>) {
+    std::thread::spawn(move || {
+        let mut cmd = Command::new("notify-send");
+        if let Some(sound) = &sound {
+            cmd.arg(format!("--hint=string:sound-name:{}", sound.to_lowercase()));
+        }
+        for action in AlertAction::ALL {
+            cmd.arg(format!("--action={}={}", action.id(), action.label()));
+        }
+        match cmd.arg(&alert.title).arg(&alert.body).output() {
+            Ok(out) if out.status.success() => {
+                if let Some(action) = AlertAction::from_id(String::from_utf8_lossy(&out.stdout).trim()) {
+                    handler(action);
+                }
+            }
+            _ => send_notification_with_sound(&alert.title, &alert.body, sound.as_deref()),
+        }
+    });
+}
+
+fn send_notification_with_sound(title: &str, body: &str, sound: Option<&str>) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let mut notification = app.notification().builder().title(title).body(body);
+    if let Some(sound) = sound {
+        notification = notification.sound(sound);
+    }
+    if let Err(e) = notification.show() {
+        append_log(&format!("Notification failed: {}", e));
+    }
+}
+
+pub(crate) fn is_snoozed(state: &AppState, now: chrono::DateTime<chrono::Local>) -> bool {
+    state.alerts_snoozed_until.is_some_and(|until| now < until)
+}
+
+// Usage alerts (as opposed to feedback like "Backup saved") are held back while
+// away or snoozed; readings are still collected either way
+pub(crate) fn alerts_allowed(state: &AppState, now: chrono::DateTime<chrono::Local>) -> bool {
+    !away::is_away(state) && !is_snoozed(state, now)
+}
+
+// "For Today" snoozes until midnight
+pub(crate) fn end_of_day(now: chrono::DateTime<chrono::Local>) -> Option<chrono::DateTime<chrono::Local>> {
+    use chrono::TimeZone;
+    let midnight = now.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?;
+    chrono::Local.from_local_datetime(&midnight).earliest()
+}
+
+// Menu status line while snoozed
+pub(crate) fn snooze_text(state: &AppState, now: chrono::DateTime<chrono::Local>) -> Option<String> {
+    let until = state.alerts_snoozed_until.filter(|_| is_snoozed(state, now))?;
+    Some(format!("🔕 Alerts snoozed until {}", until.format(locale::clock(false))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_snooze_suppresses_alerts_until_it_ends() {
+        let now = chrono::Local.with_ymd_and_hms(2026, 1, 28, 14, 0, 0).unwrap();
+        let mut state = AppState::default();
+        assert!(alerts_allowed(&state, now));
+        state.alerts_snoozed_until = end_of_day(now);
+        assert!(!alerts_allowed(&state, now));
+        assert_eq!(snooze_text(&state, now).as_deref(), Some("🔕 Alerts snoozed until 00:00"));
+        assert!(alerts_allowed(&state, now + chrono::Duration::hours(10)));
+    }
+
+    #[test]
+    fn test_alert_actions_match_menu_ids() {
+        assert_eq!(AlertAction::from_id("charts"), Some(AlertAction::ShowCharts));
+        assert_eq!(AlertAction::from_id("refresh"), Some(AlertAction::Refresh));
+        // Dismissed without a click: notify-send prints nothing
+        assert_eq!(AlertAction::from_id(""), None);
+        assert_eq!(AlertAction::Refresh.label(), "Refresh");
+    }
+}
@@ -0,0 +1,284 @@
+// Pace indicator cutoffs per metric: how many points usage may run ahead of the
+// elapsed share of the window before the indicator turns orange and red, and
+// the usage that's red regardless of pace. Set with `<metric>_pace_thresholds`
+// ([orange, red, always red], default 10, 20, 90) and kept in a global like
+// the language, since the indicator is drawn from code with no settings at hand.
+//
+// `pacing_mode` decides what "elapsed" means: "linear" counts every hour of
+// the window, "work_hours" only weekday hours between `work_hours_start` and
+// `work_hours_end` (09:00-17:00), so a busy Monday isn't judged against a
+// week of nights and weekends. "off" drops pacing for plain usage levels.
+use std::sync::RwLock;
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Weekday};
+
+use crate::{elapsed_percent, quiet, MenuMetric, Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PaceThresholds {
+    pub(crate) orange: i32,
+    pub(crate) red: i32,
+    pub(crate) always_red: i32,
+}
+
+pub(crate) const DEFAULT: PaceThresholds = PaceThresholds {
+    orange: 10,
+    red: 20,
+    always_red: 90,
+};
+
+static CURRENT: RwLock<[PaceThresholds; 4]> = RwLock::new([DEFAULT; 4]);
+
+// With pacing off: yellow from here, orange from `OFF_ORANGE`, red from the
+// metric's always-red cutoff
+const OFF_YELLOW: i32 = 50;
+const OFF_ORANGE: i32 = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum PacingMode {
+    #[default]
+    Linear,
+    WorkHours,
+    Off,
+}
+
+impl PacingMode {
+    pub(crate) const ALL: [PacingMode; 3] = [PacingMode::Linear, PacingMode::WorkHours, PacingMode::Off];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            PacingMode::Linear => "linear",
+            PacingMode::WorkHours => "work_hours",
+            PacingMode::Off => "off",
+        }
+    }
+
+    fn from_setting(value: Option<&str>) -> Self {
+        Self::ALL.into_iter().find(|m| Some(m.as_str()) == value).unwrap_or_default()
+    }
+}
+
+// The mode, and the working day for `WorkHours`
+static PACING: RwLock<(PacingMode, Option<(NaiveTime, NaiveTime)>)> = RwLock::new((PacingMode::Linear, None));
+
+fn default_work_hours() -> (NaiveTime, NaiveTime) {
+    (
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap_or(NaiveTime::MIN),
+        NaiveTime::from_hms_opt(17, 0, 0).unwrap_or(NaiveTime::MIN),
+    )
+}
+
+fn work_hours(settings: &Settings) -> (NaiveTime, NaiveTime) {
+    let (default_start, default_end) = default_work_hours();
+    let start = settings.work_hours_start.as_deref().and_then(quiet::parse_clock);
+    let end = settings.work_hours_end.as_deref().and_then(quiet::parse_clock);
+    (start.unwrap_or(default_start), end.unwrap_or(default_end))
+}
+
+// Weekday minutes between `from` and `to` that fall inside the working day
+pub(crate) fn work_minutes(from: DateTime<Local>, to: DateTime<Local>, day: (NaiveTime, NaiveTime)) -> i64 {
+    let mut total = 0;
+    let mut date = from.date_naive();
+    while date <= to.date_naive() {
+        if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            let at = |time: NaiveTime| Local.from_local_datetime(&date.and_time(time)).earliest();
+            if let (Some(start), Some(end)) = (at(day.0), at(day.1)) {
+                let (a, b) = (start.max(from), end.min(to));
+                if b > a {
+                    total += b.signed_duration_since(a).num_minutes();
+                }
+            }
+        }
+        let Some(next) = date.succ_opt() else {
+            break;
+        };
+        date = next;
+    }
+    total
+}
+
+// Share of the window's working minutes gone by `now`. None for a window with
+// no working time in it (a late-night session), which falls back to linear.
+pub(crate) fn work_elapsed_percent(
+    reset_at: DateTime<Local>,
+    period_hours: i64,
+    now: DateTime<Local>,
+    day: (NaiveTime, NaiveTime),
+) -> Option<i32> {
+    let start = reset_at - chrono::Duration::hours(period_hours);
+    let total = work_minutes(start, reset_at, day);
+    (total > 0).then(|| (work_minutes(start, now.min(reset_at), day) * 100 / total) as i32)
+}
+
+// Elapsed share of the metric's window under the current pacing mode
+pub(crate) fn elapsed(metric: MenuMetric, reset_time: Option<DateTime<Local>>, now: DateTime<Local>) -> i32 {
+    let hours = metric.period_hours();
+    let (mode, day) = *PACING.read().unwrap();
+    if let (PacingMode::WorkHours, Some(reset_at)) = (mode, reset_time) {
+        if let Some(percent) = work_elapsed_percent(reset_at, hours, now, day.unwrap_or_else(default_work_hours)) {
+            return percent;
+        }
+    }
+    elapsed_percent(reset_time, hours as i32, now)
+}
+
+// The metric's pace indicator as of `now`
+pub(crate) fn indicator(
+    metric: MenuMetric,
+    usage_percent: i32,
+    reset_time: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> &'static str {
+    let cutoffs = thresholds(metric);
+    match PACING.read().unwrap().0 {
+        PacingMode::Off => cutoffs.level_indicator(usage_percent),
+        _ => cutoffs.indicator(usage_percent, elapsed(metric, reset_time, now)),
+    }
+}
+
+fn setting(settings: &Settings, metric: MenuMetric) -> Option<&Vec<i32>> {
+    match metric {
+        MenuMetric::Session => settings.session_pace_thresholds.as_ref(),
+        MenuMetric::Weekly => settings.weekly_pace_thresholds.as_ref(),
+        MenuMetric::Sonnet => settings.sonnet_pace_thresholds.as_ref(),
+        MenuMetric::Opus => settings.opus_pace_thresholds.as_ref(),
+    }
+}
+
+impl PaceThresholds {
+    pub(crate) fn from_values(values: &[i32]) -> Option<Self> {
+        match *values {
+            [orange, red, always_red] => Some(PaceThresholds { orange, red, always_red }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_settings(settings: &Settings, metric: MenuMetric) -> Self {
+        setting(settings, metric)
+            .and_then(|values| Self::from_values(values))
+            .unwrap_or(DEFAULT)
+    }
+
+    // "🔴" at `always_red` and up or `red`+ points ahead, "🟠" `orange`+
+    // ahead, "🟡" ahead at all, otherwise "🟢"
+    pub(crate) fn indicator(self, usage_percent: i32, time_percent: i32) -> &'static str {
+        let pace_diff = usage_percent - time_percent;
+        if usage_percent >= self.always_red || pace_diff >= self.red {
+            "🔴"
+        } else if pace_diff >= self.orange {
+            "🟠"
+        } else if pace_diff > 0 {
+            "🟡"
+        } else {
+            "🟢"
+        }
+    }
+
+    // Pacing off: the color follows usage alone
+    pub(crate) fn level_indicator(self, usage_percent: i32) -> &'static str {
+        if usage_percent >= self.always_red {
+            "🔴"
+        } else if usage_percent >= OFF_ORANGE {
+            "🟠"
+        } else if usage_percent >= OFF_YELLOW {
+            "🟡"
+        } else {
+            "🟢"
+        }
+    }
+}
+
+// Why a `<metric>_pace_thresholds` value is invalid
+pub(crate) fn check(settings: &Settings) -> Result<(), String> {
+    for metric in MenuMetric::ALL {
+        let Some(values) = setting(settings, metric) else {
+            continue;
+        };
+        let Some(t) = PaceThresholds::from_values(values) else {
+            return Err(format!("{} pace thresholds need three numbers: orange, red, always red", metric.as_str()));
+        };
+        if !(1..=100).contains(&t.orange) || !(1..=100).contains(&t.red) || !(1..=100).contains(&t.always_red) {
+            return Err(format!("{} pace thresholds must be between 1 and 100", metric.as_str()));
+        }
+        if t.orange > t.red {
+            return Err(format!("{} pace: orange can't be above red", metric.as_str()));
+        }
+    }
+    let work = [settings.work_hours_start.as_deref(), settings.work_hours_end.as_deref()];
+    if let Some(value) = work.into_iter().flatten().find(|v| quiet::parse_clock(v).is_none()) {
+        return Err(format!("Work hours must look like 09:00 (got {})", value));
+    }
+    let (start, end) = work_hours(settings);
+    if start >= end {
+        return Err("Work hours must end after they start".to_string());
+    }
+    Ok(())
+}
+
+fn index(metric: MenuMetric) -> usize {
+    MenuMetric::ALL.iter().position(|m| *m == metric).unwrap_or(0)
+}
+
+pub(crate) fn apply(settings: &Settings) {
+    let mut current = CURRENT.write().unwrap();
+    for metric in MenuMetric::ALL {
+        current[index(metric)] = PaceThresholds::from_settings(settings, metric);
+    }
+    let mode = PacingMode::from_setting(settings.pacing_mode.as_deref());
+    *PACING.write().unwrap() = (mode, Some(work_hours(settings)));
+}
+
+pub(crate) fn thresholds(metric: MenuMetric) -> PaceThresholds {
+    CURRENT.read().unwrap()[index(metric)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_thresholds_shift_the_colors() {
+        let conservative = PaceThresholds::from_values(&[5, 10, 80]).unwrap();
+        assert_eq!(DEFAULT.indicator(57, 50), "🟡");
+        assert_eq!(conservative.indicator(57, 50), "🟠");
+        assert_eq!(DEFAULT.indicator(85, 80), "🟡");
+        assert_eq!(conservative.indicator(85, 80), "🔴");
+
+        let settings = Settings {
+            weekly_pace_thresholds: Some(vec![20, 40, 95]),
+            ..Default::default()
+        };
+        assert_eq!(PaceThresholds::from_settings(&settings, MenuMetric::Session), DEFAULT);
+        assert_eq!(PaceThresholds::from_settings(&settings, MenuMetric::Weekly).always_red, 95);
+        assert!(check(&settings).is_ok());
+        let backwards = Settings {
+            session_pace_thresholds: Some(vec![30, 20, 90]),
+            ..Default::default()
+        };
+        assert!(check(&backwards).is_err());
+        let short = Settings {
+            opus_pace_thresholds: Some(vec![10, 20]),
+            ..Default::default()
+        };
+        assert!(check(&short).is_err());
+    }
+
+    #[test]
+    fn test_work_hours_skip_nights_and_weekends() {
+        let day = default_work_hours();
+        // The week resets at 9am next Monday; by 5pm on Monday (2026-01-26),
+        // 1 of 5 working days has gone, though it's only 8h into 168h
+        let reset_at = Local.with_ymd_and_hms(2026, 2, 2, 9, 0, 0).unwrap();
+        let monday_evening = Local.with_ymd_and_hms(2026, 1, 26, 17, 0, 0).unwrap();
+        assert_eq!(work_elapsed_percent(reset_at, 168, monday_evening, day), Some(20));
+        let saturday = Local.with_ymd_and_hms(2026, 1, 31, 12, 0, 0).unwrap();
+        assert_eq!(work_elapsed_percent(reset_at, 168, saturday, day), Some(100));
+        // A session entirely after hours has no working time
+        let late = Local.with_ymd_and_hms(2026, 1, 28, 23, 0, 0).unwrap();
+        assert_eq!(work_elapsed_percent(late, 4, late, day), None);
+
+        assert_eq!(DEFAULT.level_indicator(60), "🟡");
+        assert_eq!(DEFAULT.level_indicator(30), "🟢");
+        assert_eq!(PacingMode::from_setting(Some("work_hours")), PacingMode::WorkHours);
+    }
+}
@@ -0,0 +1,68 @@
+// Pausing automatic refresh from the tray (tethering, flights, or just not
+// wanting the extra claude sessions). Unlike away mode nothing is recorded and
+// notifications keep working; "Refresh Now" still fetches. The pause lasts
+// until it expires, "Resume Auto-Refresh", or the app restarts.
+use chrono::{DateTime, Local};
+
+use crate::{locale, AppState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RefreshPause {
+    Until(DateTime<Local>),
+    UntilResumed,
+}
+
+pub(crate) fn is_paused(state: &AppState, now: DateTime<Local>) -> bool {
+    match state.refresh_pause {
+        Some(RefreshPause::Until(until)) => now < until,
+        Some(RefreshPause::UntilResumed) => true,
+        None => false,
+    }
+}
+
+// Seconds until a timed pause ends, so the loop wakes up on time
+pub(crate) fn secs_until_resume(state: &AppState, now: DateTime<Local>) -> Option<u64> {
+    let Some(RefreshPause::Until(until)) = state.refresh_pause else {
+        return None;
+    };
+    let remaining = until.signed_duration_since(now).num_seconds();
+    (remaining > 0).then_some(remaining as u64)
+}
+
+// Menu status line while paused
+pub(crate) fn status_text(state: &AppState, now: DateTime<Local>) -> Option<String> {
+    if !is_paused(state, now) {
+        return None;
+    }
+    Some(match state.refresh_pause? {
+        RefreshPause::Until(until) if until.date_naive() == now.date_naive() => {
+            format!("⏸ Auto-refresh paused until {}", until.format(locale::clock(false)))
+        }
+        RefreshPause::Until(until) => format!("⏸ Auto-refresh paused until {}", until.format(&locale::date_clock(false))),
+        RefreshPause::UntilResumed => "⏸ Auto-refresh paused".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 0, 0).unwrap();
+        let mut state = AppState {
+            refresh_pause: Some(RefreshPause::Until(now + chrono::Duration::hours(1))),
+            ..Default::default()
+        };
+        assert!(is_paused(&state, now));
+        assert_eq!(secs_until_resume(&state, now), Some(3600));
+        assert_eq!(status_text(&state, now).as_deref(), Some("⏸ Auto-refresh paused until 15:00"));
+        assert!(!is_paused(&state, now + chrono::Duration::hours(2)));
+        assert_eq!(status_text(&state, now + chrono::Duration::hours(2)), None);
+
+        state.refresh_pause = Some(RefreshPause::UntilResumed);
+        assert!(is_paused(&state, now + chrono::Duration::days(30)));
+        assert_eq!(secs_until_resume(&state, now), None);
+    }
+}
@@ -0,0 +1,104 @@
+// Limit window lengths, for pace and for telling windows apart. Set with
+// `session_period_hours` / `weekly_period_hours`; unset, each is derived from
+// history when there's enough of it, else the 4h / 168h defaults. Sonnet and
+// Opus follow the weekly window. Kept in a global like the pace cutoffs,
+// refreshed at startup and when settings are saved.
+use std::sync::RwLock;
+
+use chrono::{DateTime, Local};
+use rusqlite::Connection;
+
+use crate::{init_db, parse_reading_timestamp, Settings, SESSION_PERIOD_HOURS, WEEKLY_PERIOD_HOURS};
+
+// Windows of history needed before trusting a derived length
+const MIN_WINDOWS: usize = 3;
+// How far back derivation looks
+const HISTORY_DAYS: i64 = 60;
+
+pub(crate) const MAX_SESSION_HOURS: i64 = 12;
+pub(crate) const MAX_WEEKLY_HOURS: i64 = 336;
+
+static CURRENT: RwLock<(i64, i64)> = RwLock::new((SESSION_PERIOD_HOURS, WEEKLY_PERIOD_HOURS));
+
+pub(crate) fn session_hours() -> i64 {
+    CURRENT.read().unwrap().0
+}
+
+pub(crate) fn weekly_hours() -> i64 {
+    CURRENT.read().unwrap().1
+}
+
+// A window starts with its first use, so no reading in it is older than its
+// length before the reset. The longest such gap, rounded up to the hour, is
+// the length once enough windows have a reading near their start. Gaps longer
+// than `max_hours` (a reset string read wrong) are ignored.
+pub(crate) fn derive_hours(windows: &[(DateTime<Local>, DateTime<Local>)], max_hours: i64) -> Option<i64> {
+    let hours: Vec<i64> = windows
+        .iter()
+        .map(|(reset_at, first_read)| reset_at.signed_duration_since(*first_read).num_minutes())
+        .filter(|&m| m > 0)
+        .map(|m| (m + 59) / 60)
+        .filter(|&h| h <= max_hours)
+        .collect();
+    (hours.len() >= MIN_WINDOWS).then(|| hours.into_iter().max()).flatten()
+}
+
+// (reset, earliest reading with usage) for each window in recent history;
+// `metric` is the column prefix
+fn observed_windows(conn: &Connection, metric: &str) -> Vec<(DateTime<Local>, DateTime<Local>)> {
+    let cutoff = (Local::now() - chrono::Duration::days(HISTORY_DAYS))
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string();
+    let sql = format!(
+        "SELECT {0}_reset_at, MIN(timestamp) FROM usage_history
+         WHERE {0}_reset_at IS NOT NULL AND {0}_percent > 0 AND timestamp >= ?1
+         GROUP BY {0}_reset_at",
+        metric
+    );
+    let Ok(mut stmt) = conn.prepare(&sql) else {
+        return Vec::new();
+    };
+    stmt.query_map([&cutoff], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map(|rows| {
+            rows.flatten()
+                .filter_map(|(reset_at, first)| Some((parse_reading_timestamp(&reset_at)?, parse_reading_timestamp(&first)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn apply(settings: &Settings) {
+    let conn = init_db().ok();
+    let derived = |metric: &str, max_hours: i64| {
+        conn.as_ref()
+            .and_then(|conn| derive_hours(&observed_windows(conn, metric), max_hours))
+    };
+    let session = settings
+        .session_period_hours
+        .or_else(|| derived("session", MAX_SESSION_HOURS))
+        .unwrap_or(SESSION_PERIOD_HOURS);
+    let weekly = settings
+        .weekly_period_hours
+        .or_else(|| derived("weekly", MAX_WEEKLY_HOURS))
+        .unwrap_or(WEEKLY_PERIOD_HOURS);
+    *CURRENT.write().unwrap() = (session, weekly);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_derive_hours_from_earliest_readings() {
+        let reset = |day: u32| Local.with_ymd_and_hms(2026, 1, day, 15, 0, 0).unwrap();
+        let before = |day: u32, mins: i64| reset(day) - chrono::Duration::minutes(mins);
+        // A 5h window: one window read 4h52m before its reset
+        let windows = [(reset(26), before(26, 180)), (reset(27), before(27, 292)), (reset(28), before(28, 60))];
+        assert_eq!(derive_hours(&windows, MAX_SESSION_HOURS), Some(5));
+        assert_eq!(derive_hours(&windows[..2], MAX_SESSION_HOURS), None);
+        // 50h can't be a session, leaving too few windows
+        let stale = [(reset(26), before(26, 3000)), (reset(27), before(27, 60)), (reset(28), before(28, 60))];
+        assert_eq!(derive_hours(&stale, MAX_SESSION_HOURS), None);
+    }
+}
@@ -0,0 +1,91 @@
+// Left-click popover: with the `tray_click` setting set to "popover", a left
+// click on the tray icon toggles a small always-on-top window (popover.html:
+// gauges and a sparkline) under the icon, and the menu moves to right-click.
+// The popover hides again when it loses focus.
+use tauri::{Manager, Runtime, WebviewWindowBuilder};
+
+pub(crate) const POPOVER_LABEL: &str = "popover";
+// Logical size
+const WIDTH: f64 = 300.0;
+const HEIGHT: f64 = 190.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum TrayClick {
+    #[default]
+    Menu,
+    Popover,
+}
+
+impl TrayClick {
+    // `tray_click` setting: "menu" (default) or "popover"
+    pub(crate) fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("popover") => TrayClick::Popover,
+            _ => TrayClick::Menu,
+        }
+    }
+}
+
+// Top-left corner (physical pixels) centering a `width`-wide popover under the
+// icon, kept on screen at the left edge
+pub(crate) fn popover_position(icon_x: f64, icon_y: f64, icon_width: f64, icon_height: f64, width: f64) -> (f64, f64) {
+    let x = (icon_x + icon_width / 2.0 - width / 2.0).max(0.0);
+    (x, icon_y + icon_height)
+}
+
+fn show_under_icon<R: Runtime>(window: &tauri::WebviewWindow<R>, icon: tauri::Rect) {
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let icon_pos = icon.position.to_physical::<f64>(scale);
+    let icon_size = icon.size.to_physical::<f64>(scale);
+    let (x, y) = popover_position(icon_pos.x, icon_pos.y, icon_size.width, icon_size.height, WIDTH * scale);
+    let _ = window.set_position(tauri::PhysicalPosition::new(x, y));
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+// Left click on the tray icon
+pub(crate) fn toggle<R: Runtime>(app: &tauri::AppHandle<R>, icon: tauri::Rect) {
+    if let Some(window) = app.get_webview_window(POPOVER_LABEL) {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            // Reload so it shows the current reading, not the one from last time
+            let _ = window.eval("window.location.reload()");
+            show_under_icon(&window, icon);
+        }
+        return;
+    }
+    let Ok(window) = WebviewWindowBuilder::new(app, POPOVER_LABEL, tauri::WebviewUrl::App("popover.html".into()))
+        .title("Claude Usage")
+        .inner_size(WIDTH, HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+    else {
+        return;
+    };
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Focused(false) = event {
+            let _ = handle.hide();
+        }
+    });
+    show_under_icon(&window, icon);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_popover_centered_under_icon() {
+        assert_eq!(popover_position(1000.0, 0.0, 44.0, 48.0, 600.0), (722.0, 48.0));
+        // Icon near the left edge
+        assert_eq!(popover_position(20.0, 0.0, 44.0, 48.0, 600.0), (0.0, 48.0));
+        assert_eq!(TrayClick::from_setting(Some("popover")), TrayClick::Popover);
+        assert_eq!(TrayClick::from_setting(None), TrayClick::Menu);
+    }
+}
@@ -0,0 +1,151 @@
+// Named profiles for more than one Claude account (work and personal). Each
+// one runs the fetch with its own CLAUDE_CONFIG_DIR, keeps its own cache file,
+// and tags its history rows, and the tray's Profile submenu picks the one on
+// display. The default profile is the plain account with untagged rows, as
+// before profiles existed. Defined in `profiles` ([{name, claude_config_dir}])
+// with `active_profile` naming the current one; kept in a global like the
+// pace cutoffs, since the fetch and the cache path have no settings at hand.
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datadir, load_cached_usage, load_previous_reading, load_saved_settings, load_settings, save_settings, store, trend,
+    AppState, Settings, CACHE_FILE,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub(crate) struct Profile {
+    pub(crate) name: String,
+    // Unset uses the claude CLI's own default (~/.claude)
+    #[serde(default)]
+    pub(crate) claude_config_dir: Option<String>,
+}
+
+pub(crate) const MENU_PREFIX: &str = "profile:";
+
+// Every profile, and the active one (None for the default)
+static CURRENT: RwLock<(Vec<Profile>, Option<Profile>)> = RwLock::new((Vec::new(), None));
+
+// An `active_profile` that's no longer defined falls back to the default
+fn active_in(settings: &Settings) -> Option<Profile> {
+    let name = settings.active_profile.as_deref()?;
+    settings.profiles.as_ref()?.iter().find(|p| p.name == name).cloned()
+}
+
+// Cache file names come from the profile name
+fn slug(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+pub(crate) fn check(settings: &Settings) -> Result<(), String> {
+    let profiles = settings.profiles.as_deref().unwrap_or_default();
+    let mut slugs = Vec::new();
+    for profile in profiles {
+        if profile.name.trim().is_empty() {
+            return Err("Profiles need a name".to_string());
+        }
+        let slug = slug(&profile.name);
+        if slugs.contains(&slug) {
+            return Err(format!("Profile names must differ (\"{}\" is used twice)", profile.name));
+        }
+        slugs.push(slug);
+    }
+    Ok(())
+}
+
+// True when the active profile changed. Readings still queued belong to the
+// old one, so they're written before it switches.
+pub(crate) fn apply(settings: &Settings) -> bool {
+    let active = active_in(settings);
+    let changed = CURRENT.read().unwrap().1 != active;
+    if changed {
+        store::flush();
+    }
+    *CURRENT.write().unwrap() = (settings.profiles.clone().unwrap_or_default(), active);
+    changed
+}
+
+pub(crate) fn all() -> Vec<Profile> {
+    CURRENT.read().unwrap().0.clone()
+}
+
+// The tag on the active profile's history rows
+pub(crate) fn active_name() -> Option<String> {
+    CURRENT.read().unwrap().1.as_ref().map(|p| p.name.clone())
+}
+
+pub(crate) fn cache_file() -> String {
+    match active_name() {
+        Some(name) => format!("cc-usage-cache-{}.json", slug(&name)),
+        None => CACHE_FILE.to_string(),
+    }
+}
+
+// CLAUDE_CONFIG_DIR for the active profile, if it sets one
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let current = CURRENT.read().unwrap();
+    let dir = current.1.as_ref()?.claude_config_dir.as_deref()?;
+    (!dir.trim().is_empty()).then(|| datadir::expand_home(dir.trim()))
+}
+
+pub(crate) fn menu_id(name: Option<&str>) -> String {
+    format!("{}{}", MENU_PREFIX, name.unwrap_or(""))
+}
+
+// The profile a menu id selects: Some(None) for the default
+pub(crate) fn from_menu_id(id: &str) -> Option<Option<String>> {
+    let name = id.strip_prefix(MENU_PREFIX)?;
+    Some((!name.is_empty()).then(|| name.to_string()))
+}
+
+// Show the new profile's last reading and history until its first fetch
+pub(crate) fn reload(state: &mut AppState) {
+    state.usage = load_cached_usage().unwrap_or_default();
+    state.previous_usage = load_previous_reading(&state.usage);
+    state.last_error = None;
+    state.consecutive_errors = 0;
+    state.trends = trend::Trends::load();
+}
+
+pub(crate) fn switch(state: &mut AppState, name: Option<String>) {
+    let mut settings = load_saved_settings();
+    settings.active_profile = name;
+    save_settings(&settings);
+    if apply(&load_settings()) {
+        reload(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_need_distinct_names() {
+        let profile = |name: &str| Profile {
+            name: name.to_string(),
+            claude_config_dir: Some("~/.claude-work".to_string()),
+        };
+        let mut settings = Settings {
+            profiles: Some(vec![profile("Work"), profile("Personal")]),
+            active_profile: Some("Work".to_string()),
+            ..Default::default()
+        };
+        assert!(check(&settings).is_ok());
+        assert_eq!(active_in(&settings), Some(profile("Work")));
+        settings.active_profile = Some("Side project".to_string());
+        assert_eq!(active_in(&settings), None);
+        settings.profiles = Some(vec![profile("Work"), profile("work")]);
+        assert!(check(&settings).is_err());
+
+        assert_eq!(slug("My Work"), "my-work");
+        assert_eq!(from_menu_id(&menu_id(Some("Work"))), Some(Some("Work".to_string())));
+        assert_eq!(from_menu_id(&menu_id(None)), Some(None));
+        assert_eq!(from_menu_id("refresh"), None);
+    }
+}
@@ -0,0 +1,153 @@
+// Shell prompt segments (starship, powerlevel10k). The tray keeps
+// cc-usage-prompt.json in the data folder current: per metric, the percent,
+// its pace color, and the time to reset, rewritten (atomically, like the
+// status file) on every tray update. `cc-usage prompt` prints it as a short
+// ANSI-colored segment straight from that file, without loading settings or
+// opening the database, since prompts run it on every command.
+use std::collections::BTreeMap;
+use std::fs;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datadir, get_status_indicator_paced, indicator_color, parse_reading_timestamp, privacy_mode, resolve_reset, tmux,
+    AppState, MenuMetric, Settings, UsageData,
+};
+
+pub(crate) const PROMPT_FILE: &str = "cc-usage-prompt.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MetricSnapshot {
+    percent: i32,
+    // "green", "yellow", "orange", or "red"
+    color: String,
+    // As of `written_at`; None without a reset time
+    reset_secs: Option<i64>,
+    resets_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PromptSnapshot {
+    // The reading's own timestamp
+    timestamp: Option<String>,
+    written_at: String,
+    // Fetches are failing; the numbers are from the last good reading
+    failing: bool,
+    metrics: BTreeMap<String, MetricSnapshot>,
+}
+
+pub(crate) fn snapshot(usage: &UsageData, failing: bool, now: DateTime<Local>) -> PromptSnapshot {
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    let metrics = MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let item = metric.item(usage);
+            let percent = item.percent?;
+            let reset_at = resolve_reset(item.resets.as_deref(), read_at);
+            let snapshot = MetricSnapshot {
+                percent,
+                color: indicator_color(get_status_indicator_paced(percent, item.resets.as_deref(), metric)).to_string(),
+                reset_secs: reset_at.map(|at| at.signed_duration_since(now).num_seconds().max(0)),
+                resets_at: reset_at.map(|at| at.to_rfc3339()),
+            };
+            Some((metric.as_str().to_string(), snapshot))
+        })
+        .collect();
+    PromptSnapshot {
+        timestamp: usage.timestamp.clone(),
+        written_at: now.to_rfc3339(),
+        failing,
+        metrics,
+    }
+}
+
+pub(crate) fn write_snapshot(state: &AppState, settings: &Settings) {
+    if privacy_mode(settings) {
+        return;
+    }
+    let snapshot = snapshot(&state.usage, state.last_error.is_some(), Local::now());
+    if let Ok(json) = serde_json::to_string_pretty(&snapshot) {
+        let _ = datadir::write_atomic(&datadir::data_dir().join(PROMPT_FILE), &json);
+    }
+}
+
+fn ansi(color: &str) -> &'static str {
+    match color {
+        "yellow" => "\x1b[33m",
+        "orange" => "\x1b[38;5;208m",
+        "red" => "\x1b[31m",
+        _ => "\x1b[32m",
+    }
+}
+
+// "S42% W67% 3h", each percentage in its pace color. The countdown is worked
+// out from `resets_at` now, not from when the file was written.
+pub(crate) fn render(snapshot: &PromptSnapshot, color: bool, now: DateTime<Local>) -> String {
+    let mut parts = Vec::new();
+    for (key, letter) in [("session", "S"), ("weekly", "W")] {
+        if let Some(metric) = snapshot.metrics.get(key) {
+            let text = format!("{}{}%", letter, metric.percent);
+            parts.push(if color { format!("{}{}\x1b[0m", ansi(&metric.color), text) } else { text });
+        }
+    }
+    let reset_at = snapshot
+        .metrics
+        .get("session")
+        .and_then(|m| m.resets_at.as_deref())
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok());
+    parts.extend(reset_at.and_then(|at| tmux::compact_countdown(at.with_timezone(&Local), now)));
+    if snapshot.failing {
+        parts.push("!".to_string());
+    }
+    parts.join(" ")
+}
+
+// Prints nothing (and succeeds) before the app has written a reading, so an
+// empty segment is hidden. NO_COLOR turns the colors off.
+pub(crate) fn print() -> i32 {
+    let path = datadir::data_dir().join(PROMPT_FILE);
+    let Some(snapshot) = fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<PromptSnapshot>(&json).ok())
+    else {
+        return 0;
+    };
+    let color = std::env::var_os("NO_COLOR").map_or(true, |v| v.is_empty());
+    let line = render(&snapshot, color, Local::now());
+    if !line.is_empty() {
+        println!("{}", line);
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_snapshot_and_prompt() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 12, 50, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T12:45:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(95);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(10);
+        let snapshot = snapshot(&usage, false, now);
+        let session = &snapshot.metrics["session"];
+        assert_eq!(session.color, "red");
+        assert_eq!(session.reset_secs, Some(190 * 60));
+        assert!(!snapshot.metrics.contains_key("opus"));
+
+        // Read back from the file
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: PromptSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(render(&snapshot, false, now), "S95% W10% 3h");
+        // The countdown comes from resets_at, not the saved reset_secs
+        assert_eq!(render(&snapshot, false, now + chrono::Duration::minutes(150)), "S95% W10% 40m");
+        assert!(render(&snapshot, true, now).starts_with("\x1b[31mS95%\x1b[0m "));
+    }
+}
@@ -0,0 +1,150 @@
+// Quiet hours: usage alerts raised during them are held back instead of shown,
+// and the countdown ticker sends one "While you were away" summary when they
+// end. Unlike snoozing, nothing is dropped. Set with `quiet_hours_start` /
+// `quiet_hours_end` ("22:00" / "08:00", may span midnight) and
+// `quiet_weekends`.
+use chrono::{DateTime, Datelike, Local, NaiveTime, Weekday};
+
+use crate::locale::{text, tr, Msg};
+use crate::notify::{self, Alert};
+use crate::{AppState, Settings};
+
+// Titles listed in the summary before it switches to "and N more"
+const SUMMARY_MAX_TITLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct QuietHours {
+    span: Option<(NaiveTime, NaiveTime)>,
+    weekends: bool,
+}
+
+pub(crate) fn parse_clock(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+impl QuietHours {
+    // None when neither hours nor weekends are set
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        let start = settings.quiet_hours_start.as_deref().and_then(parse_clock);
+        let end = settings.quiet_hours_end.as_deref().and_then(parse_clock);
+        let span = start.zip(end).filter(|(s, e)| s != e);
+        let weekends = settings.quiet_weekends.unwrap_or(false);
+        (span.is_some() || weekends).then_some(QuietHours { span, weekends })
+    }
+
+    pub(crate) fn contains(&self, now: DateTime<Local>) -> bool {
+        if self.weekends && matches!(now.weekday(), Weekday::Sat | Weekday::Sun) {
+            return true;
+        }
+        let Some((start, end)) = self.span else {
+            return false;
+        };
+        let time = now.time();
+        if start < end {
+            start <= time && time < end
+        } else {
+            // Overnight, e.g. 22:00-08:00
+            time >= start || time < end
+        }
+    }
+}
+
+pub(crate) fn is_quiet(settings: &Settings, now: DateTime<Local>) -> bool {
+    QuietHours::from_settings(settings).is_some_and(|q| q.contains(now))
+}
+
+// Holds the alert for the summary during quiet hours; otherwise hands it back
+// to be sent
+pub(crate) fn hold_if_quiet(
+    state: &mut AppState,
+    settings: &Settings,
+    now: DateTime<Local>,
+    alert: Alert,
+) -> Option<Alert> {
+    if is_quiet(settings, now) {
+        state.held_alerts.push(alert);
+        None
+    } else {
+        Some(alert)
+    }
+}
+
+// "While you were away" / "Session at 85%; Weekly at 70%; … and 2 more", as
+// severe as the worst held alert
+pub(crate) fn summary(held: &[Alert]) -> Option<Alert> {
+    let severity = held.iter().map(|a| a.severity).max()?;
+    let titles = held.iter().take(SUMMARY_MAX_TITLES).map(|a| a.title.as_str());
+    let mut body = titles.collect::<Vec<_>>().join("; ");
+    if held.len() > SUMMARY_MAX_TITLES {
+        body.push_str(&tr(Msg::AndMore, &[&(held.len() - SUMMARY_MAX_TITLES)]));
+    }
+    Some(Alert::new(text(Msg::QuietSummaryTitle), body, severity))
+}
+
+// Called every minute by the countdown ticker
+pub(crate) fn flush_if_over(state: &mut AppState, settings: &Settings, now: DateTime<Local>) {
+    if is_quiet(settings, now) {
+        return;
+    }
+    if let Some(alert) = summary(&state.held_alerts) {
+        state.held_alerts.clear();
+        notify::send_alert(&alert, settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_quiet_hours_span_midnight_and_weekends() {
+        let settings = Settings {
+            quiet_hours_start: Some("22:00".to_string()),
+            quiet_hours_end: Some("08:00".to_string()),
+            ..Default::default()
+        };
+        // 2026-01-28 is a Wednesday
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2026, 1, day, hour, 0, 0).unwrap();
+        assert!(is_quiet(&settings, at(28, 23)));
+        assert!(is_quiet(&settings, at(28, 7)));
+        assert!(!is_quiet(&settings, at(28, 8)));
+        assert!(!is_quiet(&settings, at(31, 12)));
+        let weekends = Settings {
+            quiet_weekends: Some(true),
+            ..Default::default()
+        };
+        assert!(is_quiet(&weekends, at(31, 12)));
+        assert!(!is_quiet(&Settings::default(), at(28, 23)));
+    }
+
+    #[test]
+    fn test_summary_lists_held_alerts() {
+        use crate::notify::Severity;
+        let held: Vec<Alert> = [
+            ("Session at 70%", Severity::Warning),
+            ("Session at 85%", Severity::Warning),
+            ("Weekly at 70%", Severity::Warning),
+            ("Session at 95%", Severity::Critical),
+        ]
+        .map(|(title, severity)| Alert::new(title, "", severity))
+        .to_vec();
+        let alert = summary(&held).unwrap();
+        assert_eq!(alert.title, "While you were away");
+        assert_eq!(alert.body, "Session at 70%; Session at 85%; Weekly at 70%; … and 1 more");
+        assert_eq!(alert.severity, Severity::Critical);
+        assert_eq!(summary(&[]), None);
+
+        let mut state = AppState {
+            held_alerts: held,
+            ..Default::default()
+        };
+        let settings = Settings {
+            quiet_weekends: Some(true),
+            ..Default::default()
+        };
+        let saturday = Local.with_ymd_and_hms(2026, 1, 31, 12, 0, 0).unwrap();
+        flush_if_over(&mut state, &settings, saturday);
+        assert_eq!(state.held_alerts.len(), 4);
+    }
+}
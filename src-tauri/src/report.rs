@@ -0,0 +1,324 @@
+// A usage report over a range (the last week by default) for pasting into a
+// team retro: averages and peaks, the busiest days, limit hits, how far ahead
+// of pace the week ran, and tokens and cost from the transcripts. Markdown, or
+// HTML for tools that don't take Markdown.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+
+use crate::locale::{text, Msg};
+use crate::{
+    choose_save_path, cost, format_hours_minutes, init_db, limits, locale, parse_reading_timestamp, period, profiles, stats,
+    transcripts,
+};
+
+const DEFAULT_DAYS: i64 = 7;
+const PEAK_DAYS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+// Dates or timestamps as `get_stats` takes them; `to` defaults to now and
+// `from` to a week before it
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct ReportRange {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    heading: String,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Report {
+    title: String,
+    sections: Vec<Section>,
+}
+
+fn day_label(timestamp: &str) -> String {
+    parse_reading_timestamp(timestamp)
+        .map(|t| t.format("%a %b %-d").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn time_label(timestamp: &str) -> String {
+    parse_reading_timestamp(timestamp)
+        .map(|t| t.format(&format!("%a %b %-d {}", locale::clock(false))).to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn tokens(count: u64) -> String {
+    match count {
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1_000_000.0),
+        n if n >= 1_000 => format!("{:.1}k", n as f64 / 1_000.0),
+        n => n.to_string(),
+    }
+}
+
+fn summary_lines(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<Vec<String>> {
+    let stats = stats::query_stats(conn, from, to)?;
+    let mut lines = vec![format!("Readings: {}", stats.readings)];
+    for (label, metric) in [("Session", &stats.session), ("Weekly", &stats.weekly), ("Sonnet", &stats.sonnet)] {
+        if let (Some(avg), Some(max)) = (metric.avg, metric.max) {
+            let peak = metric.peak_at.as_deref().map(time_label).unwrap_or_default();
+            lines.push(format!("{}: average {:.0}%, peak {}% ({})", label, avg, max, peak));
+        }
+    }
+    Ok(lines)
+}
+
+// The days with the most weekly usage added
+fn peak_day_lines(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT substr(timestamp, 1, 10) AS day, SUM(weekly_delta), MAX(weekly_percent) FROM usage_history
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND weekly_delta > 0 AND profile IS ?3
+         GROUP BY day ORDER BY SUM(weekly_delta) DESC, day ASC LIMIT ?4",
+    )?;
+    let rows = stmt.query_map(params![from, to, profiles::active_name(), PEAK_DAYS as i64], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<i32>>(2)?))
+    })?;
+    rows.map(|row| {
+        let (day, added, max) = row?;
+        let reached = max.map(|m| format!(", reaching {}%", m)).unwrap_or_default();
+        Ok(format!("{}: +{}% weekly{}", day_label(&format!("{}T00:00:00", day)), added, reached))
+    })
+    .collect()
+}
+
+fn limit_hit_lines(conn: &Connection, from: &str, to: &str) -> Vec<String> {
+    // Oldest first, unlike the query
+    limits::query_limit_events(conn, from)
+        .iter()
+        .rev()
+        .filter(|e| e.timestamp.as_str() < to)
+        .map(|e| {
+            format!(
+                "{} hit 100% {}, {} before reset",
+                e.metric,
+                time_label(&e.timestamp),
+                format_hours_minutes(chrono::Duration::minutes(e.minutes_before_reset))
+            )
+        })
+        .collect()
+}
+
+// Weekly usage minus the elapsed share of its window, averaged over readings
+fn average_pace(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<Option<f64>> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, weekly_percent, weekly_period FROM usage_history
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND weekly_percent IS NOT NULL
+           AND weekly_period IS NOT NULL AND profile IS ?3",
+    )?;
+    let window_minutes = (period::weekly_hours() * 60) as f64;
+    let diffs: Vec<f64> = stmt
+        .query_map(params![from, to, profiles::active_name()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?))
+        })?
+        .filter_map(|row| {
+            let (timestamp, percent, period) = row.ok()?;
+            let at = parse_reading_timestamp(&timestamp)?;
+            let start = parse_reading_timestamp(&format!("{}:00", period))?;
+            let elapsed = at.signed_duration_since(start).num_minutes() as f64 / window_minutes * 100.0;
+            Some(percent as f64 - elapsed.clamp(0.0, 100.0))
+        })
+        .collect();
+    Ok((!diffs.is_empty()).then(|| diffs.iter().sum::<f64>() / diffs.len() as f64))
+}
+
+fn pace_lines(pace: Option<f64>) -> Vec<String> {
+    let Some(pace) = pace else {
+        return Vec::new();
+    };
+    let pace = pace.round() as i64;
+    vec![match pace {
+        0 => "Weekly usage ran on pace".to_string(),
+        p if p > 0 => format!("Weekly usage ran {} points ahead of pace on average", p),
+        p => format!("Weekly usage ran {} points behind pace on average", -p),
+    }]
+}
+
+fn token_lines(entries: &[transcripts::TranscriptEntry]) -> Vec<String> {
+    let mut by_model: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for entry in entries {
+        let totals = by_model.entry(entry.model.as_deref().unwrap_or("unknown")).or_default();
+        totals.0 += entry.input_tokens + entry.cache_creation_tokens + entry.cache_read_tokens;
+        totals.1 += entry.output_tokens;
+    }
+    let mut lines: Vec<String> = by_model
+        .into_iter()
+        .map(|(model, (input, output))| format!("{}: {} input, {} output tokens", model, tokens(input), tokens(output)))
+        .collect();
+    let cost = cost::breakdown(entries).total_cost;
+    if cost > 0.0 {
+        lines.push(format!("Estimated cost at API prices: ${:.2}", cost));
+    }
+    lines
+}
+
+pub(crate) fn build(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    entries: &[transcripts::TranscriptEntry],
+) -> rusqlite::Result<Report> {
+    let last_day = parse_reading_timestamp(to)
+        .map(|t| (t - chrono::Duration::seconds(1)).format("%Y-%m-%dT%H:%M:%S").to_string())
+        .unwrap_or_else(|| to.to_string());
+    let section = |heading: &str, lines: Vec<String>| Section {
+        heading: heading.to_string(),
+        lines,
+    };
+    let sections = vec![
+        section("Summary", summary_lines(conn, from, to)?),
+        section("Peak days", peak_day_lines(conn, from, to)?),
+        section("Limit hits", limit_hit_lines(conn, from, to)),
+        section("Pace", pace_lines(average_pace(conn, from, to)?)),
+        section("Tokens", token_lines(entries)),
+    ];
+    Ok(Report {
+        title: format!("Claude usage: {} – {}", day_label(from), day_label(&last_day)),
+        sections: sections.into_iter().filter(|s| !s.lines.is_empty()).collect(),
+    })
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub(crate) fn render(report: &Report, format: ReportFormat) -> String {
+    let mut out = String::new();
+    match format {
+        ReportFormat::Markdown => {
+            out.push_str(&format!("# {}\n", report.title));
+            for section in &report.sections {
+                out.push_str(&format!("\n## {}\n\n", section.heading));
+                for line in &section.lines {
+                    out.push_str(&format!("- {}\n", line));
+                }
+            }
+        }
+        ReportFormat::Html => {
+            let title = html_escape(&report.title);
+            out.push_str(&format!(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n",
+                title, title
+            ));
+            for section in &report.sections {
+                out.push_str(&format!("<h2>{}</h2>\n<ul>\n", html_escape(&section.heading)));
+                for line in &section.lines {
+                    out.push_str(&format!("<li>{}</li>\n", html_escape(line)));
+                }
+                out.push_str("</ul>\n");
+            }
+            out.push_str("</body>\n</html>\n");
+        }
+    }
+    out
+}
+
+fn bounds(range: &ReportRange) -> Result<(String, String), String> {
+    let to = match range.to.as_deref() {
+        Some(to) => stats::parse_bound(to, true)?,
+        None => chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+    };
+    let from = match range.from.as_deref() {
+        Some(from) => stats::parse_bound(from, false)?,
+        None => parse_reading_timestamp(&to)
+            .map(|t| (t - chrono::Duration::days(DEFAULT_DAYS)).format("%Y-%m-%dT%H:%M:%S").to_string())
+            .unwrap_or_default(),
+    };
+    Ok((from, to))
+}
+
+fn generate(range: &ReportRange, format: ReportFormat) -> Result<String, String> {
+    let (from, to) = bounds(range)?;
+    let entries = match (parse_reading_timestamp(&from), parse_reading_timestamp(&to)) {
+        (Some(start), Some(end)) => transcripts::read_entries(start, end),
+        _ => Vec::new(),
+    };
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let report = build(&conn, &from, &to, &entries).map_err(|e| format!("Failed to query history: {}", e))?;
+    Ok(render(&report, format))
+}
+
+#[tauri::command]
+pub(crate) async fn generate_report(range: Option<ReportRange>, format: Option<ReportFormat>) -> Result<String, String> {
+    let range = range.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || generate(&range, format.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+// The tray's "Export Weekly Report...": last week's report, as HTML if the
+// chosen name ends in .html. Returns the written path, or None if cancelled.
+pub(crate) fn export_with_dialog() -> Result<Option<PathBuf>, String> {
+    let name = format!("cc-usage-report-{}.md", chrono::Local::now().format("%Y-%m-%d"));
+    let Some(path) = choose_save_path(text(Msg::ExportReportPrompt), &name) else {
+        return Ok(None);
+    };
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some("html" | "htm") => ReportFormat::Html,
+        _ => ReportFormat::Markdown,
+    };
+    let content = generate(&ReportRange::default(), format)?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_peak_days_limit_hits_and_pace() {
+        let db_path = std::env::temp_dir().join(format!("test_db_report_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        let rows = [
+            ("2026-01-26T10:00:00", 60, 10, 10),
+            ("2026-01-27T10:00:00", 100, 40, 30),
+            ("2026-01-28T10:00:00", 50, 45, 5),
+        ];
+        for (timestamp, session, weekly, delta) in rows {
+            conn.execute(
+                "INSERT INTO usage_history (timestamp, session_percent, weekly_percent, weekly_period, weekly_delta)
+                 VALUES (?1, ?2, ?3, '2026-01-26T09:00', ?4)",
+                params![timestamp, session, weekly, delta],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO limit_events (metric, timestamp, period, reset_at, minutes_into_window, minutes_before_reset)
+             VALUES ('session', '2026-01-27T10:00:00', '2026-01-27T07:00', '2026-01-27T12:00:00', 180, 120)",
+            [],
+        )
+        .unwrap();
+
+        let report = build(&conn, "2026-01-22T00:00:00", "2026-01-29T00:00:00", &[]).unwrap();
+        assert_eq!(report.title, "Claude usage: Thu Jan 22 – Wed Jan 28");
+        let markdown = render(&report, ReportFormat::Markdown);
+        assert!(markdown.contains("## Peak days\n\n- Tue Jan 27: +30% weekly, reaching 40%\n- Mon Jan 26: +10% weekly"));
+        assert!(markdown.contains("- session hit 100% Tue Jan 27"));
+        assert!(markdown.contains("2h 0m before reset"));
+        // 10% at 1h in, 40% at 25h, 45% at 49h of 168: ~17 points ahead
+        assert!(markdown.contains("ran 17 points ahead of pace"));
+        assert!(!markdown.contains("## Tokens"));
+        let html = render(&report, ReportFormat::Html);
+        assert!(html.contains("<h2>Limit hits</h2>"));
+        assert_eq!(tokens(2_500_000), "2.5M");
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
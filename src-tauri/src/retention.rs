@@ -0,0 +1,268 @@
+// Deleting history: everything, only readings before a date, or a range
+// picked in the charts window
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+use crate::locale::{text, tr, Msg};
+use crate::{confirm, ensure_not_guest, init_db, notify, profiles, prompt_text, rollups, stats, velocity, AppState};
+
+// Start of the given day; readings from that day on are kept
+fn parse_before_date(input: &str) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d")
+        .map(|d| format!("{}T00:00:00", d.format("%Y-%m-%d")))
+        .map_err(|_| format!("Invalid date: {} (expected YYYY-MM-DD)", input.trim()))
+}
+
+// The active profile's readings, all of them or those before `before`
+fn count_readings(conn: &Connection, before: Option<&str>) -> Result<i64, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM usage_history WHERE (?1 IS NULL OR timestamp < ?1) AND profile IS ?2",
+        params![before, profiles::active_name()],
+        |row| row.get(0),
+    )
+}
+
+// Clears the active profile's readings. Limit events and rollups are derived from
+// readings, so they follow them; events carry no profile, so only those recorded at
+// one of the removed readings go. Returns the number of readings deleted.
+pub(crate) fn clear_readings(conn: &mut Connection, before: Option<&str>) -> Result<usize, rusqlite::Error> {
+    let profile = profiles::active_name();
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM limit_events
+         WHERE timestamp IN (
+             SELECT substr(timestamp, 1, 19) FROM usage_history
+             WHERE (?1 IS NULL OR timestamp < ?1) AND profile IS ?2
+         )",
+        params![before, profile],
+    )?;
+    let deleted = tx.execute(
+        "DELETE FROM usage_history WHERE (?1 IS NULL OR timestamp < ?1) AND profile IS ?2",
+        params![before, profile],
+    )?;
+    rollups::rebuild(&tx)?;
+    tx.commit()?;
+    // Give the space back to the filesystem
+    conn.execute_batch("VACUUM")?;
+    Ok(deleted)
+}
+
+fn count_range(conn: &Connection, from: &str, to: &str) -> Result<i64, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM usage_history WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3",
+        params![from, to, profiles::active_name()],
+        |row| row.get(0),
+    )
+}
+
+// The active profile's readings from `from` up to `to`, with the limit events
+// recorded at them. The first reading after the range gets its delta against the
+// last one before it. Returns the number of readings deleted.
+pub(crate) fn delete_range(conn: &mut Connection, from: &str, to: &str) -> Result<usize, rusqlite::Error> {
+    let profile = profiles::active_name();
+    let tx = conn.transaction()?;
+    // Events carry no profile, so only those recorded at one of the removed
+    // readings go (events are stamped to the second, readings may not be)
+    tx.execute(
+        "DELETE FROM limit_events
+         WHERE timestamp >= ?1 AND timestamp < ?2
+           AND timestamp IN (
+               SELECT substr(timestamp, 1, 19) FROM usage_history
+               WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3
+           )",
+        params![from, to, profile],
+    )?;
+    let deleted = tx.execute(
+        "DELETE FROM usage_history WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3",
+        params![from, to, profile],
+    )?;
+    let next: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM usage_history WHERE timestamp >= ?1 AND profile IS ?2 ORDER BY timestamp ASC LIMIT 1",
+            params![to, profile],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = next {
+        velocity::update_around(&tx, id)?;
+    }
+    rollups::rebuild(&tx)?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct RangeDeletion {
+    readings: i64,
+    deleted: bool,
+}
+
+// Two steps, so the window can confirm: without `confirmed` this only counts
+// the readings in the range; with it they're deleted. `from` / `to` take
+// dates or timestamps like `get_stats`, with a bare `to` date inclusive.
+#[tauri::command]
+pub(crate) fn delete_history_range(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    from: String,
+    to: String,
+    confirmed: Option<bool>,
+) -> Result<RangeDeletion, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let (from, to) = (stats::parse_bound(&from, false)?, stats::parse_bound(&to, true)?);
+    if from >= to {
+        return Err("The range must end after it starts".to_string());
+    }
+    let mut conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    if !confirmed.unwrap_or(false) {
+        let readings = count_range(&conn, &from, &to).map_err(|e| format!("Failed to count readings: {}", e))?;
+        return Ok(RangeDeletion { readings, deleted: false });
+    }
+    let readings = delete_range(&mut conn, &from, &to).map_err(|e| format!("Failed to delete readings: {}", e))?;
+    Ok(RangeDeletion {
+        readings: readings as i64,
+        deleted: true,
+    })
+}
+
+// `before_date` is YYYY-MM-DD; omitted clears all history
+#[tauri::command]
+pub(crate) fn clear_history(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    before_date: Option<String>,
+) -> Result<usize, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    let before = before_date.as_deref().map(parse_before_date).transpose()?;
+    let mut conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    clear_readings(&mut conn, before.as_deref()).map_err(|e| format!("Failed to clear history: {}", e))
+}
+
+// Tray menu "Clear History...": ask for an optional date, confirm with the
+// number of readings affected, then delete
+pub(crate) fn clear_with_dialog() -> Result<(), String> {
+    let Some(input) = prompt_text(text(Msg::ClearHistoryPrompt), "") else {
+        return Ok(());
+    };
+    let before = Some(input.trim()).filter(|i| !i.is_empty()).map(parse_before_date).transpose()?;
+    let mut conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let count = count_readings(&conn, before.as_deref()).map_err(|e| e.to_string())?;
+    if count == 0 {
+        notify::send_notification(text(Msg::NothingToClearTitle), text(Msg::NothingToClearBody));
+        return Ok(());
+    }
+    let scope = match before {
+        Some(_) => tr(Msg::ClearBefore, &[&input.trim()]),
+        None => text(Msg::ClearAll).to_string(),
+    };
+    let question = tr(Msg::ClearHistoryQuestion, &[&count, &scope]);
+    if !confirm(&question, text(Msg::ClearHistoryButton)) {
+        return Ok(());
+    }
+    let deleted = clear_readings(&mut conn, before.as_deref()).map_err(|e| e.to_string())?;
+    notify::send_notification(text(Msg::HistoryClearedTitle), &tr(Msg::HistoryClearedBody, &[&deleted]));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_clear_before_date_keeps_newer_readings() {
+        let db_path = std::env::temp_dir().join(format!("test_db_clear_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        for ts in ["2026-01-01T10:00:00", "2026-01-31T23:50:00", "2026-02-01T00:00:00"] {
+            conn.execute("INSERT INTO usage_history (timestamp, session_percent) VALUES (?1, 10)", params![ts])
+                .unwrap();
+        }
+
+        let before = parse_before_date("2026-02-01").unwrap();
+        assert_eq!(count_readings(&conn, Some(&before)).unwrap(), 2);
+        assert_eq!(clear_readings(&mut conn, Some(&before)).unwrap(), 2);
+        assert_eq!(count_readings(&conn, None).unwrap(), 1);
+
+        // Another profile's readings are left alone
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent, profile) VALUES ('2026-01-01T10:00:00', 10, 'work')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(clear_readings(&mut conn, None).unwrap(), 1);
+        assert_eq!(count_readings(&conn, None).unwrap(), 0);
+        let total: i64 = conn.query_row("SELECT COUNT(*) FROM usage_history", [], |row| row.get(0)).unwrap();
+        assert_eq!(total, 1);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_delete_range_recomputes_the_next_delta() {
+        let db_path = std::env::temp_dir().join(format!("test_db_delete_range_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        for (ts, session) in [
+            ("2026-01-28T09:00:00", 10),
+            ("2026-01-28T10:00:00", 80),
+            ("2026-01-28T11:00:00", 90),
+            ("2026-01-28T12:00:00", 20),
+        ] {
+            conn.execute(
+                "INSERT INTO usage_history (timestamp, session_percent) VALUES (?1, ?2)",
+                params![ts, session],
+            )
+            .unwrap();
+            let id = conn.last_insert_rowid();
+            velocity::update_around(&conn, id).unwrap();
+        }
+
+        // Limits hit at 10:00 in this profile and at 10:30 in another
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent, profile) VALUES (?1, 100, 'work')",
+            params!["2026-01-28T10:30:00"],
+        )
+        .unwrap();
+        for ts in ["2026-01-28T10:00:00", "2026-01-28T10:30:00"] {
+            conn.execute(
+                "INSERT INTO limit_events
+                    (metric, timestamp, period, reset_at, minutes_into_window, minutes_before_reset)
+                 VALUES ('session', ?1, ?1, '2026-01-28T14:00', 60, 240)",
+                params![ts],
+            )
+            .unwrap();
+        }
+
+        let (from, to) = ("2026-01-28T10:00:00", "2026-01-28T12:00:00");
+        assert_eq!(count_range(&conn, from, to).unwrap(), 2);
+        assert_eq!(delete_range(&mut conn, from, to).unwrap(), 2);
+        assert_eq!(count_readings(&conn, None).unwrap(), 3);
+        let events: Vec<String> = conn
+            .prepare("SELECT timestamp FROM limit_events")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, vec!["2026-01-28T10:30:00".to_string()]);
+        // 12:00 was a reset after 90%; against 09:00's 10% it's +10
+        let delta: Option<i32> = conn
+            .query_row(
+                "SELECT session_delta FROM usage_history WHERE timestamp = '2026-01-28T12:00:00'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(delta, Some(10));
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_parse_before_date_rejects_garbage() {
+        assert!(parse_before_date("last month").is_err());
+        assert_eq!(parse_before_date(" 2026-03-01 ").unwrap(), "2026-03-01T00:00:00");
+    }
+}
@@ -0,0 +1,274 @@
+// Daily and weekly rollups (min/max/avg per metric), kept up to date on insert
+// so long-range charts read one row per day instead of every 10-minute reading.
+// Each profile has its own rows, keyed '' for the default profile since a NULL
+// in the key wouldn't be unique.
+use rusqlite::{params, Connection};
+
+use crate::{profiles, Aggregation, UsageHistoryRow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rollup {
+    Daily,
+    Weekly,
+}
+
+const ROLLUPS: [Rollup; 2] = [Rollup::Daily, Rollup::Weekly];
+const METRICS: [&str; 4] = ["session", "weekly", "sonnet", "opus"];
+
+impl Rollup {
+    fn table(self) -> &'static str {
+        match self {
+            Rollup::Daily => "usage_daily",
+            Rollup::Weekly => "usage_weekly",
+        }
+    }
+
+    // Period key for a reading's timestamp column: the day, or the Monday
+    // starting its week
+    fn key_sql(self) -> &'static str {
+        match self {
+            Rollup::Daily => "substr(timestamp, 1, 10)",
+            Rollup::Weekly => "date(substr(timestamp, 1, 10), 'weekday 0', '-6 days')",
+        }
+    }
+
+    // [start, end) of the period containing `day`
+    fn bounds(self, day: chrono::NaiveDate) -> (chrono::NaiveDate, chrono::NaiveDate) {
+        use chrono::Datelike;
+        match self {
+            Rollup::Daily => (day, day + chrono::Duration::days(1)),
+            Rollup::Weekly => {
+                let start = day - chrono::Duration::days(day.weekday().num_days_from_monday() as i64);
+                (start, start + chrono::Duration::days(7))
+            }
+        }
+    }
+}
+
+pub(crate) fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
+    for rollup in ROLLUPS {
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                period TEXT PRIMARY KEY,
+                first_reading TEXT NOT NULL,
+                readings INTEGER NOT NULL,
+                session_min INTEGER, session_max INTEGER, session_avg REAL,
+                weekly_min INTEGER, weekly_max INTEGER, weekly_avg REAL,
+                sonnet_min INTEGER, sonnet_max INTEGER, sonnet_avg REAL
+            )",
+            rollup.table()
+        ))?;
+    }
+    Ok(())
+}
+
+// Recreate the tables keyed by period and profile (migration 10). They're
+// derived data, so they're dropped rather than copied; rebuild() fills them.
+pub(crate) fn key_by_profile(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let columns: Vec<String> = METRICS
+        .iter()
+        .map(|m| format!("{m}_min INTEGER, {m}_max INTEGER, {m}_avg REAL", m = m))
+        .collect();
+    for rollup in ROLLUPS {
+        conn.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {table};
+             CREATE TABLE {table} (
+                period TEXT NOT NULL,
+                profile TEXT NOT NULL,
+                first_reading TEXT NOT NULL,
+                readings INTEGER NOT NULL,
+                {columns},
+                PRIMARY KEY (period, profile)
+             );",
+            table = rollup.table(),
+            columns = columns.join(", "),
+        ))?;
+    }
+    Ok(())
+}
+
+// For metrics added after the tables were created
+pub(crate) fn add_metric_columns(conn: &Connection, metric: &str) -> Result<(), rusqlite::Error> {
+    for rollup in ROLLUPS {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {m}_min INTEGER;
+             ALTER TABLE {table} ADD COLUMN {m}_max INTEGER;
+             ALTER TABLE {table} ADD COLUMN {m}_avg REAL;",
+            table = rollup.table(),
+            m = metric
+        ))?;
+    }
+    Ok(())
+}
+
+fn insert_select(rollup: Rollup, filter: &str) -> String {
+    let columns: Vec<String> = METRICS
+        .iter()
+        .map(|m| format!("{m}_min, {m}_max, {m}_avg", m = m))
+        .collect();
+    let aggregates: Vec<String> = METRICS
+        .iter()
+        .map(|m| format!("MIN({m}_percent), MAX({m}_percent), AVG({m}_percent)", m = m))
+        .collect();
+    format!(
+        "INSERT OR REPLACE INTO {table}
+            (period, profile, first_reading, readings, {columns})
+         SELECT {key} AS period, IFNULL(profile, '') AS profile_key, MIN(timestamp), COUNT(*), {aggregates}
+         FROM usage_history
+         {filter}
+         GROUP BY period, profile_key",
+        table = rollup.table(),
+        key = rollup.key_sql(),
+        columns = columns.join(", "),
+        aggregates = aggregates.join(", "),
+        filter = filter,
+    )
+}
+
+// Recompute the day and week containing a reading, for its profile
+pub(crate) fn refresh_for(conn: &Connection, timestamp: &str, profile: Option<&str>) -> Result<(), rusqlite::Error> {
+    let Some(day) = timestamp
+        .get(..10)
+        .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    else {
+        return Ok(());
+    };
+    for rollup in ROLLUPS {
+        let (start, end) = rollup.bounds(day);
+        let (start, end) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+        conn.execute(
+            &format!("DELETE FROM {} WHERE period = ?1 AND profile = IFNULL(?2, '')", rollup.table()),
+            params![start, profile],
+        )?;
+        conn.execute(
+            &insert_select(rollup, "WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3"),
+            params![start, end, profile],
+        )?;
+    }
+    Ok(())
+}
+
+// From scratch, after bulk changes like clearing history
+pub(crate) fn rebuild(conn: &Connection) -> Result<(), rusqlite::Error> {
+    for rollup in ROLLUPS {
+        conn.execute(&format!("DELETE FROM {}", rollup.table()), [])?;
+        conn.execute(&insert_select(rollup, ""), [])?;
+    }
+    Ok(())
+}
+
+// The active profile's rollup rows since `cutoff`, labeled with each period's
+// first reading like the bucketed history query. None for aggregations the
+// rollups don't store.
+pub(crate) fn query(
+    conn: &Connection,
+    rollup: Rollup,
+    cutoff: &str,
+    aggregation: Aggregation,
+) -> Option<Vec<UsageHistoryRow>> {
+    let column = |metric: &str| match aggregation {
+        Aggregation::Min => Some(format!("{}_min", metric)),
+        Aggregation::Max => Some(format!("{}_max", metric)),
+        Aggregation::Avg => Some(format!("CAST(ROUND({}_avg) AS INTEGER)", metric)),
+        Aggregation::Latest => None,
+    };
+    let sql = format!(
+        "SELECT first_reading, {}, {}, {}, {} FROM {}
+         WHERE first_reading >= ?1 AND profile = IFNULL(?2, '')
+         ORDER BY period ASC",
+        column("session")?,
+        column("weekly")?,
+        column("sonnet")?,
+        column("opus")?,
+        rollup.table()
+    );
+    let rows = conn.prepare(&sql).and_then(|mut stmt| {
+        stmt.query_map(params![cutoff, profiles::active_name()], |row| {
+            Ok(UsageHistoryRow {
+                timestamp: row.get(0)?,
+                session_percent: row.get(1)?,
+                weekly_percent: row.get(2)?,
+                sonnet_percent: row.get(3)?,
+                opus_percent: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+    });
+    Some(rows.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn insert(conn: &Connection, timestamp: &str, session: i32, profile: Option<&str>) {
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent, profile) VALUES (?1, ?2, ?3)",
+            params![timestamp, session, profile],
+        )
+        .unwrap();
+        refresh_for(conn, timestamp, profile).unwrap();
+    }
+
+    #[test]
+    fn test_rollups_follow_inserts() {
+        let db_path = std::env::temp_dir().join(format!("test_db_rollups_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        // Wednesday, Wednesday, Thursday of the same week; then the next Monday
+        insert(&conn, "2026-01-28T09:00:00", 10, None);
+        insert(&conn, "2026-01-28T12:00:00", 30, None);
+        insert(&conn, "2026-01-29T08:00:00", 50, None);
+        insert(&conn, "2026-02-02T08:00:00", 5, None);
+        // Another profile's reading gets rows of its own
+        insert(&conn, "2026-01-28T10:00:00", 90, Some("work"));
+
+        let daily = query(&conn, Rollup::Daily, "2026-01-01T00:00:00", Aggregation::Max).unwrap();
+        assert_eq!(daily.len(), 3);
+        assert_eq!(daily[0].timestamp, "2026-01-28T09:00:00");
+        assert_eq!(daily[0].session_percent, Some(30));
+
+        let weekly = query(&conn, Rollup::Weekly, "2026-01-01T00:00:00", Aggregation::Avg).unwrap();
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].session_percent, Some(30));
+        assert_eq!(weekly[1].session_percent, Some(5));
+
+        // A rebuild from scratch agrees with the incremental rows
+        rebuild(&conn).unwrap();
+        assert_eq!(query(&conn, Rollup::Weekly, "2026-01-01T00:00:00", Aggregation::Avg).unwrap().len(), 2);
+        assert!(query(&conn, Rollup::Daily, "2026-01-01T00:00:00", Aggregation::Latest).is_none());
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_rollups_include_opus() {
+        let db_path = std::env::temp_dir().join(format!("test_db_rollups_opus_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        for (timestamp, opus) in [("2026-01-28T09:00:00", 12), ("2026-01-28T12:00:00", 18)] {
+            conn.execute(
+                "INSERT INTO usage_history (timestamp, session_percent, opus_percent) VALUES (?1, 5, ?2)",
+                params![timestamp, opus],
+            )
+            .unwrap();
+            refresh_for(&conn, timestamp, None).unwrap();
+        }
+        let daily = query(&conn, Rollup::Daily, "2026-01-01T00:00:00", Aggregation::Max).unwrap();
+        assert_eq!(daily[0].opus_percent, Some(18));
+        assert_eq!(daily[0].sonnet_percent, None);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_week_starts_on_monday() {
+        let sunday = chrono::NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let (start, end) = Rollup::Weekly.bounds(sunday);
+        assert_eq!(start.to_string(), "2026-01-26");
+        assert_eq!(end.to_string(), "2026-02-02");
+    }
+}
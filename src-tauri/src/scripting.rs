@@ -0,0 +1,90 @@
+// Scripting for osascript, Keyboard Maestro, Hammerspoon, and the like,
+// through the command line: `cc-usage get [metric]` prints the latest
+// percentages and `cc-usage refresh` makes the running app fetch now. Tauri
+// apps can't declare an AppleScript dictionary, so osascript goes through
+// `do shell script`. `refresh` reaches the running app by way of the
+// single-instance plugin, which hands a second launch's arguments to the
+// first instance; with no app running, it starts the app, which fetches at
+// launch anyway.
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Map, Value};
+use tauri::{Manager, Runtime};
+
+use crate::{
+    load_cached_usage, parse_reading_timestamp, resolve_reset, spawn_triggered_refresh, AppState, MenuMetric, UsageData,
+};
+
+pub(crate) const REFRESH_ARG: &str = "refresh";
+
+// `{"session": 42, "weekly": 67, ..., "session_resets_at": "...",
+// "timestamp": "..."}`, or one metric's percentage alone. Resets are ISO 8601
+// with the offset, which Shortcuts reads as dates. Err for an unknown metric.
+pub(crate) fn get_output(usage: &UsageData, metric: Option<&str>) -> Result<Option<String>, String> {
+    match metric {
+        Some(name) => {
+            let metric = MenuMetric::parse(name).ok_or_else(|| format!("Unknown metric: {}", name))?;
+            Ok(metric.item(usage).percent.map(|p| p.to_string()))
+        }
+        None => {
+            let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp);
+            let mut out = Map::new();
+            for metric in MenuMetric::ALL {
+                let item = metric.item(usage);
+                out.insert(metric.as_str().to_string(), json!(item.percent));
+                let reset_at = read_at.and_then(|at| resolve_reset(item.resets.as_deref(), at));
+                out.insert(format!("{}_resets_at", metric.as_str()), json!(reset_at.map(|at| at.to_rfc3339())));
+            }
+            out.insert("timestamp".to_string(), json!(usage.timestamp));
+            Ok(Some(Value::Object(out).to_string()))
+        }
+    }
+}
+
+// Exit code 1 when the metric has no reading, 2 for a bad metric name
+pub(crate) fn get(metric: Option<&str>) -> i32 {
+    match get_output(&load_cached_usage().unwrap_or_default(), metric) {
+        Ok(Some(out)) => {
+            println!("{}", out);
+            0
+        }
+        Ok(None) => 1,
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+// From the single-instance plugin, with the second launch's arguments
+// (program name first)
+pub(crate) fn handle_forwarded<R: Runtime>(app: &tauri::AppHandle<R>, args: &[String]) {
+    if args.get(1).map(String::as_str) == Some(REFRESH_ARG) {
+        let state = app.state::<Arc<Mutex<AppState>>>().inner().clone();
+        spawn_triggered_refresh(app.clone(), state, "script");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_output() {
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T10:40:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        usage.session.resets = Some("4pm".to_string());
+        assert_eq!(get_output(&usage, Some("session")), Ok(Some("42".to_string())));
+        assert_eq!(get_output(&usage, Some("opus")), Ok(None));
+        assert!(get_output(&usage, Some("monthly")).is_err());
+        let all: Value = serde_json::from_str(&get_output(&usage, None).unwrap().unwrap()).unwrap();
+        assert_eq!(all["session"], 42);
+        assert_eq!(all["weekly"], Value::Null);
+        assert!(all["session_resets_at"].as_str().unwrap().starts_with("2026-01-28T16:00:00"));
+        assert_eq!(all["weekly_resets_at"], Value::Null);
+        assert_eq!(all["timestamp"], "2026-01-28T10:40:00");
+    }
+}
@@ -0,0 +1,300 @@
+// Local HTTP server for scrapers and scripts, off unless `server_port` is set.
+// It listens on 127.0.0.1 only and answers requests one at a time:
+// `/metrics` in Prometheus text format (metrics.rs), `/resets.ics`
+// (calendar.rs), `/streamdeck` and `POST /refresh` (streamdeck.rs), and
+// upgrades `/ws` to a WebSocket that's pushed every reading (ws.rs). Started
+// at launch and restarted on save when the port changes; `cc-usage --daemon`
+// serves it without the app (daemon.rs). No HTTP crate: requests are small
+// enough to read by hand. Requests whose Host isn't this machine are refused,
+// so a page can't reach the server through DNS rebinding.
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::{Manager, Runtime};
+
+use crate::{
+    append_log, calendar, load_settings, metrics, period, spawn_triggered_refresh, streamdeck, ws, AppState,
+    MenuMetric, Settings,
+};
+
+pub(crate) const MIN_PORT: u16 = 1024;
+// Headers past this are cut off; no route needs them
+const MAX_REQUEST_BYTES: usize = 8192;
+const READ_TIMEOUT_SECS: u64 = 5;
+
+// The port being served and the flag that stops it
+static RUNNING: Mutex<Option<(u16, Arc<AtomicBool>)>> = Mutex::new(None);
+
+// What the routes read and start: the app's state or the daemon's
+#[derive(Clone)]
+pub(crate) struct Source {
+    pub(crate) state: Arc<Mutex<AppState>>,
+    // Starts a fetch in the background, logged under the trigger
+    pub(crate) refresh: Arc<dyn Fn(&'static str) + Send + Sync>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", body),
+        }
+    }
+}
+
+// (method, path, query string) from "GET /metrics?x=1 HTTP/1.1"
+fn parse_request_line(line: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    parts.next()?.starts_with("HTTP/").then_some(())?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Some((method, path, query))
+}
+
+// A header's value from the request head, matching the name in any case
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+// True for a Host naming this machine, with or without the port. HTTP/1.0
+// clients may leave it out.
+fn is_local_host(host: Option<&str>) -> bool {
+    let Some(host) = host else {
+        return true;
+    };
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    matches!(name.to_ascii_lowercase().as_str(), "127.0.0.1" | "localhost")
+}
+
+// POST /refresh starts a claude session, so it needs the `server_token`
+// setting as a bearer token, and anything from a browser page (which always
+// sends Origin on a POST) is refused
+fn check_refresh(head: &str, token: Option<&str>) -> Result<(), Response> {
+    if header(head, "Origin").is_some() {
+        return Err(Response::text("403 Forbidden", "Refreshing from a web page isn't allowed"));
+    }
+    let Some(token) = token else {
+        return Err(Response::text("403 Forbidden", "Set server_token to allow refreshing"));
+    };
+    let sent = header(head, "Authorization").and_then(|value| value.strip_prefix("Bearer "));
+    if sent.map(str::trim) != Some(token) {
+        return Err(Response::text("401 Unauthorized", "Missing or wrong token"));
+    }
+    Ok(())
+}
+
+fn route(source: &Source, head: &str, method: &str, path: &str, query: &str) -> Response {
+    // The Stream Deck key's press
+    if path == "/refresh" {
+        if method != "POST" {
+            return Response::text("405 Method Not Allowed", "Use POST to refresh");
+        }
+        if let Err(response) = check_refresh(head, load_settings().server_token.as_deref()) {
+            return response;
+        }
+        (source.refresh)("streamdeck");
+        return Response::text("202 Accepted", "Refreshing");
+    }
+    if method != "GET" {
+        return Response::text("405 Method Not Allowed", "Only GET is supported");
+    }
+    match path {
+        "/metrics" => {
+            let (usage, consecutive_errors) = {
+                let state = source.state.lock().unwrap();
+                (state.usage.clone(), state.consecutive_errors)
+            };
+            Response {
+                status: "200 OK",
+                content_type: "text/plain; version=0.0.4; charset=utf-8",
+                body: metrics::render(&usage, consecutive_errors, metrics::fetch_counts(), chrono::Local::now()),
+            }
+        }
+        // For calendar apps to subscribe to
+        "/resets.ics" => {
+            let usage = source.state.lock().unwrap().usage.clone();
+            Response {
+                status: "200 OK",
+                content_type: "text/calendar; charset=utf-8",
+                body: calendar::calendar(&usage, period::weekly_hours(), chrono::Local::now()),
+            }
+        }
+        "/streamdeck" => {
+            let name = streamdeck::metric_param(query).unwrap_or("session");
+            let Some(metric) = MenuMetric::parse(name) else {
+                return Response::text("400 Bad Request", &format!("Unknown metric: {}", name));
+            };
+            let usage = source.state.lock().unwrap().usage.clone();
+            let key = streamdeck::key_state(&usage, metric, chrono::Local::now());
+            Response {
+                status: "200 OK",
+                content_type: "application/json",
+                body: serde_json::to_string(&key).unwrap_or_default(),
+            }
+        }
+        _ => Response::text("404 Not Found", "Not found"),
+    }
+}
+
+fn ws_origin_allowed(head: &str) -> bool {
+    let allowed = load_settings().server_allowed_origins.unwrap_or_default();
+    ws::origin_allowed(header(head, "Origin"), &allowed)
+}
+
+fn read_request_head(stream: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while head.len() < MAX_REQUEST_BYTES && !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    String::from_utf8_lossy(&head).into_owned()
+}
+
+fn handle(source: &Source, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(READ_TIMEOUT_SECS)));
+    let head = read_request_head(&mut stream);
+    let response = match parse_request_line(head.lines().next().unwrap_or("")) {
+        Some(_) if !is_local_host(header(&head, "Host")) => Response::text("403 Forbidden", "Unknown host"),
+        Some(("GET", "/ws", _)) => match ws::upgrade_key(&head) {
+            Some(_) if !ws_origin_allowed(&head) => Response::text("403 Forbidden", "Origin not allowed"),
+            Some(key) => {
+                let hello = ws::message(&source.state.lock().unwrap(), "hello");
+                ws::accept(stream, key, &hello);
+                return;
+            }
+            None => Response::text("426 Upgrade Required", "Expected a WebSocket upgrade"),
+        },
+        Some((method, path, query)) => route(source, &head, method, path, query),
+        None => Response::text("400 Bad Request", "Bad request"),
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+}
+
+fn stop(port: u16, flag: &AtomicBool) {
+    flag.store(true, Ordering::SeqCst);
+    ws::disconnect_all();
+    // Wake the blocked accept so the thread sees the flag
+    let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, port));
+}
+
+// At startup and on save: serve `server_port`, or stop serving. A port that
+// can't be bound (already in use) is logged and retried on the next save.
+pub(crate) fn apply<R: Runtime>(app: &tauri::AppHandle<R>, settings: &Settings) {
+    let state = app.state::<Arc<Mutex<AppState>>>().inner().clone();
+    let app = app.clone();
+    let source = Source {
+        state: state.clone(),
+        refresh: Arc::new(move |trigger| spawn_triggered_refresh(app.clone(), state.clone(), trigger)),
+    };
+    serve(settings, source);
+}
+
+// apply for a given state and refresh, for the daemon as well as the app
+pub(crate) fn serve(settings: &Settings, source: Source) {
+    let mut running = RUNNING.lock().unwrap();
+    if running.as_ref().map(|(port, _)| *port) == settings.server_port {
+        return;
+    }
+    if let Some((port, flag)) = running.take() {
+        stop(port, &flag);
+    }
+    let Some(port) = settings.server_port else {
+        return;
+    };
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            append_log(&format!("Local server: couldn't listen on port {}: {}", port, e));
+            return;
+        }
+    };
+    let flag = Arc::new(AtomicBool::new(false));
+    *running = Some((port, flag.clone()));
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if flag.load(Ordering::SeqCst) {
+                break;
+            }
+            if let Ok(stream) = stream {
+                handle(&source, stream);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(parse_request_line("GET /metrics HTTP/1.1"), Some(("GET", "/metrics", "")));
+        assert_eq!(
+            parse_request_line("GET /metrics?name[]=x HTTP/1.0"),
+            Some(("GET", "/metrics", "name[]=x"))
+        );
+        assert_eq!(parse_request_line("GET /metrics"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+
+    #[test]
+    fn test_header_lookup() {
+        let head = "GET / HTTP/1.1\r\nHost: 127.0.0.1:8787\r\norigin: https://example.com\r\n\r\n";
+        assert_eq!(header(head, "host"), Some("127.0.0.1:8787"));
+        assert_eq!(header(head, "Origin"), Some("https://example.com"));
+        assert_eq!(header(head, "Authorization"), None);
+    }
+
+    #[test]
+    fn test_only_local_hosts_are_served() {
+        assert!(is_local_host(Some("127.0.0.1:8787")));
+        assert!(is_local_host(Some("localhost")));
+        assert!(is_local_host(Some("LOCALHOST:8787")));
+        assert!(is_local_host(None));
+        assert!(!is_local_host(Some("attacker.example:8787")));
+        assert!(!is_local_host(Some("127.0.0.1.attacker.example")));
+    }
+
+    #[test]
+    fn test_refresh_needs_the_token_and_no_origin() {
+        let request = |headers: &str| format!("POST /refresh HTTP/1.1\r\nHost: 127.0.0.1\r\n{}\r\n", headers);
+        let ok = request("Authorization: Bearer s3cret\r\n");
+        assert!(check_refresh(&ok, Some("s3cret")).is_ok());
+        assert_eq!(check_refresh(&ok, None).unwrap_err().status, "403 Forbidden");
+        let wrong = request("Authorization: Bearer guess\r\n");
+        assert_eq!(check_refresh(&wrong, Some("s3cret")).unwrap_err().status, "401 Unauthorized");
+        assert_eq!(check_refresh(&request(""), Some("s3cret")).unwrap_err().status, "401 Unauthorized");
+        let from_page = request("Origin: https://example.com\r\nAuthorization: Bearer s3cret\r\n");
+        assert_eq!(check_refresh(&from_page, Some("s3cret")).unwrap_err().status, "403 Forbidden");
+    }
+}
@@ -0,0 +1,315 @@
+// The Settings window ("Settings..." in the tray, or open_settings from the
+// charts window) and the get_settings / set_settings commands behind it.
+// Settings with side effects beyond the file (data_dir, encrypt_database, away
+// mode, guest mode) keep their own commands and menu items, and set_settings
+// leaves them as they are. Every save is broadcast as a `settings-changed`
+// event carrying the saved Settings, so other open windows can follow along.
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tauri::{Emitter, Manager, Runtime, WebviewWindowBuilder};
+
+use crate::locale::{Language, TimeFormat};
+use crate::{config, dailynote, dock, mqtt, notify, pace, period, profiles, quiet, server, telemetry, theme};
+use crate::{
+    ensure_not_guest, load_saved_settings, load_settings, save_settings, update_tray, AppState, MenuMetric, Settings, TrayMetric,
+    REFRESH_INTERVAL_SECS,
+};
+
+pub(crate) const MIN_REFRESH_MINS: u64 = 1;
+pub(crate) const MAX_REFRESH_MINS: u64 = 120;
+
+const ICON_STYLES: [&str; 4] = ["gauge", "battery", "sparkline", "static"];
+const ICON_APPEARANCES: [&str; 2] = ["status", "template"];
+const TRAY_CLICKS: [&str; 2] = ["menu", "popover"];
+
+// Time between automatic refreshes; the `refresh_interval_mins` setting,
+// clamped, or 10 minutes
+pub(crate) fn refresh_interval_secs(settings: &Settings) -> u64 {
+    settings
+        .refresh_interval_mins
+        .map(|mins| mins.clamp(MIN_REFRESH_MINS, MAX_REFRESH_MINS) * 60)
+        .unwrap_or(REFRESH_INTERVAL_SECS)
+}
+
+fn check_one_of(name: &str, value: Option<&str>, allowed: &[&str]) -> Result<(), String> {
+    match value {
+        Some(v) if !allowed.contains(&v) => Err(format!("Unknown {}: {} (expected one of {})", name, v, allowed.join(", "))),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn validate(settings: &Settings) -> Result<(), String> {
+    if let Some(mins) = settings.refresh_interval_mins {
+        if !(MIN_REFRESH_MINS..=MAX_REFRESH_MINS).contains(&mins) {
+            return Err(format!(
+                "Refresh interval must be between {} and {} minutes",
+                MIN_REFRESH_MINS, MAX_REFRESH_MINS
+            ));
+        }
+    }
+    if settings.stale_after_mins.is_some_and(|mins| mins < 1) {
+        return Err("Stale threshold must be at least a minute".to_string());
+    }
+    check_one_of("icon style", settings.icon_style.as_deref(), &ICON_STYLES)?;
+    check_one_of("icon appearance", settings.icon_appearance.as_deref(), &ICON_APPEARANCES)?;
+    check_one_of("tray click action", settings.tray_click.as_deref(), &TRAY_CLICKS)?;
+    let metrics = TrayMetric::ALL.map(TrayMetric::as_str);
+    check_one_of("menu bar metric", settings.tray_metric.as_deref(), &metrics)?;
+    let languages = Language::ALL.map(Language::code);
+    check_one_of("language", settings.language.as_deref(), &languages)?;
+    let dock_icons = dock::DockIcon::ALL.map(dock::DockIcon::as_str);
+    check_one_of("Dock icon", settings.dock_icon.as_deref(), &dock_icons)?;
+    let themes = theme::Theme::ALL.map(theme::Theme::as_str);
+    check_one_of("theme", settings.theme.as_deref(), &themes)?;
+    let pacing_modes = pace::PacingMode::ALL.map(pace::PacingMode::as_str);
+    check_one_of("pacing mode", settings.pacing_mode.as_deref(), &pacing_modes)?;
+    let time_formats = TimeFormat::ALL.map(TimeFormat::as_str);
+    check_one_of("time format", settings.time_format.as_deref(), &time_formats)?;
+    for sound in [&settings.sound_info, &settings.sound_warning, &settings.sound_critical] {
+        check_one_of("sound", sound.as_deref(), &notify::SOUNDS)?;
+    }
+    if let Some(name) = settings.menu_metrics.iter().flatten().find(|name| MenuMetric::parse(name).is_none()) {
+        return Err(format!("Unknown metric: {}", name));
+    }
+    let thresholds = [
+        &settings.session_alert_thresholds,
+        &settings.weekly_alert_thresholds,
+        &settings.sonnet_alert_thresholds,
+        &settings.opus_alert_thresholds,
+    ];
+    if let Some(t) = thresholds.into_iter().flatten().flatten().find(|t| !(1..=100).contains(*t)) {
+        return Err(format!("Alert thresholds must be between 1 and 100 (got {})", t));
+    }
+    pace::check(settings)?;
+    profiles::check(settings)?;
+    if settings.session_period_hours.is_some_and(|h| !(1..=period::MAX_SESSION_HOURS).contains(&h)) {
+        return Err(format!("Session length must be between 1 and {} hours", period::MAX_SESSION_HOURS));
+    }
+    if settings.weekly_period_hours.is_some_and(|h| !(1..=period::MAX_WEEKLY_HOURS).contains(&h)) {
+        return Err(format!("Weekly length must be between 1 and {} hours", period::MAX_WEEKLY_HOURS));
+    }
+    let quiet = [settings.quiet_hours_start.as_deref(), settings.quiet_hours_end.as_deref()];
+    if let Some(value) = quiet.into_iter().flatten().find(|v| quiet::parse_clock(v).is_none()) {
+        return Err(format!("Quiet hours must look like 22:00 (got {})", value));
+    }
+    if quiet.iter().filter(|v| v.is_some()).count() == 1 {
+        return Err("Quiet hours need both a start and an end".to_string());
+    }
+    if settings.escalate_every_mins.is_some_and(|mins| mins < 1) {
+        return Err("Escalation interval must be at least a minute".to_string());
+    }
+    if settings.weekly_early_warning_hours.is_some_and(|hours| hours < 1) {
+        return Err("Early warning must be at least an hour before the reset".to_string());
+    }
+    if let Some(url) = settings.webhook_url.as_deref() {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("Webhook URL must start with http:// or https:// (got {})", url));
+        }
+    }
+    if let Some(url) = settings.slack_webhook_url.as_deref() {
+        if !url.starts_with("https://hooks.slack.com/") {
+            return Err(format!("Slack webhook URL must start with https://hooks.slack.com/ (got {})", url));
+        }
+    }
+    if let Some(at) = settings.slack_daily_summary_at.as_deref() {
+        if quiet::parse_clock(at).is_none() {
+            return Err(format!("Daily summary time must look like 09:00 (got {})", at));
+        }
+    }
+    if let Some(dir) = settings.sync_dir.as_deref() {
+        if !Path::new(dir).is_absolute() {
+            return Err(format!("Sync folder must be an absolute path: {}", dir));
+        }
+    }
+    if let Some(path) = settings.daily_note_path.as_deref() {
+        dailynote::check(path)?;
+    }
+    if let Some(broker) = settings.mqtt_broker.as_deref() {
+        if mqtt::parse_broker(broker).is_none() {
+            return Err(format!("MQTT broker must look like host or host:port (got {})", broker));
+        }
+    }
+    mqtt::check_topic("topic", settings.mqtt_topic.as_deref())?;
+    mqtt::check_topic("discovery prefix", settings.mqtt_discovery_prefix.as_deref())?;
+    if let Some(host) = settings.statsd_host.as_deref() {
+        if telemetry::parse_statsd_host(host).is_none() {
+            return Err(format!("statsd host must look like host or host:port (got {})", host));
+        }
+    }
+    if let Some(url) = settings.otlp_endpoint.as_deref() {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("OTLP endpoint must start with http:// or https:// (got {})", url));
+        }
+    }
+    if let Some(url) = settings.upload_url.as_deref() {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("Upload URL must start with http:// or https:// (got {})", url));
+        }
+    }
+    if settings.upload_access_key_id.is_some() != settings.upload_secret_access_key.is_some() {
+        return Err("Upload access key ID and secret access key go together".to_string());
+    }
+    if settings.server_port.is_some_and(|port| port < server::MIN_PORT) {
+        return Err(format!("Server port must be {} or higher", server::MIN_PORT));
+    }
+    if settings.machine_name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err("Machine name can't be empty".to_string());
+    }
+    Ok(())
+}
+
+// Blank text fields from the form mean "use the default"
+fn normalize(mut settings: Settings) -> Settings {
+    let blank_to_none = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    settings.sync_dir = blank_to_none(settings.sync_dir);
+    settings.machine_name = blank_to_none(settings.machine_name);
+    settings.quiet_hours_start = blank_to_none(settings.quiet_hours_start);
+    settings.quiet_hours_end = blank_to_none(settings.quiet_hours_end);
+    settings.work_hours_start = blank_to_none(settings.work_hours_start);
+    settings.work_hours_end = blank_to_none(settings.work_hours_end);
+    settings.webhook_url = blank_to_none(settings.webhook_url);
+    settings.webhook_template = blank_to_none(settings.webhook_template);
+    settings.slack_webhook_url = blank_to_none(settings.slack_webhook_url);
+    settings.slack_daily_summary_at = blank_to_none(settings.slack_daily_summary_at);
+    settings.server_token = blank_to_none(settings.server_token);
+    settings.mqtt_broker = blank_to_none(settings.mqtt_broker);
+    settings.mqtt_username = blank_to_none(settings.mqtt_username);
+    settings.mqtt_password = blank_to_none(settings.mqtt_password);
+    settings.mqtt_topic = blank_to_none(settings.mqtt_topic);
+    settings.mqtt_discovery_prefix = blank_to_none(settings.mqtt_discovery_prefix);
+    settings.statsd_host = blank_to_none(settings.statsd_host);
+    settings.otlp_endpoint = blank_to_none(settings.otlp_endpoint);
+    settings.upload_url = blank_to_none(settings.upload_url);
+    settings.upload_access_key_id = blank_to_none(settings.upload_access_key_id);
+    settings.upload_secret_access_key = blank_to_none(settings.upload_secret_access_key);
+    settings.upload_region = blank_to_none(settings.upload_region);
+    settings.daily_note_path = blank_to_none(settings.daily_note_path);
+    settings
+}
+
+// Take the editable fields from `update`, everything else from `current`
+pub(crate) fn merge(current: Settings, update: Settings) -> Settings {
+    Settings {
+        guest_mode: current.guest_mode,
+        away_since: current.away_since,
+        away_until: current.away_until,
+        encrypt_database: current.encrypt_database,
+        data_dir: current.data_dir,
+        active_profile: current.active_profile,
+        ..normalize(update)
+    }
+}
+
+pub(crate) const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+// Open or focus the Settings window
+pub(crate) fn open_settings_window<R: Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.set_focus();
+    } else {
+        let _ = WebviewWindowBuilder::new(app, "settings", tauri::WebviewUrl::App("settings.html".into()))
+            .title("Claude Usage Settings")
+            .inner_size(460.0, 620.0)
+            .resizable(true)
+            .build();
+        dock::update(app, None);
+    }
+}
+
+#[tauri::command]
+pub(crate) fn open_settings(app: tauri::AppHandle, state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<(), String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    open_settings_window(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn get_settings(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Result<Settings, String> {
+    ensure_not_guest(&state.lock().unwrap())?;
+    Ok(config::layer(&config::load_config(), &load_saved_settings()))
+}
+
+// Saves and applies right away; a new refresh interval starts with the next
+// refresh. Returns the settings as the window should show them: the config
+// file with the saved settings on top. Values that only repeat the config file
+// aren't written, so later edits to it still apply.
+#[tauri::command]
+pub(crate) fn set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    let mut state = state.lock().unwrap();
+    ensure_not_guest(&state)?;
+    let config = config::load_config();
+    let settings = merge(load_saved_settings(), settings);
+    validate(&settings)?;
+    save_settings(&config::without_config_values(&settings, &config));
+    // Environment overrides still win over what was just saved
+    let shown = config::layer(&config, &settings);
+    let settings = load_settings();
+
+    state.show_percentages = settings.show_percentages.unwrap_or(true);
+    state.tray_metric = TrayMetric::from_setting(settings.tray_metric.as_deref());
+    state.stale_after_mins = settings.stale_after_mins;
+    state.stale_title_marker = settings.stale_title_marker.unwrap_or(false);
+    state.menu_metrics = MenuMetric::from_setting(settings.menu_metrics.as_deref());
+    crate::apply_display_settings(&settings);
+    if profiles::apply(&settings) {
+        profiles::reload(&mut state);
+    }
+    update_tray(&app, &state);
+    dock::update(&app, None);
+    theme::apply(&app, &settings);
+    server::apply(&app, &settings);
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, &shown);
+    Ok(shown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_fields_with_their_own_commands() {
+        let current = Settings {
+            data_dir: Some("/Volumes/Data/cc-usage".to_string()),
+            encrypt_database: Some(true),
+            show_percentages: Some(true),
+            ..Default::default()
+        };
+        let update = Settings {
+            data_dir: None,
+            show_percentages: Some(false),
+            sync_dir: Some("  ".to_string()),
+            refresh_interval_mins: Some(5),
+            ..Default::default()
+        };
+        let merged = merge(current, update);
+        assert_eq!(merged.data_dir.as_deref(), Some("/Volumes/Data/cc-usage"));
+        assert_eq!(merged.encrypt_database, Some(true));
+        assert_eq!(merged.show_percentages, Some(false));
+        assert_eq!(merged.sync_dir, None);
+        assert_eq!(refresh_interval_secs(&merged), 300);
+        assert!(validate(&merged).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_out_of_range_and_unknown_values() {
+        let with = |f: fn(&mut Settings)| {
+            let mut settings = Settings::default();
+            f(&mut settings);
+            validate(&settings)
+        };
+        assert!(with(|s| s.refresh_interval_mins = Some(0)).is_err());
+        assert!(with(|s| s.refresh_interval_mins = Some(MAX_REFRESH_MINS + 1)).is_err());
+        assert!(with(|s| s.icon_style = Some("donut".to_string())).is_err());
+        assert!(with(|s| s.tray_metric = Some("weekly".to_string())).is_ok());
+        assert!(with(|s| s.language = Some("klingon".to_string())).is_err());
+        assert!(with(|s| s.weekly_alert_thresholds = Some(vec![50, 120])).is_err());
+        assert!(with(|s| s.quiet_hours_start = Some("22:00".to_string())).is_err());
+        assert!(with(|s| s.sync_dir = Some("Dropbox/cc-usage".to_string())).is_err());
+        assert_eq!(refresh_interval_secs(&Settings::default()), REFRESH_INTERVAL_SECS);
+    }
+}
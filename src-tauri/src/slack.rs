@@ -0,0 +1,113 @@
+// Slack incoming webhook (`slack_webhook_url`): a short message with each
+// metric's percentage, pace emoji, and reset on threshold crossings, plus an
+// optional daily summary at `slack_daily_summary_at` ("09:00"), sent by the
+// countdown ticker. Posted with curl.rs, like the generic webhook.
+use chrono::{DateTime, Local};
+
+use crate::alerts::Crossing;
+use crate::locale::{text, tr, Msg};
+use crate::{
+    append_log, curl, format_time_remaining, get_status_indicator_paced, quiet, AppState, MenuMetric, Settings,
+    UsageData,
+};
+
+// "🟠 Session: 87% · 1h left", one line per metric with a reading
+fn metric_lines(usage: &UsageData) -> Vec<String> {
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let item = metric.item(usage);
+            let percent = item.percent?;
+            let indicator = get_status_indicator_paced(percent, item.resets.as_deref(), metric);
+            let line = format!("{} {}: {}%", indicator, metric.label(), percent);
+            Some(match item.resets.as_deref() {
+                Some(resets) => format!("{} · {}", line, format_time_remaining(resets)),
+                None => line,
+            })
+        })
+        .collect()
+}
+
+// Bold heading, then the metric lines
+pub(crate) fn message(heading: &str, usage: &UsageData) -> String {
+    let mut lines = vec![format!("*{}*", heading)];
+    lines.extend(metric_lines(usage));
+    lines.join("\n")
+}
+
+fn post(url: String, text: String) {
+    let body = serde_json::json!({ "text": text }).to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = curl::post_json(&url, &body) {
+            append_log(&format!("Slack post failed: {}", e));
+        }
+    });
+}
+
+pub(crate) fn send_crossing(settings: &Settings, crossing: &Crossing, usage: &UsageData) {
+    let Some(url) = settings.slack_webhook_url.clone() else {
+        return;
+    };
+    let heading = tr(Msg::ThresholdTitle, &[&crossing.metric.label(), &crossing.threshold]);
+    post(url, message(&heading, usage));
+}
+
+// Summary is due once `slack_daily_summary_at` has passed today and it hasn't
+// gone out yet (so a Mac waking at 9:40 still sends the 9:00 one)
+pub(crate) fn summary_due(settings: &Settings, sent_on: Option<chrono::NaiveDate>, now: DateTime<Local>) -> bool {
+    let Some(at) = settings.slack_daily_summary_at.as_deref().and_then(quiet::parse_clock) else {
+        return false;
+    };
+    settings.slack_webhook_url.is_some() && now.time() >= at && sent_on != Some(now.date_naive())
+}
+
+// Called every minute by the countdown ticker
+pub(crate) fn send_daily_summary_if_due(state: &mut AppState, settings: &Settings, now: DateTime<Local>) {
+    if !summary_due(settings, state.slack_summary_sent_on, now) || state.usage.session.percent.is_none() {
+        return;
+    }
+    state.slack_summary_sent_on = Some(now.date_naive());
+    if let Some(url) = settings.slack_webhook_url.clone() {
+        post(url, message(text(Msg::DailySummaryTitle), &state.usage));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UsageItem;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_message_lists_metrics_with_readings() {
+        let usage = UsageData {
+            session: UsageItem {
+                percent: Some(95),
+                resets: None,
+            },
+            weekly_all: UsageItem {
+                percent: Some(10),
+                resets: Some("not a time".to_string()),
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            message("Session at 95%", &usage),
+            "*Session at 95%*\n🔴 Session: 95%\n🟢 Weekly (all): 10% · Resets today not a time"
+        );
+    }
+
+    #[test]
+    fn test_daily_summary_due_once_per_day() {
+        let settings = Settings {
+            slack_webhook_url: Some("https://hooks.slack.com/services/x".to_string()),
+            slack_daily_summary_at: Some("09:00".to_string()),
+            ..Default::default()
+        };
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 9, 40, 0).unwrap();
+        assert!(summary_due(&settings, None, now));
+        assert!(!summary_due(&settings, Some(now.date_naive()), now));
+        assert!(!summary_due(&settings, None, now - chrono::Duration::hours(1)));
+        assert!(!summary_due(&Settings::default(), None, now));
+    }
+}
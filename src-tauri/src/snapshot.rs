@@ -0,0 +1,141 @@
+// The snapshot contract for third-party tools: cc-usage-snapshot.json in the
+// data folder, documented in docs/snapshot.md. Unlike the cache, which is
+// whatever UsageData serializes to and may change with it, this file has a
+// `schema_version` and fixed fields: resets as ISO 8601 times, not the
+// scraped text alone, and each limit's pace status as the tray works it out.
+// Rewritten on every tray update, atomically, at the same path whatever the
+// profile. Within a version fields are only ever added; renaming or removing
+// one, or changing its meaning, bumps the version.
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    datadir, indicator_color, pace, pace_indicator_at, parse_reading_timestamp, privacy_mode, resolve_reset,
+    AppState, MenuMetric, Settings, UsageData, INDICATORS_BY_SEVERITY,
+};
+
+pub(crate) const SNAPSHOT_FILE: &str = "cc-usage-snapshot.json";
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct LimitSnapshot {
+    percent: i32,
+    // As scraped, e.g. "4pm" or "Feb 3 at 5:59pm"
+    resets: Option<String>,
+    resets_at: Option<String>,
+    seconds_until_reset: Option<i64>,
+    // How much of the window has passed, as pacing counts it; None without
+    // a reset time, when pacing assumes the midpoint
+    elapsed_percent: Option<i32>,
+    // "green", "yellow", "orange", or "red"
+    pace: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct Snapshot {
+    schema_version: u32,
+    written_at: String,
+    // When the reading was taken; None before the first one
+    reading_at: Option<String>,
+    // Fetches are failing; the numbers are from the last good reading
+    stale: bool,
+    last_error: Option<String>,
+    // The worst pace across the limits
+    pace: &'static str,
+    limits: BTreeMap<&'static str, LimitSnapshot>,
+}
+
+pub(crate) fn snapshot(usage: &UsageData, last_error: Option<&str>, now: DateTime<Local>) -> Snapshot {
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp);
+    let mut worst = 0;
+    let mut limits = BTreeMap::new();
+    for metric in MenuMetric::ALL {
+        let item = metric.item(usage);
+        let Some(percent) = item.percent else {
+            continue;
+        };
+        let reset_at = resolve_reset(item.resets.as_deref(), read_at.unwrap_or(now));
+        let indicator = pace_indicator_at(percent, reset_at, metric, now);
+        let severity = INDICATORS_BY_SEVERITY.iter().position(|i| *i == indicator).unwrap_or(0);
+        worst = worst.max(severity);
+        let limit = LimitSnapshot {
+            percent,
+            resets: item.resets.clone(),
+            resets_at: reset_at.map(|at| at.to_rfc3339()),
+            seconds_until_reset: reset_at.map(|at| at.signed_duration_since(now).num_seconds().max(0)),
+            elapsed_percent: reset_at.map(|_| pace::elapsed(metric, reset_at, now)),
+            pace: indicator_color(indicator),
+        };
+        limits.insert(metric.as_str(), limit);
+    }
+    Snapshot {
+        schema_version: SCHEMA_VERSION,
+        written_at: now.to_rfc3339(),
+        reading_at: read_at.map(|at| at.to_rfc3339()),
+        stale: last_error.is_some(),
+        last_error: last_error.map(str::to_string),
+        pace: indicator_color(INDICATORS_BY_SEVERITY[worst]),
+        limits,
+    }
+}
+
+// Not in privacy mode
+pub(crate) fn write(state: &AppState, settings: &Settings) {
+    if privacy_mode(settings) {
+        return;
+    }
+    let snapshot = snapshot(&state.usage, state.last_error.as_deref(), Local::now());
+    if let Ok(json) = serde_json::to_string_pretty(&snapshot) {
+        let _ = datadir::write_atomic(&datadir::data_dir().join(SNAPSHOT_FILE), &format!("{}\n", json));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    // The field names are the contract; changing one here means a new
+    // schema version and an update to docs/snapshot.md
+    #[test]
+    fn test_snapshot_fields() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 30, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T14:00:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(95);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(10);
+        let json = serde_json::to_value(snapshot(&usage, Some("Timed out"), now)).unwrap();
+        let keys = |v: &Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            keys(&json),
+            ["last_error", "limits", "pace", "reading_at", "schema_version", "stale", "written_at"]
+        );
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["stale"], true);
+        assert_eq!(json["pace"], "red");
+        assert!(json["reading_at"].as_str().unwrap().starts_with("2026-01-28T14:00:00"));
+        assert_eq!(keys(&json["limits"]), ["session", "weekly"]);
+        let session = &json["limits"]["session"];
+        assert_eq!(
+            keys(session),
+            ["elapsed_percent", "pace", "percent", "resets", "resets_at", "seconds_until_reset"]
+        );
+        assert_eq!(session["percent"], 95);
+        assert_eq!(session["resets"], "4pm");
+        assert!(session["resets_at"].as_str().unwrap().starts_with("2026-01-28T16:00:00"));
+        assert_eq!(session["seconds_until_reset"], 5400);
+        assert_eq!(json["limits"]["weekly"]["resets_at"], Value::Null);
+        assert_eq!(json["limits"]["weekly"]["elapsed_percent"], Value::Null);
+
+        let empty = serde_json::to_value(snapshot(&UsageData::default(), None, now)).unwrap();
+        assert_eq!(empty["reading_at"], Value::Null);
+        assert_eq!(empty["pace"], "green");
+        assert_eq!(keys(&empty["limits"]), Vec::<String>::new());
+    }
+}
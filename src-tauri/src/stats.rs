@@ -0,0 +1,152 @@
+// Summary statistics over a range of history, for the stats panel in the
+// charts window
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::{init_db, profiles};
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub(crate) struct MetricStats {
+    pub(crate) avg: Option<f64>,
+    pub(crate) min: Option<i32>,
+    pub(crate) max: Option<i32>,
+    // First reading that hit `max`
+    pub(crate) peak_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub(crate) struct UsageStats {
+    from: String,
+    to: String,
+    pub(crate) readings: i64,
+    first_reading: Option<String>,
+    last_reading: Option<String>,
+    pub(crate) session: MetricStats,
+    pub(crate) weekly: MetricStats,
+    pub(crate) sonnet: MetricStats,
+}
+
+// Accepts "YYYY-MM-DD" or a full "YYYY-MM-DDTHH:MM:SS". A bare `to` date is
+// inclusive, so it becomes the start of the following day.
+pub(crate) fn parse_bound(value: &str, end_of_range: bool) -> Result<String, String> {
+    let value = value.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let date = if end_of_range { date.succ_opt().unwrap_or(date) } else { date };
+        return Ok(format!("{}T00:00:00", date.format("%Y-%m-%d")));
+    }
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .map_err(|_| format!("Invalid date: {}", value))
+}
+
+fn query_metric_stats(conn: &Connection, column: &str, from: &str, to: &str) -> rusqlite::Result<MetricStats> {
+    let (avg, min, max): (Option<f64>, Option<i32>, Option<i32>) = conn.query_row(
+        &format!(
+            "SELECT AVG({col}), MIN({col}), MAX({col}) FROM usage_history
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3",
+            col = column
+        ),
+        params![from, to, profiles::active_name()],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let peak_at = match max {
+        Some(max) => conn
+            .query_row(
+                &format!(
+                    "SELECT timestamp FROM usage_history
+                     WHERE timestamp >= ?1 AND timestamp < ?2 AND {} = ?3 AND profile IS ?4
+                     ORDER BY timestamp ASC LIMIT 1",
+                    column
+                ),
+                params![from, to, max, profiles::active_name()],
+                |row| row.get(0),
+            )
+            .ok(),
+        None => None,
+    };
+    Ok(MetricStats {
+        avg: avg.map(|a| (a * 10.0).round() / 10.0),
+        min,
+        max,
+        peak_at,
+    })
+}
+
+pub(crate) fn query_stats(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<UsageStats> {
+    let (readings, first_reading, last_reading) = conn.query_row(
+        "SELECT COUNT(*), MIN(timestamp), MAX(timestamp) FROM usage_history
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND profile IS ?3",
+        params![from, to, profiles::active_name()],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    Ok(UsageStats {
+        from: from.to_string(),
+        to: to.to_string(),
+        readings,
+        first_reading,
+        last_reading,
+        session: query_metric_stats(conn, "session_percent", from, to)?,
+        weekly: query_metric_stats(conn, "weekly_percent", from, to)?,
+        sonnet: query_metric_stats(conn, "sonnet_percent", from, to)?,
+    })
+}
+
+// `to` defaults to now
+#[tauri::command]
+pub(crate) fn get_stats(from: String, to: Option<String>) -> Result<UsageStats, String> {
+    let from = parse_bound(&from, false)?;
+    let to = match to {
+        Some(to) => parse_bound(&to, true)?,
+        None => chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+    };
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    query_stats(&conn, &from, &to).map_err(|e| format!("Failed to query stats: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn insert(conn: &Connection, timestamp: &str, session: Option<i32>, weekly: Option<i32>) {
+        conn.execute(
+            "INSERT INTO usage_history (timestamp, session_percent, weekly_percent) VALUES (?1, ?2, ?3)",
+            params![timestamp, session, weekly],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_stats_over_range() {
+        let db_path = std::env::temp_dir().join(format!("test_db_stats_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+        insert(&conn, "2026-01-27T23:00:00", Some(99), Some(10));
+        insert(&conn, "2026-01-28T09:00:00", Some(20), Some(30));
+        insert(&conn, "2026-01-28T12:00:00", Some(80), Some(35));
+        insert(&conn, "2026-01-28T15:00:00", Some(80), None);
+        insert(&conn, "2026-01-29T00:00:00", Some(5), Some(40));
+
+        let from = parse_bound("2026-01-28", false).unwrap();
+        let to = parse_bound("2026-01-28", true).unwrap();
+        let stats = query_stats(&conn, &from, &to).unwrap();
+        assert_eq!(stats.readings, 3);
+        assert_eq!(stats.first_reading.as_deref(), Some("2026-01-28T09:00:00"));
+        assert_eq!(stats.session.max, Some(80));
+        assert_eq!(stats.session.peak_at.as_deref(), Some("2026-01-28T12:00:00"));
+        assert_eq!(stats.session.avg, Some(60.0));
+        assert_eq!(stats.weekly.min, Some(30));
+        assert_eq!(stats.weekly.avg, Some(32.5));
+        assert_eq!(stats.sonnet, MetricStats::default());
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_parse_bound() {
+        assert_eq!(parse_bound("2026-01-31", true).unwrap(), "2026-02-01T00:00:00");
+        assert_eq!(parse_bound("2026-01-31T08:30:00", true).unwrap(), "2026-01-31T08:30:00");
+        assert!(parse_bound("yesterday", false).is_err());
+    }
+}
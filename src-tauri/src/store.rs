@@ -0,0 +1,168 @@
+// Buffered history writes. Readings are queued in memory and written in one
+// transaction through a connection kept open for the life of the app, instead
+// of opening the database (and running its DDL) for every reading. The fetch
+// log and sync go through the same connection.
+//
+// Anything that reads history goes through init_db(), which flushes first, so
+// queued readings are never missing from queries.
+use rusqlite::Connection;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::{append_log, insert_usage, open_db, UsageData};
+
+// Readings arrive every 10 minutes, so this is about an hour
+const FLUSH_BATCH_SIZE: usize = 6;
+const FLUSH_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+pub(crate) struct HistoryWriter {
+    conn: Option<Connection>,
+    pending: Vec<UsageData>,
+    oldest_pending: Option<Instant>,
+}
+
+static WRITER: Mutex<HistoryWriter> = Mutex::new(HistoryWriter::new());
+
+impl HistoryWriter {
+    pub(crate) const fn new() -> Self {
+        HistoryWriter {
+            conn: None,
+            pending: Vec::new(),
+            oldest_pending: None,
+        }
+    }
+
+    // Queue a reading; true when the batch should be flushed
+    pub(crate) fn push(&mut self, usage: UsageData) -> bool {
+        self.oldest_pending.get_or_insert_with(Instant::now);
+        self.pending.push(usage);
+        self.pending.len() >= FLUSH_BATCH_SIZE
+            || self.oldest_pending.is_some_and(|t| t.elapsed() >= FLUSH_MAX_AGE)
+    }
+
+    fn connection(&mut self) -> Result<&mut Connection, rusqlite::Error> {
+        if self.conn.is_none() {
+            self.conn = Some(open_db()?);
+        }
+        Ok(self.conn.as_mut().unwrap())
+    }
+
+    // Write everything queued in one transaction. On failure the readings stay
+    // queued and the connection is dropped, so the next flush reopens it.
+    pub(crate) fn flush(&mut self) -> Result<usize, rusqlite::Error> {
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+        self.connection()?;
+        let result = Self::write_all(self.conn.as_mut().unwrap(), &self.pending);
+        match result {
+            Ok(()) => {
+                let written = self.pending.len();
+                self.pending.clear();
+                self.oldest_pending = None;
+                Ok(written)
+            }
+            Err(e) => {
+                self.conn = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn write_all(conn: &mut Connection, readings: &[UsageData]) -> Result<(), rusqlite::Error> {
+        let tx = conn.transaction()?;
+        for usage in readings {
+            insert_usage(&tx, usage)?;
+        }
+        tx.commit()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_connection(conn: Connection) -> Self {
+        HistoryWriter {
+            conn: Some(conn),
+            ..HistoryWriter::new()
+        }
+    }
+}
+
+fn writer() -> std::sync::MutexGuard<'static, HistoryWriter> {
+    WRITER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub(crate) fn queue_reading(usage: &UsageData) {
+    let mut writer = writer();
+    if writer.push(usage.clone()) {
+        if let Err(e) = writer.flush() {
+            append_log(&format!("Failed to write history: {}", e));
+        }
+    }
+}
+
+pub(crate) fn flush() {
+    if let Err(e) = writer().flush() {
+        append_log(&format!("Failed to write history: {}", e));
+    }
+}
+
+// Run `f` on the shared connection. Queued readings aren't flushed first; call
+// flush() before this when `f` needs them. `f` must not call init_db() or
+// anything else in this module, since the writer stays locked while it runs.
+pub(crate) fn with_connection<T>(f: impl FnOnce(&mut Connection) -> T) -> Result<T, rusqlite::Error> {
+    let mut writer = writer();
+    writer.connection().map(f)
+}
+
+// Flush and drop the shared connection, for when the database file is about to
+// be moved, replaced, or re-keyed
+pub(crate) fn close() {
+    let mut writer = writer();
+    if let Err(e) = writer.flush() {
+        append_log(&format!("Failed to write history: {}", e));
+    }
+    writer.conn = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UsageItem;
+    use std::fs;
+
+    fn reading(timestamp: &str) -> UsageData {
+        UsageData {
+            timestamp: Some(timestamp.to_string()),
+            session: UsageItem {
+                percent: Some(10),
+                resets: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn count(path: &std::path::Path) -> i64 {
+        Connection::open(path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM usage_history", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_readings_are_written_in_batches() {
+        let db_path = std::env::temp_dir().join(format!("test_db_store_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let mut writer = HistoryWriter::with_connection(crate::init_test_db(&db_path).unwrap());
+
+        for i in 0..FLUSH_BATCH_SIZE - 1 {
+            assert!(!writer.push(reading(&format!("2026-01-28T10:{:02}:00", i * 10))));
+        }
+        assert_eq!(count(&db_path), 0);
+        assert!(writer.push(reading("2026-01-28T11:00:00")));
+        assert_eq!(writer.flush().unwrap(), FLUSH_BATCH_SIZE);
+        assert_eq!(count(&db_path), FLUSH_BATCH_SIZE as i64);
+        assert_eq!(writer.flush().unwrap(), 0);
+
+        drop(writer);
+        let _ = fs::remove_file(&db_path);
+    }
+}
@@ -0,0 +1,94 @@
+// Stream Deck keys, through the local server. A plugin polls
+// `GET /streamdeck?metric=session` (any of the menu's metric names; session
+// by default) for the key: a two-line title ("42%" over "3h"), the pace
+// color as a name and a hex value for the key's background, and the parts
+// for plugins that draw their own. `POST /refresh` is the key press: it
+// starts a fetch like Menu > Refresh Now and returns right away; the next
+// poll shows the result. The state comes from the reading in memory, so
+// polling every second is cheap.
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    get_status_indicator_paced, indicator_color, parse_reading_timestamp, resolve_reset, tmux, MenuMetric, UsageData,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct KeyState {
+    metric: &'static str,
+    percent: Option<i32>,
+    // "green" through "red", or "none" without a reading
+    color: &'static str,
+    background: &'static str,
+    // "45m", "3h", or "2d" until the reset
+    countdown: Option<String>,
+    title: String,
+}
+
+// Close to the system colors the tray's indicators use
+fn hex(color: &str) -> &'static str {
+    match color {
+        "green" => "#34C759",
+        "yellow" => "#FFCC00",
+        "orange" => "#FF9500",
+        "red" => "#FF3B30",
+        _ => "#8E8E93",
+    }
+}
+
+pub(crate) fn key_state(usage: &UsageData, metric: MenuMetric, now: DateTime<Local>) -> KeyState {
+    let item = metric.item(usage);
+    let color = match item.percent {
+        Some(percent) => indicator_color(get_status_indicator_paced(percent, item.resets.as_deref(), metric)),
+        None => "none",
+    };
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    let countdown = item
+        .percent
+        .and(resolve_reset(item.resets.as_deref(), read_at))
+        .and_then(|at| tmux::compact_countdown(at, now));
+    let mut title = item.percent.map_or("--".to_string(), |p| format!("{}%", p));
+    if let Some(countdown) = &countdown {
+        title = format!("{}\n{}", title, countdown);
+    }
+    KeyState {
+        metric: metric.as_str(),
+        percent: item.percent,
+        color,
+        background: hex(color),
+        countdown,
+        title,
+    }
+}
+
+// The `metric` query parameter's value, if any
+pub(crate) fn metric_param(query: &str) -> Option<&str> {
+    query.split('&').find_map(|pair| pair.strip_prefix("metric="))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_key_state() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 12, 50, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T12:45:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(95);
+        usage.session.resets = Some("4pm".to_string());
+        let key = key_state(&usage, MenuMetric::Session, now);
+        assert_eq!(key.title, "95%\n3h");
+        assert_eq!(key.color, "red");
+        assert_eq!(key.background, "#FF3B30");
+        assert_eq!(key.countdown.as_deref(), Some("3h"));
+
+        let empty = key_state(&usage, MenuMetric::Opus, now);
+        assert_eq!((empty.title.as_str(), empty.color, empty.percent), ("--", "none", None));
+        assert_eq!(metric_param("x=1&metric=weekly"), Some("weekly"));
+        assert_eq!(metric_param(""), None);
+    }
+}
@@ -0,0 +1,70 @@
+// `cc-usage swiftbar`: the cached reading in SwiftBar/xbar plugin format, so
+// those menu bars can show this app's usage without fetching themselves. A
+// plugin script only has to exec the binary with this argument. The title is
+// the worst pace indicator and the tray's percentages; the dropdown has a
+// line per metric. Text that would contain `|` (SwiftBar's parameter
+// separator) uses `·` instead.
+use chrono::{DateTime, Local};
+
+use crate::cli;
+use crate::locale::{self, tr, Msg};
+use crate::{load_cached_usage, parse_reading_timestamp, worst_pace_indicator, MenuMetric, TrayMetric, UsageData};
+
+pub(crate) fn render(usage: &UsageData, now: DateTime<Local>) -> String {
+    let Some(session) = usage.session.percent else {
+        return "⚠️ --\n---\nNo reading yet. Start cc-usage to fetch one.\n".to_string();
+    };
+    let mut out = format!(
+        "{} {}% {}%\n---\n",
+        worst_pace_indicator(usage, TrayMetric::Both, &MenuMetric::ALL),
+        session,
+        usage.weekly_all.percent.unwrap_or(0)
+    );
+    for line in cli::metric_lines(usage) {
+        out.push_str(&format!("{}\n", line));
+    }
+    if let Some(at) = usage.timestamp.as_deref().and_then(parse_reading_timestamp) {
+        let pattern = if at.date_naive() == now.date_naive() {
+            locale::clock(false).to_string()
+        } else {
+            locale::date_clock(false)
+        };
+        out.push_str(&format!("---\n{}\n", tr(Msg::Updated, &[&at.format(&pattern)])));
+    }
+    out.push_str("Refresh | refresh=true\n");
+    out
+}
+
+pub(crate) fn print() -> i32 {
+    print!("{}", render(&load_cached_usage().unwrap_or_default(), Local::now()));
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render_plugin_output() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 0, 0).unwrap();
+        assert!(render(&UsageData::default(), now).starts_with("⚠️ --\n---\n"));
+
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T10:40:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        usage.weekly_all.percent = Some(67);
+        usage.weekly_opus.percent = Some(5);
+        let out = render(&usage, now);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].ends_with(" 42% 67%"));
+        assert_eq!(lines[1], "---");
+        assert!(lines[2].ends_with("Session: 42%"));
+        assert!(lines[4].ends_with(": 5%"));
+        assert_eq!(lines[6], "Updated: 10:40");
+        // Only the Refresh line has parameters
+        assert_eq!(out.matches('|').count(), 1);
+    }
+}
@@ -0,0 +1,269 @@
+// Opt-in history sync between machines through a shared folder (iCloud Drive,
+// Dropbox, ...). Each machine appends its readings to <sync_dir>/<machine>.jsonl,
+// one JSON record per line, and imports the other machines' files into its own
+// database tagged with their name, so charts and stats cover all of them.
+//
+// Files are only ever appended to, so each machine remembers how many bytes of
+// every other file it has read (sync_offsets) and picks up from there.
+// Timestamps are local time, so the machines are assumed to share a timezone.
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{append_log, export, load_settings, store, UsageData};
+
+const SYNC_EXTENSION: &str = "jsonl";
+
+// Keep names usable as file names on every platform
+fn sanitize_machine_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+fn hostname() -> Option<String> {
+    let output = std::process::Command::new("hostname").arg("-s").output().ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+pub(crate) fn machine_name() -> String {
+    let name = load_settings()
+        .machine_name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(hostname)
+        .unwrap_or_else(|| "this-machine".to_string());
+    sanitize_machine_name(&name)
+}
+
+fn sync_dir() -> Option<PathBuf> {
+    load_settings()
+        .sync_dir
+        .filter(|d| !d.trim().is_empty())
+        .map(|d| PathBuf::from(d.trim()))
+}
+
+fn record_line(record: &export::HistoryRecord) -> Option<String> {
+    serde_json::to_string(record).ok().map(|json| format!("{}\n", json))
+}
+
+// Readings recorded on this machine, not ones synced from elsewhere. Every
+// profile's, tagged, so the other machines file them under the same one.
+fn local_records(conn: &Connection) -> Result<Vec<export::HistoryRecord>, rusqlite::Error> {
+    conn.prepare(
+        "SELECT timestamp, session_percent, session_resets, weekly_percent, weekly_resets, sonnet_percent, sonnet_resets,
+                opus_percent, opus_resets, profile
+         FROM usage_history
+         WHERE machine IS NULL
+         ORDER BY timestamp ASC",
+    )?
+    .query_map([], |row| {
+        Ok(export::HistoryRecord {
+            timestamp: row.get(0)?,
+            session_percent: row.get(1)?,
+            session_resets: row.get(2)?,
+            weekly_percent: row.get(3)?,
+            weekly_resets: row.get(4)?,
+            sonnet_percent: row.get(5)?,
+            sonnet_resets: row.get(6)?,
+            opus_percent: row.get(7)?,
+            opus_resets: row.get(8)?,
+            profile: row.get(9)?,
+        })
+    })?
+    .collect()
+}
+
+// Write this machine's file from scratch with all of its local history
+fn write_snapshot(conn: &Connection, path: &Path) -> Result<(), String> {
+    let records = local_records(conn).map_err(|e| e.to_string())?;
+    let content: String = records.iter().filter_map(record_line).collect();
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn append_record(path: &Path, record: &export::HistoryRecord) -> Result<(), String> {
+    let Some(line) = record_line(record) else {
+        return Ok(());
+    };
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// After a reading is recorded: append it to this machine's file. The first
+// time (or if the file was deleted) the whole local history is written instead.
+pub(crate) fn publish(usage: &UsageData) {
+    let Some(dir) = sync_dir() else {
+        return;
+    };
+    let path = dir.join(format!("{}.{}", machine_name(), SYNC_EXTENSION));
+    let result = if path.exists() {
+        append_record(&path, &export::HistoryRecord::from_usage(usage))
+    } else {
+        // Flushed first, so the snapshot includes this reading
+        store::flush();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+            .and_then(|_| {
+                store::with_connection(|conn| write_snapshot(conn, &path))
+                    .unwrap_or_else(|e| Err(format!("Failed to open database: {}", e)))
+            })
+    };
+    if let Err(e) = result {
+        append_log(&format!("Sync publish failed: {}", e));
+    }
+}
+
+fn read_offset(conn: &Connection, machine: &str) -> Result<usize, rusqlite::Error> {
+    let offset: Option<i64> = conn
+        .query_row(
+            "SELECT bytes_read FROM sync_offsets WHERE machine = ?1",
+            params![machine],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(offset.unwrap_or(0) as usize)
+}
+
+fn write_offset(conn: &Connection, machine: &str, offset: usize) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO sync_offsets (machine, bytes_read) VALUES (?1, ?2)
+         ON CONFLICT(machine) DO UPDATE SET bytes_read = excluded.bytes_read",
+        params![machine, offset as i64],
+    )?;
+    Ok(())
+}
+
+// Import what's new in one machine's file. Only complete lines are read, since
+// the sync client may still be writing the rest. Returns the readings imported.
+fn merge_file(conn: &mut Connection, machine: &str, path: &Path) -> Result<usize, String> {
+    let content = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut offset = read_offset(conn, machine).map_err(|e| e.to_string())?;
+    // Smaller than what we've read: the file was rewritten, so start over
+    // (readings already imported are skipped as duplicates)
+    if content.len() < offset {
+        offset = 0;
+    }
+    let Some(end) = content[offset..].iter().rposition(|&b| b == b'\n').map(|i| offset + i + 1) else {
+        return Ok(0);
+    };
+    let records: Vec<Result<export::HistoryRecord, String>> = String::from_utf8_lossy(&content[offset..end])
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| format!("{}: {}", machine, e)))
+        .collect();
+    let summary = export::import_records(conn, records, Some(machine))?;
+    if !summary.invalid.is_empty() {
+        append_log(&format!("Sync: skipped {} invalid readings from {}", summary.invalid.len(), machine));
+    }
+    write_offset(conn, machine, end).map_err(|e| e.to_string())?;
+    Ok(summary.imported)
+}
+
+// Every other machine's file in `dir`
+pub(crate) fn merge_dir(conn: &mut Connection, dir: &Path, own_machine: &str) -> Result<usize, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut imported = 0;
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some(SYNC_EXTENSION) {
+            continue;
+        }
+        let Some(machine) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if machine != own_machine {
+            imported += merge_file(conn, machine, &path)?;
+        }
+    }
+    Ok(imported)
+}
+
+// After each fetch: pull in readings the other machines have published
+pub(crate) fn merge_remote() {
+    let Some(dir) = sync_dir().filter(|d| d.is_dir()) else {
+        return;
+    };
+    let result = store::with_connection(|conn| merge_dir(conn, &dir, &machine_name()))
+        .unwrap_or_else(|e| Err(format!("Failed to open database: {}", e)));
+    match result {
+        Ok(0) => {}
+        Ok(n) => append_log(&format!("Sync: merged {} readings from other machines", n)),
+        Err(e) => append_log(&format!("Sync merge failed: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: &str, session: i32) -> export::HistoryRecord {
+        export::HistoryRecord {
+            timestamp: timestamp.to_string(),
+            session_percent: Some(session),
+            session_resets: None,
+            weekly_percent: Some(20),
+            weekly_resets: None,
+            sonnet_percent: None,
+            sonnet_resets: None,
+            opus_percent: None,
+            opus_resets: None,
+            profile: None,
+        }
+    }
+
+    fn machines(conn: &Connection) -> Vec<(String, Option<String>)> {
+        conn.prepare("SELECT timestamp, machine FROM usage_history ORDER BY timestamp")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_merge_reads_only_new_complete_lines() {
+        let db_path = std::env::temp_dir().join(format!("test_db_sync_{}.db", std::process::id()));
+        let dir = std::env::temp_dir().join(format!("test_sync_{}", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut conn = crate::init_test_db(&db_path).unwrap();
+        crate::insert_usage(&conn, &record("2026-01-28T09:00:00", 5).to_usage()).unwrap();
+
+        // This machine's own file is ignored; the other's last line is still being written
+        let laptop = dir.join("laptop.jsonl");
+        write_snapshot(&conn, &dir.join("desktop.jsonl")).unwrap();
+        fs::write(&laptop, record_line(&record("2026-01-28T10:00:00", 10)).unwrap() + "{\"timest").unwrap();
+        assert_eq!(merge_dir(&mut conn, &dir, "desktop").unwrap(), 1);
+
+        let mut content = record_line(&record("2026-01-28T10:00:00", 10)).unwrap();
+        content.push_str(&record_line(&record("2026-01-28T10:10:00", 12)).unwrap());
+        fs::write(&laptop, &content).unwrap();
+        assert_eq!(merge_dir(&mut conn, &dir, "desktop").unwrap(), 1);
+        assert_eq!(merge_dir(&mut conn, &dir, "desktop").unwrap(), 0);
+
+        assert_eq!(
+            machines(&conn),
+            vec![
+                ("2026-01-28T09:00:00".to_string(), None),
+                ("2026-01-28T10:00:00".to_string(), Some("laptop".to_string())),
+                ("2026-01-28T10:10:00".to_string(), Some("laptop".to_string())),
+            ]
+        );
+        // Synced readings aren't re-published as this machine's
+        assert_eq!(local_records(&conn).unwrap().len(), 1);
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sanitize_machine_name() {
+        assert_eq!(sanitize_machine_name(" Jane's MacBook.local "), "Jane-s-MacBook-local");
+    }
+}
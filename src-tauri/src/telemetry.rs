@@ -0,0 +1,187 @@
+// Pushes to the user's own observability stack after every fetch: statsd
+// over UDP when `statsd_host` ("host" or "host:port", 8125 by default) is
+// set, OTLP/HTTP JSON to `otlp_endpoint` (a collector's .../v1/metrics) when
+// that is. Both get each limit's percentage and seconds to reset, and the
+// fetch's duration and outcome, so a failed fetch still reports its latency.
+// The same numbers as the local server's `/metrics`, pushed instead of
+// scraped.
+use std::net::UdpSocket;
+
+use chrono::{DateTime, Local};
+use serde_json::{json, Value};
+
+use crate::{append_log, curl, parse_reading_timestamp, resolve_reset, MenuMetric, Settings, UsageData};
+
+const DEFAULT_STATSD_PORT: u16 = 8125;
+
+// (host, port) from "host" or "host:port"
+pub(crate) fn parse_statsd_host(host: &str) -> Option<(&str, u16)> {
+    let host = host.trim();
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) => (name, port.parse().ok()?),
+        None => (host, DEFAULT_STATSD_PORT),
+    };
+    (!name.is_empty() && !name.contains('/')).then_some((name, port))
+}
+
+// (metric, percent, seconds to reset) for each limit with a reading
+fn limits(usage: &UsageData, now: DateTime<Local>) -> Vec<(MenuMetric, i32, Option<i64>)> {
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    MenuMetric::ALL
+        .into_iter()
+        .filter_map(|metric| {
+            let item = metric.item(usage);
+            let reset_at = resolve_reset(item.resets.as_deref(), read_at);
+            let reset_secs = reset_at.map(|at| at.signed_duration_since(now).num_seconds().max(0));
+            Some((metric, item.percent?, reset_secs))
+        })
+        .collect()
+}
+
+// One datagram, lines like "cc_usage.percent.session:42|g"
+pub(crate) fn statsd_lines(usage: &UsageData, duration_ms: i64, success: bool, now: DateTime<Local>) -> String {
+    let mut lines = Vec::new();
+    for (metric, percent, reset_secs) in limits(usage, now) {
+        lines.push(format!("cc_usage.percent.{}:{}|g", metric.as_str(), percent));
+        if let Some(secs) = reset_secs {
+            lines.push(format!("cc_usage.reset_seconds.{}:{}|g", metric.as_str(), secs));
+        }
+    }
+    lines.push(format!("cc_usage.fetch_duration:{}|ms", duration_ms));
+    lines.push("cc_usage.fetches:1|c".to_string());
+    if !success {
+        lines.push("cc_usage.fetch_errors:1|c".to_string());
+    }
+    lines.join("\n")
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({"key": key, "value": {"stringValue": value}})
+}
+
+fn gauge(name: &str, unit: &str, points: Vec<Value>) -> Value {
+    json!({"name": name, "unit": unit, "gauge": {"dataPoints": points}})
+}
+
+// An ExportMetricsServiceRequest in OTLP's JSON encoding. The fetch counts
+// are per-export deltas, so each push stands on its own.
+pub(crate) fn otlp_body(usage: &UsageData, duration_ms: i64, success: bool, now: DateTime<Local>) -> Value {
+    let time = now.timestamp_nanos_opt().unwrap_or_default().to_string();
+    let point = |value: Value, metric: Option<MenuMetric>| {
+        let mut point = json!({"timeUnixNano": time});
+        let key = if value.is_f64() { "asDouble" } else { "asInt" };
+        point[key] = value;
+        if let Some(metric) = metric {
+            point["attributes"] = json!([attribute("metric", metric.as_str())]);
+        }
+        point
+    };
+    let limits = limits(usage, now);
+    let mut metrics = Vec::new();
+    if !limits.is_empty() {
+        let percents = limits.iter().map(|(m, percent, _)| point(json!(percent), Some(*m))).collect();
+        metrics.push(gauge("cc_usage.percent", "%", percents));
+        let resets: Vec<_> = limits
+            .iter()
+            .filter_map(|(m, _, secs)| secs.map(|secs| point(json!(secs), Some(*m))))
+            .collect();
+        if !resets.is_empty() {
+            metrics.push(gauge("cc_usage.reset_seconds", "s", resets));
+        }
+    }
+    metrics.push(gauge("cc_usage.fetch_duration", "s", vec![point(json!(duration_ms as f64 / 1000.0), None)]));
+    let fetches = |name: &str, count: i64| {
+        json!({
+            "name": name,
+            "sum": {
+                "dataPoints": [point(json!(count), None)],
+                "aggregationTemporality": 1,
+                "isMonotonic": true,
+            },
+        })
+    };
+    metrics.push(fetches("cc_usage.fetches", 1));
+    metrics.push(fetches("cc_usage.fetch_errors", i64::from(!success)));
+    json!({
+        "resourceMetrics": [{
+            "resource": {"attributes": [attribute("service.name", "cc-usage")]},
+            "scopeMetrics": [{"scope": {"name": "cc-usage"}, "metrics": metrics}],
+        }],
+    })
+}
+
+fn send_statsd(host: &str, lines: &str) -> Result<(), String> {
+    let addr = parse_statsd_host(host).ok_or_else(|| format!("Bad statsd host: {}", host))?;
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+    socket.send_to(lines.as_bytes(), addr).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// After every fetch, successful or not
+pub(crate) fn push(settings: &Settings, usage: &UsageData, duration_ms: i64, success: bool) {
+    if settings.statsd_host.is_none() && settings.otlp_endpoint.is_none() {
+        return;
+    }
+    let now = Local::now();
+    let statsd = settings
+        .statsd_host
+        .clone()
+        .map(|host| (host, statsd_lines(usage, duration_ms, success, now)));
+    let otlp = settings
+        .otlp_endpoint
+        .clone()
+        .map(|url| (url, otlp_body(usage, duration_ms, success, now).to_string()));
+    std::thread::spawn(move || {
+        if let Some((host, lines)) = statsd {
+            if let Err(e) = send_statsd(&host, &lines) {
+                append_log(&format!("statsd push to {} failed: {}", host, e));
+            }
+        }
+        if let Some((url, body)) = otlp {
+            if let Err(e) = curl::post_json(&url, &body) {
+                append_log(&format!("OTLP push to {} failed: {}", url, e));
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_statsd_and_otlp() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 14, 30, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T14:00:00".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(67);
+        assert_eq!(
+            statsd_lines(&usage, 8450, true, now),
+            "cc_usage.percent.session:42|g\ncc_usage.reset_seconds.session:5400|g\n\
+             cc_usage.percent.weekly:67|g\ncc_usage.fetch_duration:8450|ms\ncc_usage.fetches:1|c"
+        );
+        // A failed fetch has no reading, just the latency and the error
+        let failed = statsd_lines(&UsageData::default(), 30000, false, now);
+        assert_eq!(failed, "cc_usage.fetch_duration:30000|ms\ncc_usage.fetches:1|c\ncc_usage.fetch_errors:1|c");
+
+        let body = otlp_body(&usage, 8450, true, now);
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(metrics[0]["name"], "cc_usage.percent");
+        let points = metrics[0]["gauge"]["dataPoints"].as_array().unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0]["asInt"], 42);
+        assert_eq!(points[0]["attributes"][0]["value"]["stringValue"], "session");
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asInt"], 5400);
+        assert_eq!(metrics[2]["gauge"]["dataPoints"][0]["asDouble"], 8.45);
+        assert_eq!(metrics[4]["sum"]["dataPoints"][0]["asInt"], 0);
+
+        assert_eq!(parse_statsd_host("localhost"), Some(("localhost", 8125)));
+        assert_eq!(parse_statsd_host("10.0.0.5:9125"), Some(("10.0.0.5", 9125)));
+        assert_eq!(parse_statsd_host("udp://x"), None);
+    }
+}
@@ -0,0 +1,106 @@
+// Light or dark windows. `theme` is "system" (default, follow the OS
+// appearance), "light", or "dark"; a fixed choice is set app-wide, so the
+// webviews' prefers-color-scheme and the menus agree with it. Each window
+// also gets `theme://changed` when its appearance changes, from either the OS
+// or a new setting, and `get_theme` for its first paint.
+use serde::Serialize;
+use tauri::{Emitter, Manager, Runtime};
+
+use crate::Settings;
+
+pub(crate) const THEME_CHANGED_EVENT: &str = "theme://changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub(crate) const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn from_setting(value: Option<&str>) -> Self {
+        Self::ALL.into_iter().find(|t| Some(t.as_str()) == value).unwrap_or_default()
+    }
+
+    // What to force on the app; None follows the OS
+    fn forced(self) -> Option<tauri::Theme> {
+        match self {
+            Theme::System => None,
+            Theme::Light => Some(tauri::Theme::Light),
+            Theme::Dark => Some(tauri::Theme::Dark),
+        }
+    }
+
+    // The appearance a window ends up with, given the OS's
+    fn resolve(self, os: tauri::Theme) -> &'static str {
+        match self.forced().unwrap_or(os) {
+            tauri::Theme::Light => "light",
+            _ => "dark",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct ThemeInfo {
+    preference: &'static str,
+    // "light" or "dark"
+    resolved: &'static str,
+}
+
+fn info(settings: &Settings, os: tauri::Theme) -> ThemeInfo {
+    let theme = Theme::from_setting(settings.theme.as_deref());
+    ThemeInfo {
+        preference: theme.as_str(),
+        resolved: theme.resolve(os),
+    }
+}
+
+// At startup and on save: force the chosen appearance (or stop forcing it)
+// and tell the open windows
+pub(crate) fn apply<R: Runtime>(app: &tauri::AppHandle<R>, settings: &Settings) {
+    app.set_theme(Theme::from_setting(settings.theme.as_deref()).forced());
+    for (label, window) in app.webview_windows() {
+        if let Ok(os) = window.theme() {
+            let _ = app.emit_to(label.as_str(), THEME_CHANGED_EVENT, info(settings, os));
+        }
+    }
+}
+
+// From the window event handler, when the OS appearance changes
+pub(crate) fn os_changed<R: Runtime>(window: &tauri::Window<R>, os: tauri::Theme) {
+    let _ = window.emit_to(window.label(), THEME_CHANGED_EVENT, info(&crate::load_settings(), os));
+}
+
+#[tauri::command]
+pub(crate) fn get_theme(window: tauri::WebviewWindow) -> ThemeInfo {
+    info(&crate::load_settings(), window.theme().unwrap_or(tauri::Theme::Dark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_theme_overrides_the_os() {
+        assert_eq!(Theme::from_setting(None), Theme::System);
+        assert_eq!(Theme::System.resolve(tauri::Theme::Light), "light");
+        assert_eq!(Theme::Dark.resolve(tauri::Theme::Light), "dark");
+        assert_eq!(Theme::Light.resolve(tauri::Theme::Dark), "light");
+        let settings = Settings {
+            theme: Some("light".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(info(&settings, tauri::Theme::Dark).preference, "light");
+    }
+}
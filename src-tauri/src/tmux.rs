@@ -0,0 +1,68 @@
+// A one-line status ("S42% W67% 3h") in cc-usage-status.txt in the data
+// folder, for tmux's status-right and anything else that can cat a file.
+// Rewritten whenever the tray is, so after every fetch and every minute for
+// the countdown. Written to a temporary file and renamed over the old one, so
+// a reader never sees it half-written. Not written in privacy mode.
+use chrono::{DateTime, Local};
+
+use crate::{datadir, parse_reading_timestamp, privacy_mode, resolve_reset, AppState, Settings, UsageData};
+
+pub(crate) const STATUS_FILE: &str = "cc-usage-status.txt";
+
+// "3h" or "45m" until the reset
+pub(crate) fn compact_countdown(reset_at: DateTime<Local>, now: DateTime<Local>) -> Option<String> {
+    let mins = reset_at.signed_duration_since(now).num_minutes();
+    match mins {
+        m if m <= 0 => None,
+        m if m < 60 => Some(format!("{}m", m)),
+        m if m < 48 * 60 => Some(format!("{}h", m / 60)),
+        m => Some(format!("{}d", m / (24 * 60))),
+    }
+}
+
+// Session and weekly percentages and the time to the session reset; a
+// trailing "!" while fetches are failing. Empty without a reading.
+pub(crate) fn status_line(usage: &UsageData, failing: bool, now: DateTime<Local>) -> String {
+    let Some(session) = usage.session.percent else {
+        return String::new();
+    };
+    let mut parts = vec![format!("S{}%", session)];
+    parts.extend(usage.weekly_all.percent.map(|p| format!("W{}%", p)));
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    parts.extend(resolve_reset(usage.session.resets.as_deref(), read_at).and_then(|at| compact_countdown(at, now)));
+    if failing {
+        parts.push("!".to_string());
+    }
+    parts.join(" ")
+}
+
+pub(crate) fn write_status(state: &AppState, settings: &Settings) {
+    if privacy_mode(settings) {
+        return;
+    }
+    let line = status_line(&state.usage, state.last_error.is_some(), Local::now());
+    let _ = datadir::write_atomic(&datadir::data_dir().join(STATUS_FILE), &format!("{}\n", line));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_status_line() {
+        let now = Local.with_ymd_and_hms(2026, 1, 28, 12, 50, 0).unwrap();
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T12:45:00".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(status_line(&usage, false, now), "");
+        usage.session.percent = Some(42);
+        usage.session.resets = Some("4pm".to_string());
+        usage.weekly_all.percent = Some(67);
+        assert_eq!(status_line(&usage, false, now), "S42% W67% 3h");
+        assert_eq!(status_line(&usage, true, now), "S42% W67% 3h !");
+        assert_eq!(compact_countdown(now + chrono::Duration::minutes(45), now).as_deref(), Some("45m"));
+        assert_eq!(compact_countdown(now + chrono::Duration::days(5), now).as_deref(), Some("5d"));
+    }
+}
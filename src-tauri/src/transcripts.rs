@@ -0,0 +1,111 @@
+// Reading Claude Code session transcripts (~/.claude/projects/<project>/<session>.jsonl).
+// Each assistant line carries the model and token usage for one response.
+use std::fs;
+use std::path::PathBuf;
+
+use crate::profiles;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TranscriptEntry {
+    pub(crate) timestamp: chrono::DateTime<chrono::Local>,
+    pub(crate) session_id: Option<String>,
+    pub(crate) model: Option<String>,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) cache_creation_tokens: u64,
+    pub(crate) cache_read_tokens: u64,
+}
+
+// Honors CLAUDE_CONFIG_DIR the same way the claude CLI does, with the active
+// profile's taking precedence
+pub(crate) fn projects_dir() -> PathBuf {
+    profiles::config_dir()
+        .or_else(|| std::env::var_os("CLAUDE_CONFIG_DIR").map(PathBuf::from))
+        .or_else(|| dirs::home_dir().map(|h| h.join(".claude")))
+        .unwrap_or_else(|| PathBuf::from(".claude"))
+        .join("projects")
+}
+
+pub(crate) fn parse_line(line: &str) -> Option<TranscriptEntry> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    if value.get("type")?.as_str()? != "assistant" {
+        return None;
+    }
+    let timestamp = chrono::DateTime::parse_from_rfc3339(value.get("timestamp")?.as_str()?)
+        .ok()?
+        .with_timezone(&chrono::Local);
+    let message = value.get("message")?;
+    let usage = message.get("usage")?;
+    let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(TranscriptEntry {
+        timestamp,
+        session_id: value.get("sessionId").and_then(|v| v.as_str()).map(String::from),
+        model: message.get("model").and_then(|v| v.as_str()).map(String::from),
+        input_tokens: tokens("input_tokens"),
+        output_tokens: tokens("output_tokens"),
+        cache_creation_tokens: tokens("cache_creation_input_tokens"),
+        cache_read_tokens: tokens("cache_read_input_tokens"),
+    })
+}
+
+// All assistant entries with from <= timestamp < to. Files not modified since
+// `from` are skipped without reading.
+pub(crate) fn read_entries(
+    from: chrono::DateTime<chrono::Local>,
+    to: chrono::DateTime<chrono::Local>,
+) -> Vec<TranscriptEntry> {
+    let mut entries = Vec::new();
+    let Ok(projects) = fs::read_dir(projects_dir()) else {
+        return entries;
+    };
+    let from_system: std::time::SystemTime = from.into();
+
+    for project in projects.flatten() {
+        let Ok(files) = fs::read_dir(project.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let modified = file.metadata().and_then(|m| m.modified());
+            if modified.is_ok_and(|m| m < from_system) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            entries.extend(
+                content
+                    .lines()
+                    .filter_map(parse_line)
+                    .filter(|e| e.timestamp >= from && e.timestamp < to),
+            );
+        }
+    }
+
+    entries.sort_by_key(|e| e.timestamp);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assistant_line() {
+        let line = r#"{"type":"assistant","sessionId":"abc","timestamp":"2026-01-28T14:00:00.000Z","message":{"model":"claude-opus-4","usage":{"input_tokens":10,"output_tokens":200,"cache_creation_input_tokens":5,"cache_read_input_tokens":1000}}}"#;
+        let entry = parse_line(line).expect("Should parse");
+        assert_eq!(entry.session_id.as_deref(), Some("abc"));
+        assert_eq!(entry.model.as_deref(), Some("claude-opus-4"));
+        assert_eq!(entry.output_tokens, 200);
+        assert_eq!(entry.cache_read_tokens, 1000);
+    }
+
+    #[test]
+    fn test_parse_skips_non_assistant_lines() {
+        assert!(parse_line(r#"{"type":"user","timestamp":"2026-01-28T14:00:00Z","message":{}}"#).is_none());
+        assert!(parse_line("not json").is_none());
+    }
+}
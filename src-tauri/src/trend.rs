@@ -0,0 +1,479 @@
+// Recent readings kept in memory (the last TREND_HOURS) for the sparkline icon,
+// the burn rate and projection lines, and the History submenu. Loaded from
+// history at startup and appended to on every fetch, so none of them need the
+// database. The charts window's projection lines (`predict_exhaustion`) read
+// history instead, since they cover the Sonnet and Opus limits too, as does
+// `get_burn_rate`.
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local, Timelike};
+use serde::Serialize;
+
+use crate::{
+    format_reset_at, init_db, locale, parse_reading_timestamp, period, query_usage_history, resolve_reset, Aggregation,
+    AppState, MenuMetric, UsageData, UsageHistoryRow, UsageItem,
+};
+
+pub(crate) const TREND_HOURS: i64 = 24;
+// Burn rate windows: the session over the last hour, the weekly limit over a day
+const SESSION_RATE_HOURS: i64 = 1;
+const WEEKLY_RATE_HOURS: i64 = 24;
+// Rates over shorter spans jump around too much to project from
+const MIN_RATE_SPAN_MINS: i64 = 20;
+// Readings listed in the History submenu
+pub(crate) const MENU_READINGS: usize = 8;
+
+pub(crate) type TrendPoint = (DateTime<Local>, i32);
+
+// Oldest first
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Trends {
+    pub(crate) session: Vec<TrendPoint>,
+    pub(crate) weekly: Vec<TrendPoint>,
+    // The last MENU_READINGS readings
+    pub(crate) latest: Vec<UsageData>,
+}
+
+fn push_point(trend: &mut Vec<TrendPoint>, at: DateTime<Local>, percent: i32) {
+    trend.push((at, percent));
+    let cutoff = at - chrono::Duration::hours(TREND_HOURS);
+    trend.retain(|(t, _)| *t >= cutoff);
+}
+
+impl Trends {
+    // Add a reading and drop the ones that have scrolled out of the window
+    pub(crate) fn push(&mut self, at: DateTime<Local>, usage: &UsageData) {
+        if let Some(percent) = usage.session.percent {
+            push_point(&mut self.session, at, percent);
+        }
+        if let Some(percent) = usage.weekly_all.percent {
+            push_point(&mut self.weekly, at, percent);
+        }
+        self.push_latest(usage.clone());
+    }
+
+    fn push_latest(&mut self, usage: UsageData) {
+        self.latest.push(usage);
+        if self.latest.len() > MENU_READINGS {
+            self.latest.remove(0);
+        }
+    }
+
+    // The last TREND_HOURS of readings from history, at startup
+    pub(crate) fn load() -> Self {
+        let cutoff = (Local::now() - chrono::Duration::hours(TREND_HOURS))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let Ok(conn) = init_db() else {
+            return Trends::default();
+        };
+        let mut trends = Trends::default();
+        for row in query_usage_history(&conn, &cutoff, None, Aggregation::Latest) {
+            let Some(at) = parse_reading_timestamp(&row.timestamp) else {
+                continue;
+            };
+            trends.session.extend(row.session_percent.map(|p| (at, p)));
+            trends.weekly.extend(row.weekly_percent.map(|p| (at, p)));
+            let item = |percent| UsageItem { percent, resets: None };
+            trends.push_latest(UsageData {
+                timestamp: Some(row.timestamp),
+                session: item(row.session_percent),
+                weekly_all: item(row.weekly_percent),
+                weekly_sonnet: item(row.sonnet_percent),
+                weekly_opus: item(row.opus_percent),
+                error: None,
+            });
+        }
+        trends
+    }
+
+    pub(crate) fn session_rate(&self) -> Option<f64> {
+        burn_rate(&self.session, SESSION_RATE_HOURS)
+    }
+
+    pub(crate) fn weekly_rate(&self) -> Option<f64> {
+        burn_rate(&self.weekly, WEEKLY_RATE_HOURS)
+    }
+}
+
+// %/hour over the last `window_hours` of readings. Usage only drops at a
+// reset, so the rate is measured from the start of the current window.
+pub(crate) fn burn_rate(trend: &[TrendPoint], window_hours: i64) -> Option<f64> {
+    burn_rate_over(trend, window_hours * 60)
+}
+
+fn burn_rate_over(trend: &[TrendPoint], window_mins: i64) -> Option<f64> {
+    let last = trend.last()?;
+    let cutoff = last.0 - chrono::Duration::minutes(window_mins);
+    let recent: Vec<&TrendPoint> = trend.iter().filter(|(t, _)| *t >= cutoff).collect();
+    let start = recent
+        .windows(2)
+        .rposition(|pair| pair[1].1 < pair[0].1)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let first = recent[start];
+    let minutes = last.0.signed_duration_since(first.0).num_minutes();
+    (minutes >= MIN_RATE_SPAN_MINS).then(|| (last.1 - first.1) as f64 / (minutes as f64 / 60.0))
+}
+
+// When usage reaches 100% going from `percent` at `rate` %/hour
+pub(crate) fn projected_exhaustion(percent: i32, rate: f64, from: DateTime<Local>) -> Option<DateTime<Local>> {
+    (rate > 0.0 && percent < 100)
+        .then(|| from + chrono::Duration::seconds(((100 - percent) as f64 / rate * 3600.0) as i64))
+}
+
+// "~4:30pm" today, "Fri ~2pm" on another day
+pub(crate) fn format_projection_time(at: DateTime<Local>, now: DateTime<Local>) -> String {
+    let time = if at.minute() == 0 {
+        at.format("%-I%P").to_string()
+    } else {
+        at.format("%-I:%M%P").to_string()
+    };
+    if at.date_naive() == now.date_naive() {
+        format!("~{}", time)
+    } else {
+        format!("{} ~{}", at.format("%a"), time)
+    }
+}
+
+// Top-of-menu warning when the weekly limit is projected to run out before it
+// resets (unknown resets don't count)
+pub(crate) fn weekly_warning(
+    percent: i32,
+    rate: Option<f64>,
+    reset_at: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Option<String> {
+    let at = projected_exhaustion(percent, rate?, now)?;
+    (at < reset_at?).then(|| format!("⚠ Weekly budget projected to run out {}", format_projection_time(at, now)))
+}
+
+// History submenu row: "10:40  Session 42% · Weekly 61%", with the date for
+// readings from other days
+pub(crate) fn reading_label(usage: &UsageData, now: DateTime<Local>) -> String {
+    let when = match usage.timestamp.as_deref().and_then(parse_reading_timestamp) {
+        Some(at) if at.date_naive() == now.date_naive() => at.format(locale::clock(false)).to_string(),
+        Some(at) => at.format(&locale::date_clock(false)).to_string(),
+        None => "--:--".to_string(),
+    };
+    let percent = |p: Option<i32>| p.map(|p| format!("{}%", p)).unwrap_or_else(|| "--".to_string());
+    format!(
+        "{}  Session {} · Weekly {}",
+        when,
+        percent(usage.session.percent),
+        percent(usage.weekly_all.percent)
+    )
+}
+
+// Menu line under a metric. None when there aren't enough recent readings or
+// the limit is already hit.
+pub(crate) fn projection_text(
+    percent: i32,
+    rate: Option<f64>,
+    reset_at: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Option<String> {
+    let rate = rate.filter(|_| percent < 100)?;
+    Some(match projected_exhaustion(percent, rate, now) {
+        Some(at) if reset_at.map_or(true, |reset| at < reset) => {
+            let when = format_projection_time(at, now);
+            match reset_at {
+                Some(_) => format!("At this pace: hits 100% {} (before reset)", when),
+                None => format!("At this pace: hits 100% {}", when),
+            }
+        }
+        _ => "At this pace: on track".to_string(),
+    })
+}
+
+// When a metric reaches 100% at the rate over its burn-rate window, with a
+// confidence window from the rates over the whole span and its recent half:
+// `earliest` at the faster, `latest` at the slower. A time past the reset (or
+// a rate that isn't climbing) is None, for "won't run out before reset".
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Projection {
+    pub(crate) rate: f64,
+    pub(crate) at: Option<DateTime<Local>>,
+    pub(crate) earliest: Option<DateTime<Local>>,
+    pub(crate) latest: Option<DateTime<Local>>,
+}
+
+pub(crate) fn project(
+    trend: &[TrendPoint],
+    window_hours: i64,
+    reset_at: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Option<Projection> {
+    let percent = trend.last()?.1;
+    let rate = burn_rate(trend, window_hours)?;
+    let recent = burn_rate_over(trend, window_hours * 30).unwrap_or(rate);
+    let before_reset = |rate: f64| {
+        projected_exhaustion(percent, rate, now).filter(|at| reset_at.map_or(true, |reset| *at < reset))
+    };
+    Some(Projection {
+        rate,
+        at: before_reset(rate),
+        earliest: before_reset(rate.max(recent)),
+        latest: before_reset(rate.min(recent)),
+    })
+}
+
+// One metric's readings from history, plus the current reading when history
+// doesn't have it yet (privacy mode, skipped unchanged readings)
+fn metric_trend(rows: &[UsageHistoryRow], metric: MenuMetric, current: Option<i32>, read_at: DateTime<Local>) -> Vec<TrendPoint> {
+    let mut trend: Vec<TrendPoint> = rows
+        .iter()
+        .filter_map(|row| {
+            let percent = match metric {
+                MenuMetric::Session => row.session_percent,
+                MenuMetric::Weekly => row.weekly_percent,
+                MenuMetric::Sonnet => row.sonnet_percent,
+                MenuMetric::Opus => row.opus_percent,
+            };
+            Some((parse_reading_timestamp(&row.timestamp)?, percent?))
+        })
+        .collect();
+    if let Some(percent) = current.filter(|_| trend.last().map_or(true, |(t, _)| *t < read_at)) {
+        trend.push((read_at, percent));
+    }
+    trend
+}
+
+fn metric_rate_hours(metric: MenuMetric) -> i64 {
+    match metric {
+        MenuMetric::Session => SESSION_RATE_HOURS,
+        _ => WEEKLY_RATE_HOURS,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ExhaustionPrediction {
+    metric: &'static str,
+    percent: Option<i32>,
+    reset_at: Option<String>,
+    // %/hour assumed, None without enough recent readings
+    rate_per_hour: Option<f64>,
+    // None when it won't reach 100% before the reset
+    exhausts_at: Option<String>,
+    earliest: Option<String>,
+    latest: Option<String>,
+}
+
+// Per metric, for the charts window's projection lines
+#[tauri::command]
+pub(crate) fn predict_exhaustion(state: tauri::State<'_, Arc<Mutex<AppState>>>) -> Vec<ExhaustionPrediction> {
+    let usage = state.lock().unwrap().usage.clone();
+    let now = Local::now();
+    let cutoff = (now - chrono::Duration::hours(TREND_HOURS)).format("%Y-%m-%dT%H:%M:%S").to_string();
+    let rows = init_db()
+        .map(|conn| query_usage_history(&conn, &cutoff, None, Aggregation::Latest))
+        .unwrap_or_default();
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    MenuMetric::ALL
+        .into_iter()
+        .map(|metric| {
+            let item = metric.item(&usage);
+            let trend = metric_trend(&rows, metric, item.percent, read_at);
+            let reset_at = resolve_reset(item.resets.as_deref(), read_at);
+            let projection = project(&trend, metric_rate_hours(metric), reset_at, now);
+            let format = |at: Option<DateTime<Local>>| at.map(format_reset_at);
+            ExhaustionPrediction {
+                metric: metric.as_str(),
+                percent: item.percent,
+                reset_at: format(reset_at),
+                rate_per_hour: projection.as_ref().map(|p| p.rate),
+                exhausts_at: projection.as_ref().and_then(|p| format(p.at)),
+                earliest: projection.as_ref().and_then(|p| format(p.earliest)),
+                latest: projection.as_ref().and_then(|p| format(p.latest)),
+            }
+        })
+        .collect()
+}
+
+// Spans `get_burn_rate` measures over. Every span starts after the last
+// reset inside it, so "reset" is the whole window so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BurnWindow {
+    LastHour,
+    LastSixHours,
+    SinceReset,
+}
+
+impl BurnWindow {
+    pub(crate) const ALL: [BurnWindow; 3] = [BurnWindow::LastHour, BurnWindow::LastSixHours, BurnWindow::SinceReset];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            BurnWindow::LastHour => "1h",
+            BurnWindow::LastSixHours => "6h",
+            BurnWindow::SinceReset => "reset",
+        }
+    }
+
+    // Since a reset, back at most the metric's window length
+    fn minutes(self, metric: MenuMetric) -> i64 {
+        match self {
+            BurnWindow::LastHour => 60,
+            BurnWindow::LastSixHours => 360,
+            BurnWindow::SinceReset if metric == MenuMetric::Session => period::session_hours() * 60,
+            BurnWindow::SinceReset => period::weekly_hours() * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct BurnRate {
+    metric: &'static str,
+    window: &'static str,
+    // None without readings spanning MIN_RATE_SPAN_MINS
+    rate_per_hour: Option<f64>,
+}
+
+fn burn_rates(rows: &[UsageHistoryRow], usage: &UsageData, read_at: DateTime<Local>, window: BurnWindow) -> Vec<BurnRate> {
+    MenuMetric::ALL
+        .into_iter()
+        .map(|metric| {
+            let trend = metric_trend(rows, metric, metric.item(usage).percent, read_at);
+            BurnRate {
+                metric: metric.as_str(),
+                window: window.as_str(),
+                rate_per_hour: burn_rate_over(&trend, window.minutes(metric)),
+            }
+        })
+        .collect()
+}
+
+// %/hour per metric over `window`: "1h" (default), "6h", or "reset"
+#[tauri::command]
+pub(crate) fn get_burn_rate(
+    state: tauri::State<'_, Arc<Mutex<AppState>>>,
+    window: Option<String>,
+) -> Result<Vec<BurnRate>, String> {
+    let name = window.unwrap_or_else(|| BurnWindow::LastHour.as_str().to_string());
+    let window = BurnWindow::ALL
+        .into_iter()
+        .find(|w| w.as_str() == name)
+        .ok_or_else(|| format!("Unknown window: {}", name))?;
+    let usage = state.lock().unwrap().usage.clone();
+    let now = Local::now();
+    let longest = MenuMetric::ALL.into_iter().map(|m| window.minutes(m)).max().unwrap_or(60);
+    let cutoff = (now - chrono::Duration::minutes(longest)).format("%Y-%m-%dT%H:%M:%S").to_string();
+    let conn = init_db().map_err(|e| format!("Failed to open database: {}", e))?;
+    let rows = query_usage_history(&conn, &cutoff, None, Aggregation::Latest);
+    let read_at = usage.timestamp.as_deref().and_then(parse_reading_timestamp).unwrap_or(now);
+    Ok(burn_rates(&rows, &usage, read_at, window))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 1, 28, hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_burn_rate_starts_after_reset() {
+        let trend = vec![(at(9, 0), 90), (at(9, 30), 4), (at(10, 0), 10), (at(10, 30), 16)];
+        assert_eq!(burn_rate(&trend, 24), Some(12.0));
+        // Too little time since the reset to say
+        assert_eq!(burn_rate(&trend[..2], 24), None);
+
+        let mut trends = Trends::default();
+        let mut usage = UsageData::default();
+        usage.session.percent = Some(50);
+        trends.push(at(1, 0), &usage);
+        trends.push(at(10, 0), &usage);
+        trends.push(at(10, 0) + chrono::Duration::hours(TREND_HOURS), &usage);
+        assert_eq!(trends.session.len(), 2);
+        assert!(trends.weekly.is_empty());
+        assert_eq!(trends.latest.len(), 3);
+    }
+
+    #[test]
+    fn test_burn_rates_per_window() {
+        let row = |hour: u32, session: Option<i32>, weekly: i32| UsageHistoryRow {
+            timestamp: at(hour, 0).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            session_percent: session,
+            weekly_percent: Some(weekly),
+            sonnet_percent: None,
+            opus_percent: None,
+        };
+        let rows = [row(5, None, 4), row(8, Some(80), 16), row(9, Some(5), 18), row(10, Some(15), 20)];
+        // The 11:00 reading isn't in history yet
+        let mut usage = UsageData::default();
+        usage.session.percent = Some(25);
+        usage.weekly_all.percent = Some(22);
+        let rates = |window| burn_rates(&rows, &usage, at(11, 0), window);
+        let hour = rates(BurnWindow::LastHour);
+        assert_eq!(hour[0].rate_per_hour, Some(10.0));
+        assert_eq!(hour[1].rate_per_hour, Some(2.0));
+        assert_eq!(hour[2].rate_per_hour, None);
+        // The session reset at 9:00, so six hours back is the same as since then
+        let six = rates(BurnWindow::LastSixHours);
+        assert_eq!(six[0].rate_per_hour, Some(10.0));
+        assert_eq!(six[1].rate_per_hour, Some(3.0));
+        assert_eq!(rates(BurnWindow::SinceReset)[1].window, "reset");
+    }
+
+    #[test]
+    fn test_projection_text() {
+        let now = at(14, 0);
+        // 40% left at 16%/hour: 2.5 hours
+        assert_eq!(
+            projection_text(60, Some(16.0), Some(at(18, 0)), now).as_deref(),
+            Some("At this pace: hits 100% ~4:30pm (before reset)")
+        );
+        assert_eq!(
+            projection_text(60, Some(16.0), Some(at(15, 0)), now).as_deref(),
+            Some("At this pace: on track")
+        );
+        assert_eq!(projection_text(60, Some(0.0), None, now).as_deref(), Some("At this pace: on track"));
+        assert_eq!(projection_text(60, None, None, now), None);
+        assert_eq!(format_projection_time(at(14, 0) + chrono::Duration::days(2), now), "Fri ~2pm");
+    }
+
+    #[test]
+    fn test_reading_label() {
+        let mut usage = UsageData {
+            timestamp: Some("2026-01-28T10:40:12.345".to_string()),
+            ..Default::default()
+        };
+        usage.session.percent = Some(42);
+        assert_eq!(reading_label(&usage, at(14, 0)), "10:40  Session 42% · Weekly --");
+        assert_eq!(
+            reading_label(&usage, at(14, 0) + chrono::Duration::days(1)),
+            "Jan 28 10:40  Session 42% · Weekly --"
+        );
+    }
+
+    #[test]
+    fn test_weekly_warning_only_before_reset() {
+        let now = at(14, 0);
+        let reset = Some(now + chrono::Duration::days(4));
+        // 20% left at 0.4%/hour: 50 hours, Friday 4pm
+        assert_eq!(
+            weekly_warning(80, Some(0.4), reset, now).as_deref(),
+            Some("⚠ Weekly budget projected to run out Fri ~4pm")
+        );
+        assert_eq!(weekly_warning(80, Some(0.1), reset, now), None);
+        assert_eq!(weekly_warning(80, Some(0.4), None, now), None);
+    }
+
+    #[test]
+    fn test_projection_window_from_recent_rate() {
+        // 10%/hour over the hour, but 16%/hour over the last half of it
+        let trend = vec![(at(13, 0), 40), (at(13, 30), 42), (at(14, 0), 50)];
+        let projection = project(&trend, 1, Some(at(20, 0)), at(14, 0)).unwrap();
+        assert_eq!(projection.rate, 10.0);
+        assert_eq!(projection.at, Some(at(19, 0)));
+        assert_eq!(projection.earliest, Some(at(17, 7) + chrono::Duration::seconds(30)));
+        assert_eq!(projection.latest, Some(at(19, 0)));
+        // Past the reset
+        let early_reset = project(&trend, 1, Some(at(18, 0)), at(14, 0)).unwrap();
+        assert_eq!(early_reset.at, None);
+        assert!(early_reset.earliest.is_some());
+        assert_eq!(project(&trend[..1], 1, None, at(14, 0)), None);
+    }
+}
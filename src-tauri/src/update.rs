@@ -0,0 +1,75 @@
+// "Check for Updates...": compares the running version with the latest GitHub
+// release and offers to open its download page. (The Tauri updater plugin needs
+// signed bundles and an update manifest, which releases don't publish yet.)
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+use crate::locale::{text, tr, Msg};
+use crate::{confirm, open_file, show_message};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/panozzaj/cc-usage/releases/latest";
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+// "v1.2.3" or "1.2.3-beta.1" -> (1, 2, 3); missing parts count as 0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().trim_start_matches('v').split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+pub(crate) fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+fn fetch_latest_release() -> Result<Release, String> {
+    let out = Command::new("curl")
+        .args(["-fsSL", "--max-time", "15", "-H", "Accept: application/vnd.github+json", LATEST_RELEASE_URL])
+        .output()
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&out.stdout).map_err(|e| format!("Unexpected response: {}", e))
+}
+
+// Blocks on the network and dialogs, so call off the main thread
+pub(crate) fn check_with_dialog() -> Result<(), String> {
+    let release = fetch_latest_release()?;
+    let current = env!("CARGO_PKG_VERSION");
+    if is_newer(&release.tag_name, current) {
+        let question = tr(Msg::UpdateAvailable, &[&release.tag_name.trim_start_matches('v'), &current]);
+        if confirm(&question, text(Msg::OpenDownloadPage)) {
+            // open(1) and xdg-open take URLs too
+            open_file(Path::new(&release.html_url));
+        }
+    } else {
+        show_message(text(Msg::UpdatesTitle), &tr(Msg::UpToDate, &[&current]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer_compares_numerically() {
+        assert!(is_newer("v0.10.0", "0.9.1"));
+        assert!(is_newer("1.0", "0.1.0"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta.2", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+}
@@ -0,0 +1,88 @@
+// Snapshot upload for self-hosted dashboards and archives, off unless
+// `upload_url` is set. After every fetch, snapshot.rs's JSON goes up with an
+// HTTP PUT: to any endpoint that takes one, or, with
+// `upload_access_key_id` / `upload_secret_access_key`, to an S3-compatible
+// bucket with the request signed (AWS Signature V4, which curl does itself
+// given the region, `upload_region`, us-east-1 by default). `{timestamp}` in
+// the URL becomes the reading's time, so each reading lands in its own object
+// for an archive; without it the one object is overwritten. curl reads its
+// options from stdin so neither the keys nor the body show up in `ps`. Not in
+// privacy mode.
+use crate::curl::{self, config_line};
+use crate::{append_log, privacy_mode, snapshot, AppState, Settings};
+
+const DEFAULT_REGION: &str = "us-east-1";
+pub(crate) const TIMESTAMP_PLACEHOLDER: &str = "{timestamp}";
+
+// "2026-01-28T14:40:00" -> "20260128T144000", safe in any object key
+pub(crate) fn object_url(url: &str, timestamp: Option<&str>) -> String {
+    let stamp: String = timestamp
+        .unwrap_or("unknown")
+        .chars()
+        .take(19)
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    url.replace(TIMESTAMP_PLACEHOLDER, &stamp)
+}
+
+pub(crate) fn curl_config(settings: &Settings, url: &str, body: &str) -> String {
+    let mut config = curl::base_config("PUT", 20);
+    config.push_str(&config_line("header", "Content-Type: application/json"));
+    if let (Some(key), Some(secret)) = (&settings.upload_access_key_id, &settings.upload_secret_access_key) {
+        let region = settings.upload_region.as_deref().unwrap_or(DEFAULT_REGION);
+        config.push_str(&config_line("aws-sigv4", &format!("aws:amz:{}:s3", region)));
+        config.push_str(&config_line("user", &format!("{}:{}", key, secret)));
+    }
+    config.push_str(&config_line("data-binary", body));
+    config.push_str(&config_line("url", url));
+    config
+}
+
+// After every fetch
+pub(crate) fn upload(settings: &Settings, state: &AppState) {
+    let Some(url) = settings.upload_url.as_deref() else {
+        return;
+    };
+    if privacy_mode(settings) {
+        return;
+    }
+    let snapshot = snapshot::snapshot(&state.usage, state.last_error.as_deref(), chrono::Local::now());
+    let Ok(body) = serde_json::to_string(&snapshot) else {
+        return;
+    };
+    let url = object_url(url, state.usage.timestamp.as_deref());
+    let config = curl_config(settings, &url, &body);
+    std::thread::spawn(move || {
+        if let Err(e) = curl::run(&config) {
+            append_log(&format!("Snapshot upload to {} failed: {}", url, e));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curl_config() {
+        let url = object_url("https://s3.example.com/b/cc-usage/{timestamp}.json", Some("2026-01-28T14:40:00"));
+        assert_eq!(url, "https://s3.example.com/b/cc-usage/20260128T144000.json");
+        assert_eq!(object_url("https://x/latest.json", None), "https://x/latest.json");
+
+        let plain = curl_config(&Settings::default(), "https://x/latest.json", r#"{"a":"b\"c"}"#);
+        assert!(plain.contains("request = PUT\n"));
+        assert!(plain.contains("data-binary = \"{\\\"a\\\":\\\"b\\\\\\\"c\\\"}\"\n"));
+        assert!(plain.ends_with("url = \"https://x/latest.json\"\n"));
+        assert!(!plain.contains("aws-sigv4"));
+
+        let settings = Settings {
+            upload_access_key_id: Some("AKID".to_string()),
+            upload_secret_access_key: Some("secret".to_string()),
+            upload_region: Some("auto".to_string()),
+            ..Default::default()
+        };
+        let signed = curl_config(&settings, &url, "{}");
+        assert!(signed.contains("aws-sigv4 = \"aws:amz:auto:s3\"\n"));
+        assert!(signed.contains("user = \"AKID:secret\"\n"));
+    }
+}
@@ -0,0 +1,178 @@
+// Per-reading change since the previous reading (Δ%) and usage velocity
+// (%/hour), stored with each row so burn-rate queries don't diff raw readings
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::parse_reading_timestamp;
+
+const METRICS: [&str; 3] = ["session", "weekly", "sonnet"];
+
+// Usage only drops within a window when it resets, so a drop means the new
+// window started from zero
+pub(crate) fn reading_delta(previous: Option<i32>, current: Option<i32>) -> Option<i32> {
+    match (previous, current) {
+        (Some(prev), Some(cur)) if cur < prev => Some(cur),
+        (Some(prev), Some(cur)) => Some(cur - prev),
+        _ => None,
+    }
+}
+
+pub(crate) fn velocity(delta: Option<i32>, hours: f64) -> Option<f64> {
+    let delta = delta?;
+    (hours > 0.0).then(|| (delta as f64 / hours * 100.0).round() / 100.0)
+}
+
+type Percents = [Option<i32>; 3];
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<(i64, String, Percents)> {
+    Ok((row.get(0)?, row.get(1)?, [row.get(2)?, row.get(3)?, row.get(4)?]))
+}
+
+fn write_deltas(
+    conn: &Connection,
+    id: i64,
+    current: (&str, &Percents),
+    previous: Option<(&str, &Percents)>,
+) -> Result<(), rusqlite::Error> {
+    let (timestamp, percents) = current;
+    let hours = previous
+        .and_then(|(prev_ts, _)| Some((parse_reading_timestamp(timestamp)?, parse_reading_timestamp(prev_ts)?)))
+        .map(|(cur, prev)| cur.signed_duration_since(prev).num_seconds() as f64 / 3600.0)
+        .unwrap_or(0.0);
+    let deltas: Vec<Option<i32>> = (0..METRICS.len())
+        .map(|i| reading_delta(previous.and_then(|(_, p)| p[i]), percents[i]))
+        .collect();
+    conn.execute(
+        "UPDATE usage_history
+         SET session_delta = ?2, weekly_delta = ?3, sonnet_delta = ?4,
+             session_velocity = ?5, weekly_velocity = ?6, sonnet_velocity = ?7
+         WHERE id = ?1",
+        params![
+            id,
+            deltas[0],
+            deltas[1],
+            deltas[2],
+            velocity(deltas[0], hours),
+            velocity(deltas[1], hours),
+            velocity(deltas[2], hours),
+        ],
+    )?;
+    Ok(())
+}
+
+const SELECT_READING: &str = "SELECT id, timestamp, session_percent, weekly_percent, sonnet_percent FROM usage_history";
+
+fn recompute(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    let Some((id, timestamp, percents)) = conn
+        .query_row(&format!("{} WHERE id = ?1", SELECT_READING), params![id], read_row)
+        .optional()?
+    else {
+        return Ok(());
+    };
+    let previous = conn
+        .query_row(
+            &format!(
+                "{} WHERE timestamp < ?1 AND profile IS (SELECT profile FROM usage_history WHERE id = ?2)
+                 ORDER BY timestamp DESC LIMIT 1",
+                SELECT_READING
+            ),
+            params![timestamp, id],
+            read_row,
+        )
+        .optional()?;
+    write_deltas(
+        conn,
+        id,
+        (&timestamp, &percents),
+        previous.as_ref().map(|(_, ts, p)| (ts.as_str(), p)),
+    )
+}
+
+// Fill in the new row, and the one after it in case the reading was inserted
+// out of order (imports)
+pub(crate) fn update_around(conn: &Connection, id: i64) -> Result<(), rusqlite::Error> {
+    recompute(conn, id)?;
+    let next: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM usage_history
+             WHERE timestamp > (SELECT timestamp FROM usage_history WHERE id = ?1)
+               AND profile IS (SELECT profile FROM usage_history WHERE id = ?1)
+             ORDER BY timestamp ASC LIMIT 1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match next {
+        Some(next) => recompute(conn, next),
+        None => Ok(()),
+    }
+}
+
+// Every row in timestamp order, for the migration that adds the columns
+pub(crate) fn backfill(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let rows: Vec<(i64, String, Percents)> = conn
+        .prepare(&format!("{} ORDER BY timestamp ASC", SELECT_READING))?
+        .query_map([], read_row)?
+        .collect::<Result<_, _>>()?;
+    let mut previous: Option<(&str, &Percents)> = None;
+    for (id, timestamp, percents) in &rows {
+        write_deltas(conn, *id, (timestamp, percents), previous)?;
+        previous = Some((timestamp, percents));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{insert_usage, UsageData, UsageItem};
+    use std::fs;
+
+    fn reading(timestamp: &str, session: i32) -> UsageData {
+        UsageData {
+            timestamp: Some(timestamp.to_string()),
+            session: UsageItem {
+                percent: Some(session),
+                resets: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn deltas(conn: &Connection) -> Vec<(Option<i32>, Option<f64>)> {
+        conn.prepare("SELECT session_delta, session_velocity FROM usage_history ORDER BY timestamp")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reading_delta_treats_drop_as_reset() {
+        assert_eq!(reading_delta(Some(40), Some(46)), Some(6));
+        assert_eq!(reading_delta(Some(95), Some(3)), Some(3));
+        assert_eq!(reading_delta(None, Some(3)), None);
+        assert_eq!(velocity(Some(6), 0.5), Some(12.0));
+        assert_eq!(velocity(Some(6), 0.0), None);
+    }
+
+    #[test]
+    fn test_deltas_on_insert_including_out_of_order() {
+        let db_path = std::env::temp_dir().join(format!("test_db_velocity_{}.db", std::process::id()));
+        let _ = fs::remove_file(&db_path);
+        let conn = crate::init_test_db(&db_path).unwrap();
+
+        insert_usage(&conn, &reading("2026-01-28T10:00:00", 10)).unwrap();
+        insert_usage(&conn, &reading("2026-01-28T11:00:00", 30)).unwrap();
+        // Lands between the two existing readings
+        insert_usage(&conn, &reading("2026-01-28T10:30:00", 16)).unwrap();
+
+        assert_eq!(
+            deltas(&conn),
+            vec![(None, None), (Some(6), Some(12.0)), (Some(14), Some(28.0))]
+        );
+
+        drop(conn);
+        let _ = fs::remove_file(&db_path);
+    }
+}
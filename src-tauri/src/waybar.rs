@@ -0,0 +1,62 @@
+// `cc-usage waybar`: the cached reading as a waybar custom module's JSON line
+// (`"return-type": "json"`), also readable by polybar scripts. `text` is the
+// session and weekly percentages, `tooltip` the menu's metric lines, and
+// `class` the worst pace color ("green" through "red", or "none" without a
+// reading) for styling in the bar's CSS. `percentage` is the session's, for
+// format-icons.
+use serde::Serialize;
+
+use crate::cli;
+use crate::{indicator_color, load_cached_usage, worst_pace_indicator, MenuMetric, TrayMetric, UsageData};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct Status {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+    percentage: Option<i32>,
+}
+
+pub(crate) fn status(usage: &UsageData) -> Status {
+    let Some(session) = usage.session.percent else {
+        return Status {
+            text: "--".to_string(),
+            tooltip: "No reading yet. Start cc-usage to fetch one.".to_string(),
+            class: "none",
+            percentage: None,
+        };
+    };
+    let class = indicator_color(worst_pace_indicator(usage, TrayMetric::Both, &MenuMetric::ALL));
+    Status {
+        text: format!("{}% {}%", session, usage.weekly_all.percent.unwrap_or(0)),
+        tooltip: cli::metric_lines(usage).join("\n"),
+        class,
+        percentage: Some(session),
+    }
+}
+
+pub(crate) fn print() -> i32 {
+    let status = status(&load_cached_usage().unwrap_or_default());
+    println!("{}", serde_json::to_string(&status).unwrap_or_default());
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(status(&UsageData::default()).class, "none");
+        let mut usage = UsageData::default();
+        usage.session.percent = Some(95);
+        usage.weekly_all.percent = Some(10);
+        let status = status(&usage);
+        assert_eq!(status.text, "95% 10%");
+        // 90%+ is red whatever the pace
+        assert_eq!(status.class, "red");
+        assert_eq!(status.tooltip.lines().count(), 2);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["percentage"], 95);
+    }
+}
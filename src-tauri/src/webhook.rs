@@ -0,0 +1,117 @@
+// Webhook for automations (n8n, Zapier, scripts): a JSON POST to `webhook_url`
+// when usage passes an alert threshold or fetches keep failing. The body is a
+// fixed JSON object unless `webhook_template` gives one with `{{placeholders}}`.
+use serde_json::json;
+
+use crate::{alerts::Crossing, append_log, curl, Settings, UsageData};
+
+// Consecutive failed fetches before the webhook hears about it (once per streak)
+pub(crate) const ERROR_STREAK: u32 = 3;
+
+pub(crate) enum WebhookEvent<'a> {
+    Threshold(&'a Crossing),
+    FetchErrors { count: u32, error: &'a str },
+}
+
+// Placeholder values for the template, also the default payload's fields
+fn fields(event: &WebhookEvent, usage: &UsageData, timestamp: &str) -> Vec<(&'static str, serde_json::Value)> {
+    let mut fields = vec![
+        ("timestamp", json!(timestamp)),
+        ("session_percent", json!(usage.session.percent)),
+        ("weekly_percent", json!(usage.weekly_all.percent)),
+    ];
+    match event {
+        WebhookEvent::Threshold(crossing) => fields.extend([
+            ("event", json!("threshold")),
+            ("metric", json!(crossing.metric.as_str())),
+            ("threshold", json!(crossing.threshold)),
+            ("percent", json!(crossing.percent)),
+        ]),
+        WebhookEvent::FetchErrors { count, error } => fields.extend([
+            ("event", json!("fetch_errors")),
+            ("count", json!(count)),
+            ("error", json!(error)),
+        ]),
+    }
+    fields
+}
+
+// `{{name}}` -> the value as JSON, with strings left unquoted so they can sit
+// inside the template's own quotes: {"text": "{{metric}} at {{percent}}%"}
+fn render_template(template: &str, fields: &[(&str, serde_json::Value)]) -> String {
+    fields.iter().fold(template.to_string(), |out, (name, value)| {
+        let text = match value {
+            serde_json::Value::String(s) => {
+                let quoted = serde_json::to_string(s).unwrap_or_default();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            other => other.to_string(),
+        };
+        out.replace(&format!("{{{{{}}}}}", name), &text)
+    })
+}
+
+pub(crate) fn payload(event: &WebhookEvent, usage: &UsageData, template: Option<&str>, timestamp: &str) -> String {
+    let fields = fields(event, usage, timestamp);
+    match template {
+        Some(template) => render_template(template, &fields),
+        None => serde_json::Value::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect()).to_string(),
+    }
+}
+
+// Fire and forget
+pub(crate) fn send(settings: &Settings, event: WebhookEvent, usage: &UsageData) {
+    let Some(url) = settings.webhook_url.clone() else {
+        return;
+    };
+    let timestamp = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let body = payload(&event, usage, settings.webhook_template.as_deref(), &timestamp);
+    std::thread::spawn(move || {
+        if let Err(e) = curl::post_json(&url, &body) {
+            append_log(&format!("Webhook failed: {}", e));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MenuMetric, UsageItem};
+
+    #[test]
+    fn test_payload_default_and_template() {
+        let usage = UsageData {
+            session: UsageItem {
+                percent: Some(87),
+                resets: None,
+            },
+            ..Default::default()
+        };
+        let crossing = Crossing {
+            metric: MenuMetric::Session,
+            threshold: 85,
+            percent: 87,
+        };
+        let event = WebhookEvent::Threshold(&crossing);
+        let body: serde_json::Value =
+            serde_json::from_str(&payload(&event, &usage, None, "2026-01-28T10:40:00")).unwrap();
+        assert_eq!(body["event"], "threshold");
+        assert_eq!(body["metric"], "session");
+        assert_eq!(body["threshold"], 85);
+        assert_eq!(body["weekly_percent"], serde_json::Value::Null);
+
+        let template = r#"{"text": "{{metric}} at {{percent}}%", "n": {{threshold}}}"#;
+        assert_eq!(
+            payload(&event, &usage, Some(template), "2026-01-28T10:40:00"),
+            r#"{"text": "session at 87%", "n": 85}"#
+        );
+        let errors = WebhookEvent::FetchErrors {
+            count: 3,
+            error: "Timed out \"waiting\"",
+        };
+        assert_eq!(
+            payload(&errors, &usage, Some(r#"{"e": "{{error}}"}"#), "x"),
+            r#"{"e": "Timed out \"waiting\""}"#
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// The charts window's size and position, kept across opens and restarts in
+// cc-usage-window.json. Tracked in memory as it moves and written when the
+// window goes away. Coordinates are physical pixels in the desktop's global
+// space, so the saved position also says which display it was on; if that
+// display is gone, the window opens at the default place instead.
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{PhysicalPosition, PhysicalSize, Runtime, WebviewWindow, Window};
+
+use crate::datadir;
+
+pub(crate) const WINDOW_STATE_FILE: &str = "cc-usage-window.json";
+
+// Windows whose geometry is kept
+const TRACKED: [&str; 1] = ["usage"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+// The latest geometry per window label, once it has moved or resized
+static CURRENT: Mutex<Option<HashMap<String, Geometry>>> = Mutex::new(None);
+
+fn load() -> HashMap<String, Geometry> {
+    fs::read_to_string(datadir::data_dir().join(WINDOW_STATE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(states: &HashMap<String, Geometry>) {
+    let path = datadir::data_dir().join(WINDOW_STATE_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(states) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// Whether the window's top-left corner lands on one of the displays
+// (as (x, y, width, height))
+fn on_a_display(geometry: Geometry, displays: &[(i32, i32, u32, u32)]) -> bool {
+    displays.iter().any(|&(x, y, width, height)| {
+        geometry.x >= x && geometry.y >= y && geometry.x < x + width as i32 && geometry.y < y + height as i32
+    })
+}
+
+// Put a freshly built window back where it was
+pub(crate) fn restore<R: Runtime>(window: &WebviewWindow<R>) {
+    let Some(geometry) = load().get(window.label()).copied() else {
+        return;
+    };
+    let displays: Vec<(i32, i32, u32, u32)> = window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| (m.position().x, m.position().y, m.size().width, m.size().height))
+        .collect();
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    if on_a_display(geometry, &displays) {
+        let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    }
+}
+
+// From the window event handler: note moves and resizes, and write the file
+// once the window is gone
+pub(crate) fn track<R: Runtime>(window: &Window<R>, event: &tauri::WindowEvent) {
+    if !TRACKED.contains(&window.label()) {
+        return;
+    }
+    let mut current = CURRENT.lock().unwrap();
+    let states = current.get_or_insert_with(load);
+    match event {
+        tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
+            if window.is_minimized().unwrap_or(false) {
+                return;
+            }
+            if let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) {
+                let geometry = Geometry {
+                    x: position.x,
+                    y: position.y,
+                    width: size.width,
+                    height: size.height,
+                };
+                states.insert(window.label().to_string(), geometry);
+            }
+        }
+        tauri::WindowEvent::Destroyed => save(states),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_must_land_on_a_display() {
+        let displays = [(0, 0, 2560, 1440), (2560, -200, 1920, 1080)];
+        let at = |x, y| Geometry {
+            x,
+            y,
+            width: 700,
+            height: 700,
+        };
+        assert!(on_a_display(at(100, 100), &displays));
+        // On the second display, above the first one's top edge
+        assert!(on_a_display(at(3000, -100), &displays));
+        // The second display was unplugged
+        assert!(!on_a_display(at(3000, -100), &displays[..1]));
+        assert!(!on_a_display(at(-50, 100), &displays));
+    }
+}
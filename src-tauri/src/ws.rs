@@ -0,0 +1,201 @@
+// WebSocket push on the local server's `/ws`, for dashboards that should
+// update the moment a fetch lands (an OBS overlay, a wall display) instead of
+// polling `/metrics`. Each message is one JSON text frame:
+// `{"event": "reading" | "error", "snapshot": {...}}` after every fetch, the
+// snapshot being snapshot.rs's versioned one, plus `error` and
+// `consecutive_errors` for a failed fetch. A client gets `"event": "hello"`
+// with the current snapshot as soon as it connects. Messages only go out;
+// anything a client sends is ignored, and a client that can't be written to
+// is dropped. Browsers let any page open a WebSocket, so an upgrade that
+// carries an Origin is refused unless it's in `server_allowed_origins`;
+// non-browser clients send none. Like the server, no crate: the handshake
+// needs SHA-1 and base64, both short.
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{snapshot, AppState};
+
+// From RFC 6455, appended to the client's key for the accept hash
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+// A client this slow to take a frame is dropped rather than holding up the rest
+const WRITE_TIMEOUT_SECS: u64 = 2;
+
+static CLIENTS: Mutex<Vec<TcpStream>> = Mutex::new(Vec::new());
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+    let mut out = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn accept_key(client_key: &str) -> String {
+    base64(&sha1(format!("{}{}", client_key.trim(), HANDSHAKE_GUID).as_bytes()))
+}
+
+// The client's Sec-WebSocket-Key, if the request head asks for an upgrade
+pub(crate) fn upgrade_key(head: &str) -> Option<&str> {
+    let header = |name: &str| {
+        head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    };
+    header("Upgrade")
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+        .then(|| header("Sec-WebSocket-Key"))
+        .flatten()
+}
+
+// No Origin (a script or native client), or one the settings allow
+pub(crate) fn origin_allowed(origin: Option<&str>, allowed: &[String]) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let origin = origin.trim_end_matches('/');
+    allowed.iter().any(|a| a.trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+// An unmasked text frame, as servers send them
+fn text_frame(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = vec![0x81];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+pub(crate) fn message(state: &AppState, event: &str) -> String {
+    let snapshot = snapshot::snapshot(&state.usage, state.last_error.as_deref(), chrono::Local::now());
+    let mut message = json!({"event": event, "snapshot": snapshot});
+    if event == "error" {
+        message["error"] = json!(state.last_error);
+        message["consecutive_errors"] = json!(state.consecutive_errors);
+    }
+    message.to_string()
+}
+
+// Finish the handshake and keep the connection for broadcasts
+pub(crate) fn accept(mut stream: TcpStream, client_key: &str, hello: &str) {
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(WRITE_TIMEOUT_SECS)));
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(client_key)
+    );
+    if stream.write_all(response.as_bytes()).is_ok() && stream.write_all(&text_frame(hello)).is_ok() {
+        CLIENTS.lock().unwrap().push(stream);
+    }
+}
+
+// After every fetch
+pub(crate) fn broadcast(state: &AppState) {
+    if CLIENTS.lock().unwrap().is_empty() {
+        return;
+    }
+    let event = if state.last_error.is_some() { "error" } else { "reading" };
+    let frame = text_frame(&message(state, event));
+    std::thread::spawn(move || {
+        CLIENTS.lock().unwrap().retain_mut(|client| client.write_all(&frame).is_ok());
+    });
+}
+
+// When the server stops
+pub(crate) fn disconnect_all() {
+    for client in CLIENTS.lock().unwrap().drain(..) {
+        let _ = client.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_and_frames() {
+        // RFC 6455's example
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+        let head = "GET /ws HTTP/1.1\r\nHost: x\r\nupgrade: WebSocket\r\nSec-WebSocket-Key: abc==\r\n\r\n";
+        assert_eq!(upgrade_key(head), Some("abc=="));
+        assert_eq!(upgrade_key("GET /ws HTTP/1.1\r\nSec-WebSocket-Key: abc==\r\n\r\n"), None);
+        assert_eq!(text_frame("hi"), [0x81, 2, b'h', b'i']);
+        let long = text_frame(&"x".repeat(300));
+        assert_eq!(&long[..4], [0x81, 126, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn test_origin_allow_list() {
+        let allowed = vec!["http://localhost:3000/".to_string()];
+        assert!(origin_allowed(None, &[]));
+        assert!(origin_allowed(Some("http://localhost:3000"), &allowed));
+        assert!(!origin_allowed(Some("https://example.com"), &allowed));
+        assert!(!origin_allowed(Some("http://localhost:3000"), &[]));
+    }
+}